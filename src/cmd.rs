@@ -13,23 +13,47 @@
 // limitations under the License.
 
 mod account;
+mod addr;
 mod admin;
+mod agent;
 mod bench;
 mod cldi;
 mod context;
 mod ethabi;
 mod evm;
+mod relay;
 mod rpc;
+mod serve;
 mod watch;
 
 use anyhow::{bail, Result};
 use clap::builder::{IntoResettable, Str, StyledStr};
-use clap::{Arg, ArgMatches, ColorChoice};
+use clap::{Arg, ArgGroup, ArgMatches, ColorChoice};
 use std::collections::HashMap;
 use std::ffi::OsString;
 
 pub use self::cldi::cldi_cmd;
 
+/// Returned as an error by handlers whose outcome should be told by the exit status,
+/// e.g. a verification that is not fully successful. Status `1` is left for other errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, thiserror::Error)]
+#[error("exit with status `{0}`")]
+pub struct ExitStatus(pub i32);
+
+impl ExitStatus {
+    pub const SUCCESS: Self = Self(0);
+    pub const PARTIAL_SUCCESS: Self = Self(2);
+    pub const FAILURE: Self = Self(3);
+
+    pub fn into_result(self) -> Result<()> {
+        if self == Self::SUCCESS {
+            Ok(())
+        } else {
+            Err(self.into())
+        }
+    }
+}
+
 type HandleFn<'help, Ctx> =
     dyn Fn(&Command<'help, Ctx>, &ArgMatches, &mut Ctx) -> Result<()> + 'help;
 pub struct Command<'help, Ctx: 'help> {
@@ -106,6 +130,20 @@ impl<'help, Ctx: 'help> Command<'help, Ctx> {
         self
     }
 
+    pub fn args<I, A>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<Arg>,
+    {
+        self.cmd = self.cmd.args(args);
+        self
+    }
+
+    pub fn group<G: Into<ArgGroup>>(mut self, group: G) -> Self {
+        self.cmd = self.cmd.group(group);
+        self
+    }
+
     pub fn handler<H>(mut self, handler: H) -> Self
     where
         H: Fn(&Self, &ArgMatches, &mut Ctx) -> Result<()> + 'help,
@@ -12,8 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::anyhow;
-use clap::{Arg, ArgAction};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context as _, Result};
+use cita_cloud_proto::evm::{block_number::Lable, BlockNumber};
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches};
 use serde_json::json;
 
 use crate::{
@@ -21,13 +24,53 @@ use crate::{
     config::CryptoType,
     core::{
         context::Context,
-        wallet::{Account, MaybeLocked, MultiCryptoAccount},
+        evm::EvmBehaviour,
+        remote_signer::{RemoteAccount, RemoteEndpoint},
+        wallet::{Account, MaybeLocked, MultiCryptoAccount, WatchOnlyAccount},
     },
     crypto::{EthCrypto, SmCrypto},
     display::Display,
-    utils::{hex, parse_sk},
+    utils::{hex, parse_sk, prompt_password, read_password_file},
 };
 
+/// Safer ways than `-p` to give a password, which leaks into shell history and `ps`.
+pub fn password_source_args() -> [Arg; 3] {
+    [
+        Arg::new("password-file")
+            .help("Read the password from this file")
+            .long("password-file")
+            .value_parser(clap::value_parser!(PathBuf))
+            .conflicts_with_all(["password", "password-env", "ask-password"]),
+        Arg::new("password-env")
+            .help("Read the password from this env var")
+            .long("password-env")
+            .conflicts_with_all(["password", "ask-password"]),
+        Arg::new("ask-password")
+            .help("Prompt for the password")
+            .long("ask-password")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("password"),
+    ]
+}
+
+/// Get the password from `-p` or the [`password_source_args`].
+/// `confirm` asks twice when prompting for a new password.
+pub fn get_password(m: &ArgMatches, confirm: bool) -> Result<Option<String>> {
+    if let Some(pw) = m.get_one::<String>("password") {
+        Ok(Some(pw.clone()))
+    } else if let Some(path) = m.get_one::<PathBuf>("password-file") {
+        read_password_file(path).map(Some)
+    } else if let Some(env) = m.get_one::<String>("password-env") {
+        std::env::var(env)
+            .with_context(|| format!("cannot read password from env `{env}`"))
+            .map(Some)
+    } else if m.get_flag("ask-password") {
+        prompt_password("Password: ", confirm).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
 pub fn generate_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
     Command::<Context<Co, Ex, Ev>>::new("generate-account")
         .about("generate a new account")
@@ -42,6 +85,7 @@ pub fn generate_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, E
                 .long("password")
                 .help("The password to encrypt the account")
         )
+        .args(password_source_args())
         .arg(
             Arg::new("crypto-type")
                 .help("The crypto type for the generated account. [default: <current-context-crypto-type>]")
@@ -51,7 +95,8 @@ pub fn generate_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, E
         )
         .handler(|_cmd, m, ctx| {
             let name = m.get_one::<String>("name");
-            let pw = m.get_one::<String>("password").map(|s| s.as_bytes());
+            let pw = get_password(m, true)?;
+            let pw = pw.as_ref().map(|s| s.as_bytes());
             let crypto_type = m.get_one::<String>("crypto-type")
                 .map(|s| s.parse::<CryptoType>().unwrap())
                 .unwrap_or(ctx.current_setting.crypto_type);
@@ -81,11 +126,21 @@ pub fn generate_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, E
         })
 }
 
-pub fn list_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+pub fn list_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Ev: EvmBehaviour,
+{
     Command::<Context<Co, Ex, Ev>>::new("list")
         .about("list accounts")
-        .handler(|_cmd, _m, ctx| {
-            let accounts = ctx
+        .arg(
+            Arg::new("balance")
+                .help("Also fetch the pending balance of each account")
+                .short('b')
+                .long("balance")
+                .action(ArgAction::SetTrue),
+        )
+        .handler(|_cmd, m, ctx| {
+            let mut accounts = ctx
                 .wallet
                 .list()
                 .map(|(name, account)| {
@@ -94,11 +149,34 @@ pub fn list_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
                         "address": hex(account.address()),
                         "pubkey": hex(account.public_key()),
                         "is_locked": account.is_locked(),
+                        "is_remote": account.is_remote(),
+                        "is_watch_only": account.is_watch_only(),
                         "crypto_type": account.crypto_type(),
                     })
                 })
                 .collect::<Vec<_>>();
 
+            if m.get_flag("balance") {
+                let addrs = ctx
+                    .wallet
+                    .list()
+                    .map(|(_, account)| *account.address())
+                    .collect::<Vec<_>>();
+                let balances = ctx.rt.block_on(async {
+                    let mut balances = Vec::with_capacity(addrs.len());
+                    for addr in addrs {
+                        let pending = BlockNumber {
+                            lable: Some(Lable::Tag("pending".to_string())),
+                        };
+                        balances.push(ctx.evm.get_balance(addr, pending).await?);
+                    }
+                    anyhow::Ok(balances)
+                })??;
+                for (account, balance) in accounts.iter_mut().zip(balances) {
+                    account["balance"] = balance.to_json();
+                }
+            }
+
             let output = serde_json::to_string_pretty(&accounts)?;
             println!("{output}");
 
@@ -125,6 +203,7 @@ pub fn import_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>
                 .short('p')
                 .long("password")
         )
+        .args(password_source_args())
         .arg(
             Arg::new("crypto-type")
                 .help("The crypto type for the imported account. [default: <current-context-crypto-type>]")
@@ -134,7 +213,8 @@ pub fn import_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>
         )
         .handler(|_cmd, m, ctx| {
             let name = m.get_one::<String>("name");
-            let pw = m.get_one::<String>("password").map(|s| s.as_bytes());
+            let pw = get_password(m, true)?;
+            let pw = pw.as_ref().map(|s| s.as_bytes());
             let sk = m.get_one::<String>("secret-key").unwrap();
             let crypto_type = m.get_one::<String>("crypto-type")
                 .map(|s| s.parse::<CryptoType>().unwrap())
@@ -175,6 +255,101 @@ pub fn import_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>
         })
 }
 
+pub fn import_remote_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("import-remote")
+        .about("import an account signed by an external signer, e.g. an HSM")
+        .arg(
+            Arg::new("name")
+                .help("The name of the account, default to account address")
+                .long("name"),
+        )
+        .arg(
+            Arg::new("command")
+                .help("The signer command, run by `sh -c` with a JSON request on stdin")
+                .long("command"),
+        )
+        .arg(
+            Arg::new("url")
+                .help(
+                    "The signer endpoint to POST JSON requests to, e.g. http://127.0.0.1:8080/sign",
+                )
+                .long("url"),
+        )
+        .group(
+            ArgGroup::new("signer")
+                .args(["command", "url"])
+                .required(true),
+        )
+        .arg(
+            Arg::new("crypto-type")
+                .help("The crypto type of the signer. [default: <current-context-crypto-type>]")
+                .long("crypto")
+                .value_parser(["SM", "ETH"])
+                .ignore_case(true),
+        )
+        .handler(|_cmd, m, ctx| {
+            let endpoint = match m.get_one::<String>("command") {
+                Some(command) => RemoteEndpoint::Command(command.clone()),
+                None => RemoteEndpoint::Http(m.get_one::<String>("url").unwrap().clone()),
+            };
+            let crypto_type = m
+                .get_one::<String>("crypto-type")
+                .map(|s| s.parse::<CryptoType>().unwrap())
+                .unwrap_or(ctx.current_setting.crypto_type);
+            let account = RemoteAccount::connect(endpoint, crypto_type)?;
+
+            let addr = hex(account.address());
+            let name = m.get_one::<String>("name").unwrap_or(&addr).clone();
+            ctx.wallet.save(name, account)?;
+
+            println!("{}", json!({ "address": addr }).display());
+            Ok(())
+        })
+}
+
+pub fn watch_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("watch")
+        .about("add a watch-only account, which can be used for queries but not for signing")
+        .arg(
+            Arg::new("addr")
+                .help("The address of the account")
+                .required(true),
+        )
+        .arg(
+            Arg::new("name")
+                .help("The name of the account, default to account address")
+                .long("name"),
+        )
+        .arg(
+            Arg::new("pubkey")
+                .help("The public key of the account, checked against the address")
+                .long("pubkey"),
+        )
+        .arg(
+            Arg::new("crypto-type")
+                .help("The crypto type of the account. [default: <current-context-crypto-type>]")
+                .long("crypto")
+                .value_parser(["SM", "ETH"])
+                .ignore_case(true),
+        )
+        .handler(|_cmd, m, ctx| {
+            let addr = ctx.resolve_addr(m.get_one::<String>("addr").unwrap())?;
+            let crypto_type = m
+                .get_one::<String>("crypto-type")
+                .map(|s| s.parse::<CryptoType>().unwrap())
+                .unwrap_or(ctx.current_setting.crypto_type);
+            let pubkey = m.get_one::<String>("pubkey").map(String::as_str);
+            let account = WatchOnlyAccount::new(addr, pubkey, crypto_type)?;
+
+            let addr = hex(&addr);
+            let name = m.get_one::<String>("name").unwrap_or(&addr).clone();
+            ctx.wallet.save(name, account)?;
+
+            println!("{}", json!({ "address": addr }).display());
+            Ok(())
+        })
+}
+
 pub fn export_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
     Command::<Context<Co, Ex, Ev>>::new("export")
         .about("export account")
@@ -189,9 +364,11 @@ pub fn export_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>
                 .short('p')
                 .long("password"),
         )
+        .args(password_source_args())
         .handler(|_cmd, m, ctx| {
             let name = m.get_one::<String>("name").unwrap();
-            let pw = m.get_one::<String>("password").map(|s| s.as_bytes());
+            let pw = get_password(m, false)?;
+            let pw = pw.as_ref().map(|s| s.as_bytes());
 
             let maybe_locked = ctx.wallet.get(name)?;
 
@@ -220,19 +397,19 @@ pub fn unlock_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>
         )
         .arg(
             Arg::new("password")
-                .help("The password of the account")
+                .help("The password of the account, prompt for it if no password is given")
                 .short('p')
-                .long("password")
-                .required(true),
+                .long("password"),
         )
+        .args(password_source_args())
         .handler(|_cmd, m, ctx| {
             let name = m.get_one::<String>("name").unwrap();
-            let pw = m
-                .get_one::<String>("password")
-                .map(|s| s.as_bytes())
-                .unwrap();
+            let pw = match get_password(m, false)? {
+                Some(pw) => pw,
+                None => prompt_password(&format!("Password of `{name}`: "), false)?,
+            };
 
-            ctx.wallet.unlock_in_keystore(name, pw)?;
+            ctx.wallet.unlock_in_keystore(name, pw.as_bytes())?;
 
             Ok(())
         })
@@ -248,19 +425,19 @@ pub fn lock_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
         )
         .arg(
             Arg::new("password")
-                .help("The password to lock the account")
+                .help("The password to lock the account, prompt for it if no password is given")
                 .short('p')
-                .long("password")
-                .required(true),
+                .long("password"),
         )
+        .args(password_source_args())
         .handler(|_cmd, m, ctx| {
             let name = m.get_one::<String>("name").unwrap();
-            let pw = m
-                .get_one::<String>("password")
-                .map(|s| s.as_bytes())
-                .unwrap();
+            let pw = match get_password(m, true)? {
+                Some(pw) => pw,
+                None => prompt_password(&format!("New password of `{name}`: "), true)?,
+            };
 
-            ctx.wallet.lock(name, pw)?;
+            ctx.wallet.lock(name, pw.as_bytes())?;
 
             Ok(())
         })
@@ -309,7 +486,10 @@ pub fn delete_account<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>
         })
 }
 
-pub fn account_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+pub fn account_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Ev: EvmBehaviour,
+{
     Command::<Context<Co, Ex, Ev>>::new("account")
         .about("Account commands")
         .subcommand_required_else_help(true)
@@ -319,6 +499,8 @@ pub fn account_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
                 .aliases(["gen", "g", "create"]),
             list_account().name("list").aliases(["ls", "l"]),
             import_account().name("import"),
+            import_remote_account().name("import-remote"),
+            watch_account().name("watch"),
             export_account().name("export"),
             unlock_account().name("unlock"),
             lock_account().name("lock"),
@@ -330,13 +512,15 @@ pub fn account_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
 
 #[cfg(test)]
 mod tests {
+    use cita_cloud_proto::{controller::SystemConfig, evm::Balance};
+
     use crate::cmd::cldi_cmd;
     use crate::core::mock::context;
 
     #[test]
     fn test_account_subcmds() {
         let cldi_cmd = cldi_cmd();
-        let (mut ctx, _temp_dir) = context();
+        let (mut ctx, temp_dir) = context();
 
         // generate
         cldi_cmd
@@ -459,6 +643,35 @@ mod tests {
                 &mut ctx,
             )
             .unwrap();
+        let password_file = temp_dir.path().join("password");
+        std::fs::write(&password_file, "123456\n").unwrap();
+        cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "account",
+                    "export",
+                    "test2",
+                    "--password-file",
+                    password_file.to_str().unwrap(),
+                ],
+                &mut ctx,
+            )
+            .unwrap();
+        std::env::set_var("CLDI_TEST_ACCOUNT_PASSWORD", "123456");
+        cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "account",
+                    "export",
+                    "test3",
+                    "--password-env",
+                    "CLDI_TEST_ACCOUNT_PASSWORD",
+                ],
+                &mut ctx,
+            )
+            .unwrap();
         // unlock
         cldi_cmd
             .exec_from(
@@ -481,4 +694,86 @@ mod tests {
         //    .exec_from(["cldi", "account", "delete", "test1"], &mut ctx)
         //    .unwrap();
     }
+
+    #[test]
+    fn test_watch_only_account() {
+        let cldi_cmd = cldi_cmd();
+        let (mut ctx, _temp_dir) = context();
+
+        ctx.evm
+            .expect_get_balance()
+            .returning(|_addr, _bn| Ok(Balance::default()));
+        ctx.controller
+            .expect_get_system_config()
+            .returning(|| Ok(SystemConfig::default()));
+        ctx.controller
+            .expect_get_block_number()
+            .returning(|_| Ok(100u64));
+
+        cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "account",
+                    "watch",
+                    "0xf587c2fa24d23175e09d36625cfc447a4b4d679b",
+                    "--name",
+                    "watched",
+                ],
+                &mut ctx,
+            )
+            .unwrap();
+        let watched = ctx.wallet.get("watched").unwrap();
+        assert!(watched.is_watch_only());
+        assert!(watched.public_key().is_empty());
+        cldi_cmd
+            .exec_from(["cldi", "account", "list", "--balance"], &mut ctx)
+            .unwrap();
+
+        // Usable for queries.
+        cldi_cmd
+            .exec_from(["cldi", "get", "balance", "watched"], &mut ctx)
+            .unwrap();
+        cldi_cmd
+            .exec_from(["cldi", "-u", "watched", "get", "balance"], &mut ctx)
+            .unwrap();
+
+        // But not for signing, which is refused before the tx is built.
+        ctx.controller.checkpoint();
+        ctx.controller.expect_get_block_number().never();
+        let err = cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "-u",
+                    "watched",
+                    "send",
+                    "0xf587c2fa24d23175e09d36625cfc447a4b4d679b",
+                    "0xabcd",
+                ],
+                &mut ctx,
+            )
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("watch-only"));
+        let err = cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "-u",
+                    "watched",
+                    "admin",
+                    "update-admin",
+                    "0xf587c2fa24d23175e09d36625cfc447a4b4d679b",
+                ],
+                &mut ctx,
+            )
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("watch-only"));
+        assert!(cldi_cmd
+            .exec_from(
+                ["cldi", "account", "lock", "watched", "-p", "123456"],
+                &mut ctx
+            )
+            .is_err());
+    }
 }
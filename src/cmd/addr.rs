@@ -0,0 +1,232 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, ArgAction, ArgMatches};
+use serde_json::json;
+
+use crate::{cmd::Command, core::context::Context, crypto::Address, display::Display, utils::hex};
+
+fn scope_args() -> [Arg; 2] {
+    [
+        Arg::new("context")
+            .help("The context that the name is for. [default: <current-context>]")
+            .long("context")
+            .conflicts_with("global"),
+        Arg::new("global")
+            .help("The name is for all the contexts")
+            .long("global")
+            .action(ArgAction::SetTrue),
+    ]
+}
+
+// `None` for global.
+fn scope_of<'a, Co, Ex, Ev>(
+    m: &'a ArgMatches,
+    ctx: &'a Context<Co, Ex, Ev>,
+) -> anyhow::Result<Option<&'a str>> {
+    if m.get_flag("global") {
+        return Ok(None);
+    }
+    match m.get_one::<String>("context") {
+        Some(context) => {
+            ctx.get_context_setting(context)?;
+            Ok(Some(context.as_str()))
+        }
+        None => Ok(Some(ctx.current_context_name.as_str())),
+    }
+}
+
+pub fn add<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("add-addr")
+        .about("Name an address, use it as `@name` where an address is accepted")
+        .arg(
+            Arg::new("name")
+                .help("The name of the address")
+                .required(true),
+        )
+        .arg(Arg::new("addr").help("The address").required(true))
+        .args(scope_args())
+        .handler(|_cmd, m, ctx| {
+            let name = m.get_one::<String>("name").unwrap();
+            let addr = ctx.resolve_addr(m.get_one::<String>("addr").unwrap())?;
+            let scope = scope_of(m, ctx)?.map(str::to_string);
+
+            let mut book = ctx.address_book.clone();
+            book.insert(scope.as_deref(), name, addr)?;
+            book.save(&ctx.config.data_dir)?;
+            ctx.address_book = book;
+
+            Ok(())
+        })
+}
+
+pub fn list<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("list-addr")
+        .about("List the names usable in current context")
+        .arg(
+            Arg::new("all")
+                .help("List the names of all the contexts")
+                .long("all")
+                .short('a')
+                .action(ArgAction::SetTrue),
+        )
+        .handler(|_cmd, m, ctx| {
+            let book = &ctx.address_book;
+            let entry = |name: &str, addr: &Address, context: Option<&str>| {
+                json!({
+                    "name": format!("@{name}"),
+                    "address": hex(addr),
+                    "context": context,
+                })
+            };
+            let mut names = Vec::new();
+            for (context, context_names) in book.contexts() {
+                if m.get_flag("all") || *context == ctx.current_context_name {
+                    names.extend(
+                        context_names
+                            .iter()
+                            .map(|(name, addr)| entry(name, addr, Some(context.as_str()))),
+                    );
+                }
+            }
+            let current = book.names(&ctx.current_context_name);
+            for (name, addr) in book.global() {
+                // Skip the shadowed ones unless listing all.
+                if m.get_flag("all") || current.get(name.as_str()) == Some(addr) {
+                    names.push(entry(name, addr, None));
+                }
+            }
+            println!("{}", json!(names).display());
+
+            Ok(())
+        })
+}
+
+pub fn remove<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("remove-addr")
+        .about("Remove a name from the address book")
+        .arg(
+            Arg::new("name")
+                .help("The name of the address")
+                .required(true),
+        )
+        .args(scope_args())
+        .handler(|_cmd, m, ctx| {
+            let name = m.get_one::<String>("name").unwrap();
+            let name = name.strip_prefix('@').unwrap_or(name);
+            let scope = scope_of(m, ctx)?.map(str::to_string);
+
+            let mut book = ctx.address_book.clone();
+            book.remove(scope.as_deref(), name)?;
+            book.save(&ctx.config.data_dir)?;
+            ctx.address_book = book;
+
+            Ok(())
+        })
+}
+
+pub fn addr_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("addr")
+        .about("Address book commands, names are given as `@name` where an address is accepted")
+        .subcommand_required_else_help(true)
+        .subcommands([
+            add().name("add"),
+            list().name("list").aliases(["ls", "l"]),
+            remove().name("remove").aliases(["rm"]),
+        ])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::cldi_cmd;
+    use crate::core::mock::context;
+    use crate::utils::parse_addr;
+    use cita_cloud_proto::blockchain::Transaction;
+
+    #[test]
+    fn test_addr_subcmds() {
+        let cldi_cmd = cldi_cmd();
+        let (mut ctx, _temp_dir) = context();
+
+        ctx.config
+            .context_settings
+            .insert("testnet".into(), ctx.current_setting.clone());
+        cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "addr",
+                    "add",
+                    "token",
+                    "0x0102030405060708091011121314151617181920",
+                ],
+                &mut ctx,
+            )
+            .unwrap();
+        cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "addr",
+                    "add",
+                    "token",
+                    "0x2019181716151413121110090807060504030201",
+                    "--context",
+                    "testnet",
+                ],
+                &mut ctx,
+            )
+            .unwrap();
+        cldi_cmd
+            .exec_from(["cldi", "addr", "list", "--all"], &mut ctx)
+            .unwrap();
+
+        // Resolved in the context given by `-c`.
+        let token = parse_addr("0x0102030405060708091011121314151617181920").unwrap();
+        assert_eq!(ctx.resolve_addr("@token").unwrap(), token);
+        cldi_cmd
+            .exec_from(
+                ["cldi", "-c", "testnet", "addr", "add", "t", "@token"],
+                &mut ctx,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.address_book.names("testnet")["t"],
+            parse_addr("0x2019181716151413121110090807060504030201").unwrap()
+        );
+        // And back to current context.
+        assert_eq!(ctx.resolve_addr("@token").unwrap(), token);
+        assert_eq!(ctx.addr_name(&token).as_deref(), Some("@token"));
+        let tx = Transaction {
+            to: token.to_vec(),
+            ..Default::default()
+        };
+        assert!(ctx.display(&tx).contains(r#""to_name": "@token""#));
+
+        // Wallet accounts are accepted by their names.
+        let default = *ctx.wallet.get("default").unwrap().address();
+        assert_eq!(ctx.resolve_addr("default").unwrap(), default);
+        assert_eq!(ctx.addr_name(&default).as_deref(), Some("default"));
+        assert!(ctx.resolve_addr("@default").is_err());
+        assert!(ctx.resolve_addr("nobody").is_err());
+
+        cldi_cmd
+            .exec_from(["cldi", "addr", "rm", "@token"], &mut ctx)
+            .unwrap();
+        assert!(ctx.resolve_addr("@token").is_err());
+        assert!(cldi_cmd
+            .exec_from(["cldi", "addr", "rm", "token"], &mut ctx)
+            .is_err());
+    }
+}
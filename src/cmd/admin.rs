@@ -22,7 +22,6 @@ use crate::utils::{get_block_height_at, parse_data, parse_position, parse_value,
 use crate::{
     cmd::Command,
     core::{admin::AdminBehaviour, context::Context},
-    crypto::Address,
     display::Display,
     utils::{parse_addr, parse_validator_addr},
 };
@@ -36,15 +35,14 @@ where
         .arg(
             Arg::new("admin")
                 .help("the address of the new admin")
-                .required(true)
-                .value_parser(parse_addr),
+                .required(true),
         )
         .handler(|_cmd, m, ctx| {
-            let new_admin_addr = m.get_one::<Address>("admin").unwrap();
-            let old_admin_signer = ctx.current_account()?;
+            let new_admin_addr = ctx.resolve_addr(m.get_one::<String>("admin").unwrap())?;
+            let old_admin_signer = &ctx.current_signer()?;
             let tx_hash = ctx.rt.block_on(async {
                 ctx.controller
-                    .update_admin(old_admin_signer, *new_admin_addr)
+                    .update_admin(old_admin_signer, new_admin_addr)
                     .await
             })??;
             println!("{}", tx_hash.display());
@@ -72,7 +70,7 @@ where
                 .map(|v| v.to_owned())
                 .collect::<Vec<Vec<u8>>>();
 
-            let admin_signer = ctx.current_account()?;
+            let admin_signer = &ctx.current_signer()?;
             let tx_hash = ctx.rt.block_on(async {
                 ctx.controller.update_validators(admin_signer, &validators).await
             })??;
@@ -95,7 +93,7 @@ where
         )
         .handler(|_cmd, m, ctx| {
             let block_interval = *m.get_one::<u32>("block_interval").unwrap();
-            let admin_signer = ctx.current_account()?;
+            let admin_signer = &ctx.current_signer()?;
             let tx_hash = ctx.rt.block_on(async {
                 ctx.controller
                     .set_block_interval(admin_signer, block_interval)
@@ -120,7 +118,7 @@ where
         )
         .handler(|_cmd, m, ctx| {
             let switch = m.get_one::<String>("switch").unwrap() == "on";
-            let admin_signer = ctx.current_account()?;
+            let admin_signer = &ctx.current_signer()?;
             let tx_hash = ctx
                 .rt
                 .block_on(async { ctx.controller.emergency_brake(admin_signer, switch).await })??;
@@ -143,7 +141,7 @@ where
         )
         .handler(|_cmd, m, ctx| {
             let quota_limit = *m.get_one::<u64>("quota_limit").unwrap();
-            let admin_signer = ctx.current_account()?;
+            let admin_signer = &ctx.current_signer()?;
             let tx_hash = ctx.rt.block_on(async {
                 ctx.controller
                     .set_quota_limit(admin_signer, quota_limit)
@@ -163,8 +161,7 @@ where
         .arg(
             Arg::new("address")
                 .help("contract address")
-                .required(true)
-                .value_parser(parse_addr),
+                .required(true),
         )
         .arg(
             Arg::new("content")
@@ -187,12 +184,12 @@ where
                 .value_parser(parse_position),
         )
         .handler(|_cmd, m, ctx| {
-            let addr = *m.get_one::<Address>("address").unwrap();
+            let addr = ctx.resolve_addr(m.get_one::<String>("address").unwrap())?;
             let mut data = addr.to_vec();
             let content = m.get_one::<String>("content").unwrap();
             data.extend_from_slice(content.as_bytes());
             let quota = *m.get_one::<u64>("quota").unwrap();
-            let admin_signer = ctx.current_account()?;
+            let admin_signer = &ctx.current_signer()?;
             ctx.rt.block_on(async {
                 let valid_until_block = {
                     let pos = *m.get_one::<Position>("valid-until-block").unwrap();
@@ -218,8 +215,7 @@ where
         .arg(
             Arg::new("address")
                 .help("contract address")
-                .required(true)
-                .value_parser(parse_addr),
+                .required(true),
         )
         .arg(
             Arg::new("content")
@@ -243,12 +239,12 @@ where
                 .value_parser(parse_position),
         )
         .handler(|_cmd, m, ctx| {
-            let addr = *m.get_one::<Address>("address").unwrap();
+            let addr = ctx.resolve_addr(m.get_one::<String>("address").unwrap())?;
             let mut data = addr.to_vec();
             let content = m.get_one::<Vec<u8>>("content").unwrap().to_owned();
             data.extend_from_slice(&content);
             let quota = *m.get_one::<u64>("quota").unwrap();
-            let admin_signer = ctx.current_account()?;
+            let admin_signer = &ctx.current_signer()?;
             ctx.rt.block_on(async {
                 let valid_until_block = {
                     let pos = *m.get_one::<Position>("valid-until-block").unwrap();
@@ -274,8 +270,7 @@ where
         .arg(
             Arg::new("address")
                 .help("contract address")
-                .required(true)
-                .value_parser(parse_addr),
+                .required(true),
         )
         .arg(
             Arg::new("key")
@@ -305,14 +300,14 @@ where
                 .value_parser(parse_position),
         )
         .handler(|_cmd, m, ctx| {
-            let addr = *m.get_one::<Address>("address").unwrap();
+            let addr = ctx.resolve_addr(m.get_one::<String>("address").unwrap())?;
             let mut data = addr.to_vec();
             let key = m.get_one::<[u8; 32]>("key").unwrap().to_owned();
             data.extend_from_slice(&key);
             let value = m.get_one::<[u8; 32]>("value").unwrap().to_owned();
             data.extend_from_slice(&value);
             let quota = *m.get_one::<u64>("quota").unwrap();
-            let admin_signer = ctx.current_account()?;
+            let admin_signer = &ctx.current_signer()?;
             ctx.rt.block_on(async {
                 let valid_until_block = {
                     let pos = *m.get_one::<Position>("valid-until-block").unwrap();
@@ -338,8 +333,7 @@ where
         .arg(
             Arg::new("address")
                 .help("contract address")
-                .required(true)
-                .value_parser(parse_addr),
+                .required(true),
         )
         .arg(
             Arg::new("balance")
@@ -363,12 +357,12 @@ where
                 .value_parser(parse_position),
         )
         .handler(|_cmd, m, ctx| {
-            let addr = *m.get_one::<Address>("address").unwrap();
+            let addr = ctx.resolve_addr(m.get_one::<String>("address").unwrap())?;
             let mut data = addr.to_vec();
             let balance = m.get_one::<[u8; 32]>("balance").unwrap().to_owned();
             data.extend_from_slice(&balance);
             let quota = *m.get_one::<u64>("quota").unwrap();
-            let admin_signer = ctx.current_account()?;
+            let admin_signer = &ctx.current_signer()?;
             ctx.rt.block_on(async {
                 let valid_until_block = {
                     let pos = *m.get_one::<Position>("valid-until-block").unwrap();
@@ -0,0 +1,155 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use clap::Arg;
+
+use crate::{
+    cmd::{
+        account::{get_password, password_source_args},
+        Command,
+    },
+    core::{
+        agent::{self, agent_socket_path, AgentClient, Request},
+        context::Context,
+    },
+    display::Display,
+    utils::{display_time, prompt_password},
+};
+
+// The socket can be changed by `CLDI_AGENT_SOCK`.
+fn client<Co, Ex, Ev>(ctx: &Context<Co, Ex, Ev>) -> AgentClient {
+    AgentClient::new(agent_socket_path(&ctx.config.data_dir))
+}
+
+pub fn start<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("start-agent")
+        .about("Run the key agent in foreground until stopped")
+        .arg(
+            Arg::new("ttl")
+                .help("How long in seconds an added account is held by default")
+                .long("ttl")
+                .default_value("3600")
+                .value_parser(str::parse::<u64>),
+        )
+        .handler(|_cmd, m, ctx| {
+            let socket = agent_socket_path(&ctx.config.data_dir);
+            let ttl = Duration::from_secs(*m.get_one::<u64>("ttl").unwrap());
+            let wallet_dir = ctx.config.data_dir.clone();
+
+            println!("Agent listening on `{}`", socket.display());
+            let ret = ctx.rt.block_on(agent::serve(&socket, wallet_dir, ttl));
+            if ret.is_err() {
+                // Interrupted, clean up the socket.
+                let _ = std::fs::remove_file(&socket);
+            }
+            ret??;
+
+            Ok(())
+        })
+}
+
+pub fn add<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("add-to-agent")
+        .about("Unlock an account in the agent")
+        .arg(Arg::new("name").help("The name of the account. [default: <current-account>]"))
+        .arg(
+            Arg::new("password")
+                .help("The password of the account, prompt for it if no password is given")
+                .short('p')
+                .long("password"),
+        )
+        .args(password_source_args())
+        .arg(
+            Arg::new("ttl")
+                .help("How long in seconds the agent holds the account. [default: <agent-ttl>]")
+                .long("ttl")
+                .value_parser(str::parse::<u64>),
+        )
+        .handler(|_cmd, m, ctx| {
+            let name = m
+                .get_one::<String>("name")
+                .unwrap_or(&ctx.current_setting.account_name)
+                .clone();
+            // Check if the account exists.
+            ctx.wallet.get(&name)?;
+            let password = match get_password(m, false)? {
+                Some(pw) => pw,
+                None => prompt_password(&format!("Password of `{name}`: "), false)?,
+            };
+            let ttl = m.get_one::<u64>("ttl").copied();
+
+            let resp = client(ctx).request(&Request::Add {
+                name,
+                password,
+                ttl,
+            })?;
+            if let Some(expires_at) = resp["expires_at"].as_u64() {
+                println!("Held until {}", display_time(expires_at * 1000));
+            }
+
+            Ok(())
+        })
+}
+
+pub fn list<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("list-agent")
+        .about("List accounts held by the agent")
+        .handler(|_cmd, _m, ctx| {
+            let accounts = client(ctx).request(&Request::List)?;
+            println!("{}", accounts.display());
+
+            Ok(())
+        })
+}
+
+pub fn remove<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("remove-from-agent")
+        .about("Drop an account from the agent")
+        .arg(
+            Arg::new("name")
+                .help("The name of the account")
+                .required(true),
+        )
+        .handler(|_cmd, m, ctx| {
+            let name = m.get_one::<String>("name").unwrap().clone();
+            client(ctx).request(&Request::Remove { name })?;
+
+            Ok(())
+        })
+}
+
+pub fn stop<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("stop-agent")
+        .about("Stop the agent, which drops all the accounts it holds")
+        .handler(|_cmd, _m, ctx| {
+            client(ctx).request(&Request::Stop)?;
+
+            Ok(())
+        })
+}
+
+pub fn agent_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("agent")
+        .about("Key agent commands, commands use the agent to sign if the account is locked")
+        .subcommand_required_else_help(true)
+        .subcommands([
+            start().name("start"),
+            add().name("add"),
+            list().name("list").aliases(["ls", "l"]),
+            remove().name("remove").aliases(["rm"]),
+            stop().name("stop"),
+        ])
+}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::{bail, Context as _, Result};
+use anyhow::{bail, ensure, Context as _, Result};
 use clap::{Arg, ArgAction};
 use crossbeam::atomic::AtomicCell;
 use parking_lot::Mutex;
@@ -25,15 +25,11 @@ use crate::{
     cmd::{watch, Command},
     core::executor::ExecutorBehaviour,
     core::{
-        client::GrpcClientBehaviour,
+        client::{ConnectOptions, GrpcClientBehaviour},
         context::Context,
         controller::{ControllerBehaviour, SignerBehaviour},
     },
-    crypto::Address,
-    utils::{
-        get_block_height_at, parse_addr, parse_data, parse_position, parse_u64, parse_value,
-        Position,
-    },
+    utils::{get_block_height_at, parse_data, parse_position, parse_u64, parse_value, Position},
 };
 use cita_cloud_proto::blockchain::Transaction;
 
@@ -45,6 +41,13 @@ where
     Command::<Context<Co, Ex, Ev>>::new("bench")
         .about("Simple benchmarks")
         .subcommand_required_else_help(true)
+        .handler(|cmd, m, ctx| {
+            ensure!(
+                !ctx.current_setting.policy.forbid_bench,
+                "bench is forbidden in the current context"
+            );
+            cmd.dispatch_subcmd(m, ctx)
+        })
         .subcommands([bench_send().name("send"), bench_call().name("call")])
 }
 
@@ -94,8 +97,7 @@ where
             Arg::new("to")
                 .help("the target address of this tx. Default to random")
                 .short('t')
-                .long("to")
-                .value_parser(parse_addr),
+                .long("to"),
         )
         .arg(
             Arg::new("data")
@@ -147,11 +149,12 @@ where
             let valid_until_block = Arc::new(AtomicCell::new(Option::<u64>::None));
 
             ctx.rt.block_on(async {
+                let signer = &ctx.current_signer()?;
                 // Workload builder
                 let mut rng = thread_rng();
 
-                let to = match m.get_one::<Address>("to") {
-                    Some(to) => to.to_owned(),
+                let to = match m.get_one::<String>("to") {
+                    Some(to) => ctx.resolve_addr(to)?,
                     None => rng.gen(),
                 }.to_vec();
                 let data = match m.get_one::<Vec<u8>>("data") {
@@ -165,7 +168,19 @@ where
                 let system_config = ctx.controller.get_system_config().await
                     .context("failed to fetch chain status")?;
 
-                let signer = ctx.current_account()?;
+                // Bench txs are sent over their own connections, so check them at once
+                // by the context's controller.
+                let sample = Transaction {
+                    to: to.clone(),
+                    data: data.clone(),
+                    value: value.clone(),
+                    quota,
+                    chain_id: system_config.chain_id.clone(),
+                    version: system_config.version,
+                    ..Default::default()
+                };
+                ctx.controller.check_txs(&sample, total).await?;
+
                 let current_valid_until_block = valid_until_block.clone();
 
                 let workload_builder = || {
@@ -191,17 +206,18 @@ where
 
                 // Connection builder
                 let controller_addr = ctx.current_controller_addr();
+                let opts = &ConnectOptions::from_setting(&ctx.current_setting)?;
                 let connector = || async {
                     if timeout > 0 {
-                        Co::connect_timeout(controller_addr, Duration::from_secs(timeout)).await
+                        Co::connect_timeout(controller_addr, Duration::from_secs(timeout), opts).await
                     } else {
-                        Co::connect(controller_addr).await
+                        Co::connect(controller_addr, opts).await
                     }
                 };
 
                 // Work
                 let worker_fn =
-                    |client: Co, raw| async move { client.send_raw(raw).await.map(|_| ()) };
+                    |client: Co, raw: Result<_>| async move { client.send_raw(raw?).await.map(|_| ()) };
 
                 // before fns
                 let before_preparing = || async {
@@ -276,15 +292,13 @@ where
             Arg::new("from")
                 .help("Default to use current account address")
                 .short('f')
-                .long("from")
-                .value_parser(parse_addr),
+                .long("from"),
         )
         .arg(
             Arg::new("to")
                 .help("the target contract address to call. Default to random")
                 .short('t')
-                .long("to")
-                .value_parser(parse_addr),
+                .long("to"),
         )
         .arg(
             Arg::new("data")
@@ -310,12 +324,12 @@ where
                 // Workload builder
                 let mut rng = thread_rng();
 
-                let from = match m.get_one::<Address>("from") {
-                    Some(from) => from.to_owned(),
+                let from = match m.get_one::<String>("from") {
+                    Some(from) => ctx.resolve_addr(from)?,
                     None => *ctx.current_account()?.address(),
                 };
-                let to = match m.get_one::<Address>("to") {
-                    Some(to) => to.to_owned(),
+                let to = match m.get_one::<String>("to") {
+                    Some(to) => ctx.resolve_addr(to)?,
                     None => rng.gen(),
                 };
                 let data = match m.get_one::<Vec<u8>>("data") {
@@ -331,11 +345,12 @@ where
 
                 // Connection builder
                 let executor_addr = ctx.current_executor_addr();
+                let opts = &ConnectOptions::from_setting(&ctx.current_setting)?;
                 let connector = || async {
                     if timeout > 0 {
-                        Ex::connect_timeout(executor_addr, Duration::from_secs(timeout)).await
+                        Ex::connect_timeout(executor_addr, Duration::from_secs(timeout), opts).await
                     } else {
-                        Ex::connect(executor_addr).await
+                        Ex::connect(executor_addr, opts).await
                     }
                 };
 
@@ -12,15 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use clap::{crate_authors, crate_version, Arg, ColorChoice};
+use anyhow::Context as _;
+use clap::{crate_authors, crate_version, Arg, ArgAction, ArgGroup, ColorChoice};
+use std::{collections::BTreeMap, path::PathBuf};
 use tonic::transport::Endpoint;
 
 use crate::{
-    cmd::{account, admin, bench, context, ethabi, evm, rpc, watch, Command},
-    config::ContextSetting,
+    cmd::{
+        account, addr, admin, agent, bench, context, ethabi, evm, relay, rpc, serve, watch, Command,
+    },
+    config::{ContextSetting, TlsSetting},
     core::{
         client::GrpcClientBehaviour, context::Context, controller::ControllerBehaviour,
-        evm::EvmBehaviour, executor::ExecutorBehaviour,
+        evm::EvmBehaviour, executor::ExecutorBehaviour, guard::fetch_chain_pin,
     },
     utils::clap_about,
 };
@@ -73,22 +77,39 @@ where
         )
         .arg(
             Arg::new("controller-addr")
-                .help("controller address")
+                .help("controller address, repeat it for other nodes of the chain")
                 .short('r')
+                .action(ArgAction::Append)
                 .value_parser(|s: &str| Endpoint::from_shared(s.to_string())),
         )
         .arg(
             Arg::new("executor-addr")
-                .help("executor address")
+                .help("executor address, repeat it for other nodes of the chain")
                 .short('e')
+                .action(ArgAction::Append)
+                .value_parser(|s: &str| Endpoint::from_shared(s.to_string())),
+        )
+        .arg(
+            Arg::new("evm-addr")
+                .help("evm address, default to the executor address given by `-e`")
+                .long("evm-addr")
+                .action(ArgAction::Append)
                 .value_parser(|s: &str| Endpoint::from_shared(s.to_string())),
         )
+        .arg(
+            Arg::new("endpoint-strategy")
+                .help("How to pick a node when there are multiple addresses")
+                .long("endpoint-strategy")
+                .value_parser(["FAILOVER", "ROUND-ROBIN", "LATENCY"])
+                .ignore_case(true),
+        )
         .arg(Arg::new("account-name").help("account name").short('u'))
         .arg(
             Arg::new("password")
-                .help("password to unlock the account")
+                .help("password to unlock the account, prefer the safer ways below")
                 .short('p'),
         )
+        .args(account::password_source_args())
         .arg(
             Arg::new("crypto-type")
                 .help("The crypto type of the target chain")
@@ -103,6 +124,98 @@ where
                 .value_parser(["BFT", "OVERLORD", "RAFT"])
                 .ignore_case(true),
         )
+        .arg(
+            Arg::new("backend")
+                .help("The backend of the target chain, CITA for legacy CITA 1.x nodes")
+                .long("backend")
+                .value_parser(["CITACLOUD", "CITA"])
+                .ignore_case(true),
+        )
+        .arg(
+            Arg::new("tls")
+                .help("Connect over TLS, implied by the other TLS options")
+                .long("tls")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ca-cert")
+                .help("PEM CA bundle to verify the server, default to the system's roots")
+                .long("ca-cert")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("client-cert")
+                .help("PEM client certificate for mutual TLS")
+                .long("client-cert")
+                .requires("client-key")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("client-key")
+                .help("PEM client private key for mutual TLS")
+                .long("client-key")
+                .requires("client-cert")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("tls-domain")
+                .help("Override the server name used for SNI and certificate verification")
+                .long("tls-domain"),
+        )
+        .arg(
+            Arg::new("header")
+                .help("gRPC metadata attached to every request, in `KEY=VALUE` format")
+                .long("header")
+                .action(ArgAction::Append)
+                .value_parser(parse_header),
+        )
+        .arg(
+            Arg::new("token-file")
+                .help("Read the bearer token of the `authorization` header from this file")
+                .long("token-file")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("token-env")
+                .help("Read the bearer token of the `authorization` header from this env var")
+                .long("token-env"),
+        )
+        .arg(
+            Arg::new("token-command")
+                .help("Run this shell command to get the bearer token, rerun every `token_ttl` seconds")
+                .long("token-command"),
+        )
+        .group(ArgGroup::new("token").args(["token-file", "token-env", "token-command"]))
+        .arg(
+            Arg::new("max-attempts")
+                .help("Max attempts of a call on each endpoint when it fails with transient errors, 1 for no retry")
+                .long("max-attempts")
+                .value_parser(clap::value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("retry-backoff")
+                .help("Backoff in milliseconds before the first retry, doubled for each of the following ones")
+                .long("retry-backoff")
+                .value_parser(str::parse::<u64>),
+        )
+        .arg(
+            Arg::new("max-retry-backoff")
+                .help("Max backoff in milliseconds between retries")
+                .long("max-retry-backoff")
+                .value_parser(str::parse::<u64>),
+        )
+        .arg(
+            Arg::new("call-deadline")
+                .help("Seconds for a call to complete, including all of its retries")
+                .long("call-deadline")
+                .value_parser(str::parse::<u64>),
+        )
+        .arg(
+            Arg::new("trust-new-chain")
+                .help("Trust the chain that the context currently points at, and pin it in the context")
+                .long("trust-new-chain")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("connect-timeout")
                 .help("connect timeout")
@@ -112,52 +225,86 @@ where
         .handler(|cmd, m, ctx| {
             // If a subcommand is present, context modifiers(e.g. -r) will construct a tmp context for that subcommand.
             // Otherwise modify the current context.
-            let mut previous_setting: Option<ContextSetting> = None;
+            let mut previous_setting: Option<(String, ContextSetting, BTreeMap<String, String>)> =
+                None;
             let mut current_setting = ctx.current_setting.clone();
 
             let is_tmp_ctx = m.subcommand().is_some()
                 && (m.contains_id("context")
                     || m.contains_id("controller-addr")
                     || m.contains_id("executor-addr")
+                    || m.contains_id("evm-addr")
+                    || m.contains_id("endpoint-strategy")
                     || m.contains_id("account-name")
                     || m.contains_id("password")
+                    || m.contains_id("password-file")
+                    || m.contains_id("password-env")
+                    || m.get_flag("ask-password")
                     || m.contains_id("crypto-type")
                     || m.contains_id("consensus-type")
+                    || m.contains_id("backend")
+                    || m.get_flag("tls")
+                    || m.contains_id("ca-cert")
+                    || m.contains_id("client-cert")
+                    || m.contains_id("tls-domain")
+                    || m.contains_id("header")
+                    || m.contains_id("token")
+                    || m.contains_id("max-attempts")
+                    || m.contains_id("retry-backoff")
+                    || m.contains_id("max-retry-backoff")
+                    || m.contains_id("call-deadline")
                     || m.contains_id("connect-timeout"));
             if is_tmp_ctx {
-                previous_setting.replace(current_setting.clone());
+                previous_setting.replace((
+                    ctx.current_context_name.clone(),
+                    current_setting.clone(),
+                    ctx.setting_sources.clone(),
+                ));
             }
             // (account_name, password) for restoring previous account lock status if it's in tmp context.
             let mut relock_info: Option<(String, String)> = None;
 
             if let Some(setting_name) = m.get_one::<String>("context") {
                 current_setting = ctx.get_context_setting(setting_name)?.clone();
+                ctx.current_context_name = setting_name.into();
+                // Overrides are for the starting context only.
+                ctx.setting_sources.clear();
             }
-            if let Some(mut controller_addr) = m.get_raw("controller-addr") {
-                current_setting.controller_addr = controller_addr
-                    .next()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string();
+            let setting_before_flags = current_setting.clone();
+            if let Some(controller_addrs) = m.get_raw("controller-addr") {
+                let mut addrs = controller_addrs.map(|addr| addr.to_str().unwrap().to_string());
+                current_setting.controller_addr = addrs.next().unwrap();
+                current_setting.extra_controller_addrs = addrs.collect();
             }
-            if let Some(mut executor_addr) = m.get_raw("executor-addr") {
-                current_setting.executor_addr =
-                    executor_addr.next().unwrap().to_str().unwrap().to_string();
+            if let Some(executor_addrs) = m.get_raw("executor-addr") {
+                let mut addrs = executor_addrs.map(|addr| addr.to_str().unwrap().to_string());
+                current_setting.executor_addr = addrs.next().unwrap();
+                current_setting.extra_executor_addrs = addrs.collect();
+                if !m.contains_id("evm-addr") {
+                    current_setting.evm_addr = current_setting.executor_addr.clone();
+                    current_setting.extra_evm_addrs = current_setting.extra_executor_addrs.clone();
+                }
+            }
+            if let Some(evm_addrs) = m.get_raw("evm-addr") {
+                let mut addrs = evm_addrs.map(|addr| addr.to_str().unwrap().to_string());
+                current_setting.evm_addr = addrs.next().unwrap();
+                current_setting.extra_evm_addrs = addrs.collect();
+            }
+            if let Some(strategy) = m.get_one::<String>("endpoint-strategy") {
+                current_setting.endpoint_strategy = strategy.parse().unwrap();
             }
             if let Some(account_name) = m.get_one::<String>("account-name") {
                 // Check if the account exists.
                 ctx.wallet.get(account_name)?;
                 current_setting.account_name = account_name.into();
             }
-            if let Some(pw) = m.get_one::<String>("password") {
-                let account_name = m
-                    .get_one::<String>("account-name")
-                    .unwrap_or(&ctx.current_setting.account_name);
+            if let Some(pw) = account::get_password(m, false)? {
+                // It's the account of the context given by `-c` if no `-u`.
+                let account_name = &current_setting.account_name;
                 let was_locked = ctx.wallet.get(account_name)?.is_locked();
                 ctx.wallet.unlock(account_name, pw.as_bytes())?;
                 if is_tmp_ctx && was_locked {
-                    relock_info.replace((account_name.into(), pw.into()));
+                    relock_info.replace((account_name.into(), pw));
                 }
             }
             if let Some(crypto_type) = m.get_one::<String>("crypto-type") {
@@ -166,20 +313,96 @@ where
             if let Some(consensus_type) = m.get_one::<String>("consensus-type") {
                 current_setting.consensus_type = consensus_type.parse().unwrap();
             }
+            if let Some(backend) = m.get_one::<String>("backend") {
+                current_setting.backend = backend.parse().unwrap();
+            }
+            if m.get_flag("tls")
+                || m.contains_id("ca-cert")
+                || m.contains_id("client-cert")
+                || m.contains_id("tls-domain")
+            {
+                let tls = current_setting.tls.get_or_insert_with(TlsSetting::default);
+                if let Some(ca_cert) = m.get_one::<PathBuf>("ca-cert") {
+                    tls.ca_cert = Some(ca_cert.clone());
+                }
+                if let Some(client_cert) = m.get_one::<PathBuf>("client-cert") {
+                    tls.client_cert = Some(client_cert.clone());
+                    tls.client_key = m.get_one::<PathBuf>("client-key").cloned();
+                }
+                if let Some(domain_name) = m.get_one::<String>("tls-domain") {
+                    tls.domain_name = Some(domain_name.clone());
+                }
+            }
+            if let Some(headers) = m.get_many::<(String, String)>("header") {
+                current_setting.metadata.headers.extend(headers.cloned());
+            }
+            if m.contains_id("token") {
+                let metadata = &mut current_setting.metadata;
+                metadata.token_file = m.get_one::<PathBuf>("token-file").cloned();
+                metadata.token_env = m.get_one::<String>("token-env").cloned();
+                metadata.token_command = m.get_one::<String>("token-command").cloned();
+            }
+            if let Some(&max_attempts) = m.get_one::<u32>("max-attempts") {
+                current_setting.retry.max_attempts = max_attempts;
+            }
+            if let Some(&backoff) = m.get_one::<u64>("retry-backoff") {
+                current_setting.retry.initial_backoff_ms = backoff;
+            }
+            if let Some(&max_backoff) = m.get_one::<u64>("max-retry-backoff") {
+                current_setting.retry.max_backoff_ms = max_backoff;
+            }
+            if let Some(&deadline) = m.get_one::<u64>("call-deadline") {
+                current_setting.retry.deadline = Some(deadline);
+            }
             if let Some(&connect_timeout) = m.get_one::<u64>("connect-timeout") {
                 current_setting.connect_timeout = connect_timeout;
             }
 
+            for field in setting_before_flags.changed_fields(&current_setting) {
+                ctx.setting_sources.insert(field, "command line".into());
+            }
             ctx.switch_context(current_setting)?;
+            if m.get_flag("trust-new-chain") {
+                // For intentional chain resets.
+                let pin = ctx.rt.block_on(fetch_chain_pin(&ctx.controller))??;
+                // A context named by `-c` is pinned even if other flags are temporary.
+                let saved = if m.contains_id("context") || !is_tmp_ctx {
+                    ctx.config
+                        .context_settings
+                        .get_mut(&ctx.current_context_name)
+                } else {
+                    None
+                };
+                if let Some(saved) = saved {
+                    saved.chain_pin.replace(pin.clone());
+                    ctx.config.save()?;
+                    // Don't restore the outdated pin after this command.
+                    if let Some((previous_name, previous, _)) = &mut previous_setting {
+                        if *previous_name == ctx.current_context_name {
+                            previous.chain_pin.replace(pin.clone());
+                        }
+                    }
+                } else {
+                    eprintln!(
+                        "the chain is trusted for this command only, the pin is not saved to context `{}`, use `-c` to save it",
+                        ctx.current_context_name
+                    );
+                }
+                let mut setting = ctx.current_setting.clone();
+                setting.chain_pin.replace(pin);
+                ctx.switch_context(setting)?;
+            }
             let ret = cmd.dispatch_subcmd(m, ctx);
 
             // Restore previous lock status and context setting if it's in tmp context.
             if let Some((account_name, pw)) = relock_info {
                 ctx.wallet.lock_in_memory(&account_name, pw.as_bytes())?;
             }
-            if let Some(previous) = previous_setting {
+            if let Some((previous_name, previous, previous_sources)) = previous_setting {
                 ctx.switch_context(previous)
                     .expect("cannot restore previous context");
+                ctx.current_context_name = previous_name;
+                ctx.setting_sources = previous_sources;
             }
 
             ret
@@ -191,12 +414,23 @@ where
             rpc::create_contract().name("create"),
             context::context_cmd(),
             account::account_cmd().alias("a"),
+            agent::agent_cmd(),
+            addr::addr_cmd(),
             admin::admin_cmd(),
             rpc::rpc_cmd(),
             ethabi::ethabi_cmd(),
             bench::bench_cmd().alias("b"),
             watch::watch_cmd().alias("w"),
             rpc::verify_cmd().alias("v"),
+            relay::relay_cmd(),
+            serve::serve_cmd(),
         ])
         .with_completions_subcmd()
 }
+
+fn parse_header(s: &str) -> anyhow::Result<(String, String)> {
+    let (key, value) = s
+        .split_once('=')
+        .context("header must be in `KEY=VALUE` format")?;
+    Ok((key.trim().to_string(), value.trim().to_string()))
+}
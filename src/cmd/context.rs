@@ -12,32 +12,71 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use clap::Arg;
+use std::{fs, path::PathBuf};
 
-use anyhow::anyhow;
+use clap::{Arg, ArgAction};
+
+use anyhow::{anyhow, ensure};
 use serde_json::json;
 
 use crate::{
     cmd::Command,
-    core::{client::GrpcClientBehaviour, context::Context},
+    config::{Config, ContextSetting, CLOUD_CLI_CONFIG_FILE_NAME},
+    core::{
+        client::GrpcClientBehaviour,
+        context::Context,
+        controller::ControllerBehaviour,
+        guard::{check_chain_pin, fetch_chain_pin},
+        probe::probe,
+    },
     display::Display,
+    utils::{hex, parse_value, safe_save},
 };
 
 // TODO: consider if it's appropriate to use config internals here.
 // I think it's OK, at least for now. If it gets complicated, we should
 // use methods provided by Context instead of depending on its internals.
 
-pub fn save<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+pub fn save<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Co: ControllerBehaviour + GrpcClientBehaviour,
+    Ex: GrpcClientBehaviour,
+    Ev: GrpcClientBehaviour,
+{
     Command::<Context<Co, Ex, Ev>>::new("save-context")
         .about("save context")
         .arg(Arg::new("context-name").required(true))
+        .arg(
+            Arg::new("detect")
+                .help("detect the crypto type and consensus type from the chain before saving")
+                .long("detect")
+                .action(ArgAction::SetTrue),
+        )
         .handler(|_cmd, m, ctx| {
             let context_name = m.get_one::<String>("context-name").unwrap();
-            let current_setting = ctx.current_setting.clone();
+            if m.get_flag("detect") {
+                let profile = ctx.rt.block_on(probe(&ctx.controller))??;
+                ctx.current_setting.crypto_type = profile.crypto_type;
+                ctx.current_setting.consensus_type = profile.consensus_type;
+            }
+            let mut current_setting = ctx.current_setting.clone();
+            match ctx.rt.block_on(fetch_chain_pin(&ctx.controller))? {
+                Ok(pin) => {
+                    if let Some(pinned) = &current_setting.chain_pin {
+                        check_chain_pin(pinned, &pin)?;
+                    }
+                    current_setting.chain_pin.replace(pin);
+                }
+                Err(e) => println!("cannot get the chain identity to pin: {e:#}"),
+            }
             ctx.config
                 .context_settings
-                .insert(context_name.into(), current_setting);
+                .insert(context_name.into(), current_setting.clone());
             ctx.config.save()?;
+            ctx.switch_context(current_setting)?;
+            ctx.current_context_name = context_name.into();
+            // The overrides are saved into the context now.
+            ctx.setting_sources.clear();
 
             Ok(())
         })
@@ -89,16 +128,257 @@ where
             ctx.config.default_context = context_name.into();
             ctx.config.save()?;
             ctx.switch_context(setting)?;
+            ctx.current_context_name = context_name.into();
+            ctx.setting_sources.clear();
 
             Ok(())
         })
 }
 
-pub fn context_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+pub fn set_policy<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
 where
     Co: GrpcClientBehaviour,
     Ex: GrpcClientBehaviour,
     Ev: GrpcClientBehaviour,
+{
+    Command::<Context<Co, Ex, Ev>>::new("set-context-policy")
+        .about("set the guard rails of a context")
+        .arg(Arg::new("context-name").required(true))
+        .arg(
+            Arg::new("protected")
+                .help("require typed confirmation for every tx")
+                .long("protected")
+                .value_parser(clap::value_parser!(bool)),
+        )
+        .arg(
+            Arg::new("max-value")
+                .help("max value of a tx")
+                .long("max-value")
+                .value_parser(parse_value),
+        )
+        .arg(
+            Arg::new("allowed-to")
+                .help("the only addresses that txs can be sent to, contract creation is refused if set")
+                .long("allowed-to")
+                .num_args(1..),
+        )
+        .arg(
+            Arg::new("forbid-bench")
+                .help("forbid bench commands")
+                .long("forbid-bench")
+                .value_parser(clap::value_parser!(bool)),
+        )
+        .arg(
+            Arg::new("reset")
+                .help("clear the existing policy before setting the given ones")
+                .long("reset")
+                .action(ArgAction::SetTrue),
+        )
+        .handler(|_cmd, m, ctx| {
+            let context_name = m.get_one::<String>("context-name").unwrap();
+            let setting = ctx
+                .config
+                .context_settings
+                .get_mut(context_name)
+                .ok_or_else(|| anyhow!("context `{}` not found", context_name))?;
+            let policy = &mut setting.policy;
+            if m.get_flag("reset") {
+                *policy = Default::default();
+            }
+            if let Some(&protected) = m.get_one::<bool>("protected") {
+                policy.protected = protected;
+            }
+            if let Some(max_value) = m.get_one::<[u8; 32]>("max-value") {
+                policy.max_value = Some(hex(max_value));
+            }
+            if let Some(allowed_to) = m.get_many::<String>("allowed-to") {
+                policy.allowed_to = allowed_to
+                    .map(|addr| Ok(hex(ctx.resolve_addr(addr)?.as_slice())))
+                    .collect::<anyhow::Result<_>>()?;
+            }
+            if let Some(&forbid_bench) = m.get_one::<bool>("forbid-bench") {
+                policy.forbid_bench = forbid_bench;
+            }
+            let policy = policy.clone();
+            ctx.config.save()?;
+            println!("{}", json!(policy).display());
+
+            if *context_name == ctx.current_context_name {
+                let setting = ContextSetting {
+                    policy,
+                    ..ctx.current_setting.clone()
+                };
+                ctx.switch_context(setting)?;
+            }
+
+            Ok(())
+        })
+}
+
+pub fn probe_chain<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Co: ControllerBehaviour + GrpcClientBehaviour,
+{
+    Command::<Context<Co, Ex, Ev>>::new("probe-chain")
+        .about("detect the crypto type and consensus type of a chain")
+        .arg(Arg::new("controller-addr").help(
+            "the controller address of the chain [default: <current-context-controller-addr>]",
+        ))
+        .handler(|_cmd, m, ctx| {
+            let profile = ctx.rt.block_on(async {
+                match m.get_one::<String>("controller-addr") {
+                    Some(addr) => {
+                        let setting = ContextSetting {
+                            controller_addr: addr.clone(),
+                            extra_controller_addrs: vec![],
+                            ..ctx.current_setting.clone()
+                        };
+                        let controller =
+                            Co::connect_by_setting(&setting.controller_addrs(), &setting)?;
+                        probe(&controller).await
+                    }
+                    None => probe(&ctx.controller).await,
+                }
+            })??;
+            println!("{}", json!(profile).display());
+
+            Ok(())
+        })
+}
+
+pub fn validate<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("validate-config")
+        .about("validate config file")
+        .arg(
+            Arg::new("file")
+                .help("the config file to validate [default: <data-dir>/config.toml]")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .handler(|_cmd, m, ctx| {
+            let file = m
+                .get_one::<PathBuf>("file")
+                .cloned()
+                .unwrap_or_else(|| ctx.config.data_dir.join(CLOUD_CLI_CONFIG_FILE_NAME));
+            Config::load(&file)?.validate()?;
+            println!("config `{}` is valid", file.display());
+
+            Ok(())
+        })
+}
+
+pub fn edit<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Co: GrpcClientBehaviour,
+    Ex: GrpcClientBehaviour,
+    Ev: GrpcClientBehaviour,
+{
+    Command::<Context<Co, Ex, Ev>>::new("edit-config")
+        .about("edit config file with $EDITOR, it's validated before saving")
+        .handler(|_cmd, _m, ctx| {
+            let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".into());
+            let config_file = ctx.config.data_dir.join(CLOUD_CLI_CONFIG_FILE_NAME);
+            let original = fs::read_to_string(&config_file)?;
+            // Edit a copy so that the config file is never left in an invalid state.
+            let tmp = tempfile::Builder::new().suffix(".toml").tempfile()?;
+            fs::write(tmp.path(), &original)?;
+
+            let (config, edited) = loop {
+                // The editor may come with args, e.g. `code --wait`.
+                let status = std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(format!("{editor} \"$1\""))
+                    .arg("sh")
+                    .arg(tmp.path())
+                    .status()?;
+                ensure!(status.success(), "editor `{editor}` exits with {status}");
+
+                let edited = fs::read_to_string(tmp.path())?;
+                if edited == original {
+                    println!("no changes");
+                    return Ok(());
+                }
+                let result = Config::parse(&edited).and_then(|config| {
+                    config.validate()?;
+                    Ok(config)
+                });
+                match result {
+                    Ok(config) => break (config, edited),
+                    Err(e) => {
+                        println!("{e:#}");
+                        let again = ctx
+                            .editor
+                            .readline("Edit again? (y/n) ")
+                            .map(|s| s.trim().to_ascii_lowercase());
+                        if !matches!(again.as_deref(), Ok("y") | Ok("yes")) {
+                            println!("changes discarded");
+                            return Ok(());
+                        }
+                    }
+                }
+            };
+
+            let backup = ctx.config.backup()?;
+            // Keep the formatting and comments of the edited file.
+            safe_save(&config_file, edited.as_bytes(), true)?;
+            ctx.config = Config {
+                data_dir: ctx.config.data_dir.clone(),
+                ..config
+            };
+            println!(
+                "config saved, the previous one is backed up to `{}`",
+                backup.display()
+            );
+
+            if let Ok(setting) = ctx.get_context_setting(&ctx.current_context_name).cloned() {
+                ctx.switch_context(setting)?;
+            }
+
+            Ok(())
+        })
+}
+
+pub fn show<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("show-context")
+        .about("show current context")
+        .arg(
+            Arg::new("effective")
+                .help("show where each value comes from, e.g. project file, env or command line")
+                .long("effective")
+                .action(ArgAction::SetTrue),
+        )
+        .handler(|_cmd, m, ctx| {
+            let setting = serde_json::to_value(&ctx.current_setting)?;
+            let setting = if m.get_flag("effective") {
+                let serde_json::Value::Object(fields) = setting else {
+                    unreachable!("setting must be serialized to a map");
+                };
+                let context_source = format!("context `{}`", ctx.current_context_name);
+                fields
+                    .into_iter()
+                    .map(|(field, value)| {
+                        let source = ctx.setting_sources.get(&field).unwrap_or(&context_source);
+                        let entry = json!({ "value": value, "source": source });
+                        (field, entry)
+                    })
+                    .collect()
+            } else {
+                setting
+            };
+            let json = json!({
+                "name": &ctx.current_context_name,
+                "setting": setting,
+            });
+            println!("{}", json.display());
+
+            Ok(())
+        })
+}
+
+pub fn context_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Co: ControllerBehaviour + GrpcClientBehaviour,
+    Ex: GrpcClientBehaviour,
+    Ev: GrpcClientBehaviour,
 {
     Command::<Context<Co, Ex, Ev>>::new("context")
         .alias("ctx")
@@ -106,8 +386,13 @@ where
         .subcommand_required_else_help(true)
         .subcommands([
             save().name("save"),
+            show().name("show"),
             list().name("list").aliases(["ls", "l"]),
             delete().name("delete").aliases(["del", "rm"]),
             default().name("default"),
+            probe_chain().name("probe"),
+            set_policy().name("policy"),
+            validate().name("validate"),
+            edit().name("edit"),
         ])
 }
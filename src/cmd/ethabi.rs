@@ -7,6 +7,7 @@ use clap::{Arg, ArgAction};
 use ethabi::{
     decode, encode,
     param_type::{ParamType, Reader},
+    short_signature,
     token::{LenientTokenizer, StrictTokenizer, Token, Tokenizer},
     Contract, Event, Function, Hash,
 };
@@ -375,6 +376,30 @@ fn decode_log(
     Ok(result)
 }
 
+/// Encode a call to the function with signature like `verify(bytes)`, whose only param is `bytes`.
+pub fn encode_bytes_call(signature: &str, arg: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let signature = signature.replace(' ', "");
+    let (name, params) = signature
+        .strip_suffix(')')
+        .and_then(|s| s.split_once('('))
+        .ok_or_else(|| anyhow!("invalid function signature `{}`", signature))?;
+    let params = params
+        .split(',')
+        .filter(|p| !p.is_empty())
+        .map(Reader::read)
+        .collect::<Result<Vec<_>, _>>()?;
+    if params != [ParamType::Bytes] {
+        return Err(anyhow!(
+            "function `{}` should take a single `bytes` param",
+            signature
+        ));
+    }
+
+    let mut data = short_signature(name, &params).to_vec();
+    data.extend(encode(&[Token::Bytes(arg.to_vec())]));
+    Ok(data)
+}
+
 fn hash_signature(sig: &str) -> Hash {
     Hash::from_slice(Keccak256::digest(sig.replace(' ', "").as_bytes()).as_slice())
 }
@@ -382,6 +407,7 @@ fn hash_signature(sig: &str) -> Hash {
 #[cfg(test)]
 mod tests {
 
+    use super::{encode_bytes_call, hash_signature};
     use crate::cmd::cldi_cmd;
     use crate::core::mock::context;
 
@@ -454,4 +480,17 @@ mod tests {
             )
             .unwrap();
     }
+
+    #[test]
+    fn test_encode_bytes_call() {
+        let data = encode_bytes_call("verify(bytes)", &[0xab; 3]).unwrap();
+        assert_eq!(data[..4], hash_signature("verify(bytes)")[..4]);
+        // offset + length + padded bytes
+        assert_eq!(data.len(), 4 + 32 * 3);
+        assert_eq!(data[4 + 64..4 + 67], [0xab; 3]);
+
+        assert!(encode_bytes_call("verify(uint256)", &[]).is_err());
+        assert!(encode_bytes_call("verify(bytes,bytes)", &[]).is_err());
+        assert!(encode_bytes_call("verify", &[]).is_err());
+    }
 }
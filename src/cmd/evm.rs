@@ -15,6 +15,8 @@
 use cita_cloud_proto::evm::block_number::Lable;
 use cita_cloud_proto::evm::BlockNumber;
 use clap::Arg;
+use prost::Message;
+use std::fs;
 
 use crate::crypto::ArrayLike;
 use crate::utils::parse_block_number;
@@ -23,9 +25,9 @@ use crate::{
     core::{
         context::Context, controller::ControllerBehaviour, evm::EvmBehaviour, evm::EvmBehaviourExt,
     },
-    crypto::{Address, Hash},
+    crypto::Hash,
     display::Display,
-    utils::{get_block_height_at, parse_addr, parse_hash, parse_position, Position},
+    utils::{get_block_height_at, parse_hash, parse_position, Position},
 };
 
 pub fn get_receipt<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
@@ -44,7 +46,7 @@ where
             let tx_hash = *m.get_one::<Hash>("tx_hash").unwrap();
 
             let receipt = ctx.rt.block_on(ctx.evm.get_receipt(tx_hash))??;
-            println!("{}", receipt.display());
+            println!("{}", ctx.display(&receipt));
             Ok(())
         })
 }
@@ -56,19 +58,14 @@ where
 {
     Command::<Context<Co, Ex, Ev>>::new("get-code")
         .about("Get code by contract address")
-        .arg(
-            Arg::new("addr")
-                .help("Contract address")
-                .required(true)
-                .value_parser(parse_addr),
-        )
+        .arg(Arg::new("addr").help("Contract address").required(true))
         .arg(
             Arg::new("height")
                 .help("Block number, tag or block hash")
                 .value_parser(parse_block_number),
         )
         .handler(|_cmd, m, ctx| {
-            let addr = *m.get_one::<Address>("addr").unwrap();
+            let addr = ctx.resolve_addr(m.get_one::<String>("addr").unwrap())?;
             let mut block_number = m
                 .get_one::<BlockNumber>("height")
                 .unwrap_or(&BlockNumber {
@@ -100,19 +97,15 @@ where
 {
     Command::<Context<Co, Ex, Ev>>::new("get-balance")
         .about("Get balance by account address")
-        .arg(
-            Arg::new("addr")
-                .help("Account address, default to current account")
-                .value_parser(parse_addr),
-        )
+        .arg(Arg::new("addr").help("Account address, default to current account"))
         .arg(
             Arg::new("height")
                 .help("Block number, tag or block hash")
                 .value_parser(parse_block_number),
         )
         .handler(|_cmd, m, ctx| {
-            let addr = match m.get_one::<Address>("addr") {
-                Some(s) => s.to_owned(),
+            let addr = match m.get_one::<String>("addr") {
+                Some(s) => ctx.resolve_addr(s)?,
                 None => *ctx.current_account()?.address(),
             };
             let mut block_number = m
@@ -147,19 +140,15 @@ where
 {
     Command::<Context<Co, Ex, Ev>>::new("get-account-nonce")
         .about("Get the nonce of this account")
-        .arg(
-            Arg::new("addr")
-                .help("Account address, default to current account")
-                .value_parser(parse_addr),
-        )
+        .arg(Arg::new("addr").help("Account address, default to current account"))
         .arg(
             Arg::new("height")
                 .help("Block number, tag or block hash")
                 .value_parser(parse_block_number),
         )
         .handler(|_cmd, m, ctx| {
-            let addr = match m.get_one::<Address>("addr") {
-                Some(s) => s.to_owned(),
+            let addr = match m.get_one::<String>("addr") {
+                Some(s) => ctx.resolve_addr(s)?,
                 None => *ctx.current_account()?.address(),
             };
             let mut block_number = m
@@ -195,19 +184,14 @@ where
 {
     Command::<Context<Co, Ex, Ev>>::new("get-contract-abi")
         .about("Get the specific contract ABI")
-        .arg(
-            Arg::new("addr")
-                .help("Contract address")
-                .required(true)
-                .value_parser(parse_addr),
-        )
+        .arg(Arg::new("addr").help("Contract address").required(true))
         .arg(
             Arg::new("height")
                 .help("Block number, tag or block hash")
                 .value_parser(parse_block_number),
         )
         .handler(|_cmd, m, ctx| {
-            let addr = *m.get_one::<Address>("addr").unwrap();
+            let addr = ctx.resolve_addr(m.get_one::<String>("addr").unwrap())?;
             let mut block_number = m
                 .get_one::<BlockNumber>("height")
                 .unwrap_or(&BlockNumber {
@@ -240,8 +224,7 @@ where
         .about("Store EVM contract ABI")
         .arg(
             Arg::new("addr")
-                .required(true)
-                .value_parser(parse_addr),
+                .required(true),
         )
         .arg(Arg::new("abi").required(true))
         .arg(
@@ -261,7 +244,8 @@ where
         )
         .handler(|_cmd, m, ctx| {
             let tx_hash = ctx.rt.block_on(async {
-                let contract_addr = *m.get_one::<Address>("addr").unwrap();
+                let signer = &ctx.current_signer()?;
+                let contract_addr = ctx.resolve_addr(m.get_one::<String>("addr").unwrap())?;
                 let abi = m.get_one::<String>("abi").unwrap();
                 let quota = *m.get_one::<u64>("quota").unwrap();
                 let valid_until_block = {
@@ -269,7 +253,6 @@ where
                     get_block_height_at(&ctx.controller, pos).await?
                 };
 
-                let signer = ctx.current_account()?;
                 ctx.controller
                     .store_contract_abi(
                         signer,
@@ -297,11 +280,21 @@ where
                 .required(true)
                 .value_parser(parse_hash),
         )
+        .arg(
+            Arg::new("output")
+                .help("Output the receipt proof file to this dir")
+                .short('o')
+                .long("output"),
+        )
         .handler(|_cmd, m, ctx| {
             let tx_hash = *m.get_one::<Hash>("tx_hash").unwrap();
 
             let receipt_proof = ctx.rt.block_on(ctx.evm.get_receipt_proof(tx_hash))??;
             println!("{}", receipt_proof.display());
+            if let Some(dir) = m.get_one::<String>("output") {
+                let path = format!("{}/{}-rp", dir, hex::encode(&tx_hash[..8]));
+                fs::write(path, receipt_proof.encode_to_vec())?;
+            }
             Ok(())
         })
 }
@@ -350,12 +343,7 @@ where
 {
     Command::<Context<Co, Ex, Ev>>::new("get-roots-info")
         .about("Get the specific block's roots info")
-        .arg(
-            Arg::new("addr")
-                .help("Contract address")
-                .required(true)
-                .value_parser(parse_addr),
-        )
+        .arg(Arg::new("addr").help("Contract address").required(true))
         .arg(
             Arg::new("position")
                 .help("Input query storage slot position hash")
@@ -368,7 +356,7 @@ where
                 .value_parser(parse_block_number),
         )
         .handler(|_cmd, m, ctx| {
-            let addr = *m.get_one::<Address>("addr").unwrap();
+            let addr = ctx.resolve_addr(m.get_one::<String>("addr").unwrap())?;
             let position = *m.get_one::<Hash>("position").unwrap();
             let mut block_number = m
                 .get_one::<BlockNumber>("height")
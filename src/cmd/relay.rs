@@ -0,0 +1,727 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{bail, Context as _, Result};
+use cita_cloud_proto::{
+    blockchain::{raw_transaction::Tx, RawTransaction},
+    evm::Receipt,
+};
+use clap::{Arg, ArgGroup};
+use prost::Message;
+use std::time::Duration;
+
+use crate::{
+    cmd::{ethabi::encode_bytes_call, Command},
+    config::{ContextSetting, CryptoType},
+    core::{
+        client::{ConnectOptions, GrpcClientBehaviour},
+        context::Context,
+        controller::{ControllerBehaviour, SignerBehaviour, TransactionSenderBehaviour},
+        cross_chain::{self, CrossChainResultCode},
+        endpoints::{backoff, is_not_found},
+        evm::EvmBehaviour,
+    },
+    crypto::{Address, EthCrypto, Hash, SmCrypto},
+    display::Display,
+    utils::{get_block_height_at, hex, parse_hash, parse_position, Position},
+};
+
+/// The destination chain of relaying and how to call the verifier on it.
+struct Destination<'a, Co, Ev, S> {
+    controller: Co,
+    evm: Ev,
+    signer: &'a S,
+    verifier: Address,
+    method: &'a str,
+    quota: u64,
+    valid_until: Position,
+    receipt_timeout: Duration,
+}
+
+fn connect<Co, Ev>(setting: &ContextSetting) -> Result<(Co, Ev)>
+where
+    Co: GrpcClientBehaviour,
+    Ev: GrpcClientBehaviour,
+{
+    let opts = ConnectOptions::from_setting(setting)?;
+    let controller = Co::connect_with_options(&setting.controller_addrs(), setting, &opts)?;
+    let evm = Ev::connect_with_options(&setting.evm_addrs(), setting, &opts)?;
+    Ok((controller, evm))
+}
+
+/// Fetch the cross chain proof of `tx_hash` and verify it against the source chain's
+/// validators, returns the encoded proof.
+async fn fetch_proof<Co>(src: &Co, src_crypto_type: CryptoType, tx_hash: Hash) -> Result<Vec<u8>>
+where
+    Co: ControllerBehaviour,
+{
+    let ccp = src
+        .get_cross_chain_proof(tx_hash)
+        .await
+        .context("failed to get cross chain proof")?;
+    let height = ccp
+        .receipt_proof
+        .as_ref()
+        .and_then(|rp| rp.roots_info.as_ref())
+        .map(|ri| ri.height)
+        .ok_or_else(|| RelayError::InvalidProof {
+            tx: hex(&tx_hash),
+            code: CrossChainResultCode::NoneRootsInfo,
+        })?;
+    let sys_conf = src.get_system_config_by_number(height).await?;
+    let validators = sys_conf.validators.iter().map(hex::encode).collect();
+
+    let result = match src_crypto_type {
+        CryptoType::Sm => cross_chain::verify_cross_chain_proof::<SmCrypto>(
+            ccp.clone(),
+            Some(validators),
+            Some(sys_conf),
+        ),
+        CryptoType::Eth => cross_chain::verify_cross_chain_proof::<EthCrypto>(
+            ccp.clone(),
+            Some(validators),
+            Some(sys_conf),
+        ),
+    };
+    if let Err(code) = result {
+        if !code.is_success() {
+            return Err(RelayError::InvalidProof {
+                tx: hex(&tx_hash),
+                code,
+            }
+            .into());
+        }
+        println!("tx `{}`: {code}", hex(&tx_hash));
+    }
+
+    Ok(ccp.encode_to_vec())
+}
+
+/// Send the proof to the verifier, returns the relay tx hash and the height it's valid until.
+async fn send_relay<Co, Ev, S>(
+    dst: &Destination<'_, Co, Ev, S>,
+    tx_hash: Hash,
+    proof: &[u8],
+) -> Result<(Hash, u64)>
+where
+    Co: ControllerBehaviour + Send + Sync,
+    S: SignerBehaviour + Send + Sync,
+{
+    let data = encode_bytes_call(dst.method, proof)?;
+    let valid_until_block = get_block_height_at(&dst.controller, dst.valid_until).await?;
+    let relay_tx_hash = dst
+        .controller
+        .send_tx(
+            dst.signer,
+            dst.verifier.to_vec(),
+            data,
+            vec![0; 32],
+            dst.quota,
+            valid_until_block,
+        )
+        .await?;
+    println!(
+        "tx `{}` relayed by tx `{}`, waiting for receipt..",
+        hex(&tx_hash),
+        hex(&relay_tx_hash)
+    );
+    Ok((relay_tx_hash, valid_until_block))
+}
+
+async fn wait_relay_receipt<Co, Ev, S>(
+    dst: &Destination<'_, Co, Ev, S>,
+    tx_hash: Hash,
+    (relay_tx_hash, valid_until_block): (Hash, u64),
+) -> Result<Receipt>
+where
+    Co: ControllerBehaviour,
+    Ev: EvmBehaviour,
+{
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    let deadline = tokio::time::Instant::now() + dst.receipt_timeout;
+    loop {
+        interval.tick().await;
+        match dst.evm.get_receipt(relay_tx_hash).await {
+            Ok(receipt) => return check_relay_receipt(tx_hash, receipt),
+            // The receipt is not found until the relay tx is committed.
+            Err(e) if is_not_found(&e) => (),
+            Err(e) => return Err(e),
+        }
+        if tokio::time::Instant::now() >= deadline {
+            // It can't be committed after the block it's valid until.
+            let current_height = dst.controller.get_block_number(false).await?;
+            let relay_tx = hex(&relay_tx_hash);
+            if current_height > valid_until_block {
+                bail!(RelayError::Expired {
+                    relay_tx,
+                    valid_until_block
+                });
+            }
+            bail!(RelayError::ReceiptTimeout { relay_tx });
+        }
+    }
+}
+
+/// A tx being relayed, kept until it's done or failed permanently.
+struct PendingRelay {
+    tx_hash: Hash,
+    /// The relay tx sent for it and the height it's valid until. Its receipt is polled
+    /// instead of relaying the proof again, unless it's expired.
+    sent: Option<(Hash, u64)>,
+}
+
+impl PendingRelay {
+    fn new(tx_hash: Hash) -> Self {
+        Self {
+            tx_hash,
+            sent: None,
+        }
+    }
+
+    async fn relay<Co, Ev, S>(
+        &mut self,
+        src: &Co,
+        src_crypto_type: CryptoType,
+        dst: &Destination<'_, Co, Ev, S>,
+    ) -> Result<Receipt>
+    where
+        Co: ControllerBehaviour + Send + Sync,
+        Ev: EvmBehaviour,
+        S: SignerBehaviour + Send + Sync,
+    {
+        let sent = match self.sent {
+            Some(sent) => sent,
+            None => {
+                let proof = fetch_proof(src, src_crypto_type, self.tx_hash).await?;
+                *self
+                    .sent
+                    .insert(send_relay(dst, self.tx_hash, &proof).await?)
+            }
+        };
+        let result = wait_relay_receipt(dst, self.tx_hash, sent).await;
+        if let Err(e) = &result {
+            if let Some(RelayError::Expired { .. }) = e.downcast_ref() {
+                self.sent = None;
+            }
+        }
+        result
+    }
+}
+
+/// Relay the pending txs and keep the ones to retry, i.e. not failed permanently.
+async fn relay_pending<Co, Ev, S>(
+    src: &Co,
+    src_crypto_type: CryptoType,
+    dst: &Destination<'_, Co, Ev, S>,
+    pending: &mut Vec<PendingRelay>,
+) where
+    Co: ControllerBehaviour + Send + Sync,
+    Ev: EvmBehaviour,
+    S: SignerBehaviour + Send + Sync,
+{
+    let mut retries = vec![];
+    for mut relay in pending.drain(..) {
+        match relay.relay(src, src_crypto_type, dst).await {
+            Ok(receipt) => println!("{}", receipt.display()),
+            Err(e)
+                if e.downcast_ref::<RelayError>()
+                    .is_some_and(RelayError::is_permanent) =>
+            {
+                println!("{e}")
+            }
+            Err(e) => {
+                println!(
+                    "failed to relay tx `{}`, will retry: `{e:#}`",
+                    hex(&relay.tx_hash)
+                );
+                retries.push(relay);
+            }
+        }
+    }
+    *pending = retries;
+}
+
+#[derive(Debug, thiserror::Error)]
+enum RelayError {
+    /// The proof would be rejected again.
+    #[error("cross chain proof of tx `{tx}` is invalid: {code}")]
+    InvalidProof {
+        tx: String,
+        code: CrossChainResultCode,
+    },
+    /// The relay tx is executed but reverted, e.g. the verifier rejects the proof.
+    /// It's not retried since it would be reverted again.
+    #[error("relay tx `{relay_tx}` of tx `{tx}` is reverted: {message}")]
+    Reverted {
+        tx: String,
+        relay_tx: String,
+        message: String,
+    },
+    /// The relay tx may still be committed, its receipt is polled on retry.
+    #[error("timeout when waiting for the receipt of relay tx `{relay_tx}`")]
+    ReceiptTimeout { relay_tx: String },
+    /// The relay tx can't be committed anymore, the proof is relayed again on retry.
+    #[error("relay tx `{relay_tx}` is expired after block `{valid_until_block}`")]
+    Expired {
+        relay_tx: String,
+        valid_until_block: u64,
+    },
+}
+
+impl RelayError {
+    fn is_permanent(&self) -> bool {
+        matches!(self, Self::InvalidProof { .. } | Self::Reverted { .. })
+    }
+}
+
+fn check_relay_receipt(tx_hash: Hash, receipt: Receipt) -> Result<Receipt> {
+    if !receipt.error_message.is_empty() {
+        bail!(RelayError::Reverted {
+            tx: hex(&tx_hash),
+            relay_tx: hex(&receipt.transaction_hash),
+            message: receipt.error_message,
+        });
+    }
+    Ok(receipt)
+}
+
+// Normal txs sent to `addr` in block `height`.
+async fn txs_to<Co: ControllerBehaviour>(
+    controller: &Co,
+    height: u64,
+    addr: &Address,
+) -> Result<Vec<Hash>> {
+    let block = controller.get_compact_block_by_number(height).await?;
+    let mut hashes = vec![];
+    for tx_hash in block.body.map(|b| b.tx_hashes).unwrap_or_default() {
+        let tx_hash: Hash = tx_hash
+            .as_slice()
+            .try_into()
+            .context("invalid tx hash in block")?;
+        let tx = controller.get_tx(tx_hash).await?;
+        if let RawTransaction {
+            tx: Some(Tx::NormalTx(utx)),
+        } = tx
+        {
+            if utx.transaction.map(|tx| tx.to) == Some(addr.to_vec()) {
+                hashes.push(tx_hash);
+            }
+        }
+    }
+    Ok(hashes)
+}
+
+pub fn relay_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Co: ControllerBehaviour + GrpcClientBehaviour + Send + Sync,
+    Ev: EvmBehaviour + GrpcClientBehaviour,
+{
+    Command::<Context<Co, Ex, Ev>>::new("relay")
+        .about("Relay cross chain proofs from one chain to a verifier contract on another")
+        .arg(
+            Arg::new("from-context")
+                .help("the context of the source chain")
+                .long("from-context")
+                .required(true),
+        )
+        .arg(
+            Arg::new("to-context")
+                .help("the context of the destination chain, whose account sends the relay txs")
+                .long("to-context")
+                .required(true),
+        )
+        .arg(
+            Arg::new("tx")
+                .help("the tx on the source chain to relay")
+                .long("tx")
+                .value_parser(parse_hash),
+        )
+        .arg(
+            Arg::new("watch")
+                .help("watch the source chain and relay all the txs sent to this address")
+                .long("watch"),
+        )
+        .group(
+            ArgGroup::new("source")
+                .args(["tx", "watch"])
+                .required(true),
+        )
+        .arg(
+            Arg::new("begin")
+                .help("the block height the watch starts from. You can use +/- prefix to seek from current height")
+                .short('b')
+                .long("begin")
+                .requires("watch")
+                .allow_hyphen_values(true)
+                .value_parser(parse_position),
+        )
+        .arg(
+            Arg::new("verifier")
+                .help("the verifier contract on the destination chain")
+                .long("verifier")
+                .required(true),
+        )
+        .arg(
+            Arg::new("method")
+                .help("the verifier's method taking the encoded proof, e.g. `verifyProof(bytes)`")
+                .long("method")
+                .required(true),
+        )
+        .arg(
+            Arg::new("quota")
+                .help("the quota of the relay tx")
+                .short('q')
+                .long("quota")
+                .default_value("3000000")
+                .value_parser(str::parse::<u64>),
+        )
+        .arg(
+            Arg::new("valid-until-block")
+                .help("the relay tx is valid until the given block height. `+h` means `<current-height> + h`")
+                .long("until")
+                .default_value("+95")
+                .value_parser(parse_position),
+        )
+        .arg(
+            Arg::new("receipt-timeout")
+                .help("seconds to wait for the receipt of a relay tx")
+                .long("receipt-timeout")
+                .default_value("60")
+                .value_parser(str::parse::<u64>),
+        )
+        .handler(|_cmd, m, ctx| {
+            let from_name = m.get_one::<String>("from-context").unwrap();
+            let to_name = m.get_one::<String>("to-context").unwrap();
+            let from_setting = ctx.get_context_setting(from_name)?;
+            let to_setting = ctx.get_context_setting(to_name)?;
+
+            // Names are resolved against the address book of the chain they live on.
+            let verifier = ctx.resolve_addr_in(to_name, m.get_one::<String>("verifier").unwrap())?;
+            let watched = m
+                .get_one::<String>("watch")
+                .map(|watched| ctx.resolve_addr_in(from_name, watched))
+                .transpose()?;
+            let signer = &ctx.context_signer(to_setting)?;
+
+            ctx.rt.block_on(async {
+                let (src, _) = connect::<Co, Ev>(from_setting)?;
+                let (controller, evm) = connect::<Co, Ev>(to_setting)?;
+                let dst = Destination {
+                    controller,
+                    evm,
+                    signer,
+                    verifier,
+                    method: m.get_one::<String>("method").unwrap(),
+                    quota: *m.get_one::<u64>("quota").unwrap(),
+                    valid_until: *m.get_one::<Position>("valid-until-block").unwrap(),
+                    receipt_timeout: Duration::from_secs(
+                        *m.get_one::<u64>("receipt-timeout").unwrap(),
+                    ),
+                };
+
+                let Some(watched) = watched else {
+                    let tx_hash = *m.get_one::<Hash>("tx").unwrap();
+                    let receipt = PendingRelay::new(tx_hash)
+                        .relay(&src, from_setting.crypto_type, &dst)
+                        .await?;
+                    println!("{}", receipt.display());
+                    return anyhow::Ok(());
+                };
+
+                let current_height = src.get_block_number(false).await?;
+                let mut h = m
+                    .get_one::<Position>("begin")
+                    .map(|p| p.with_current(current_height))
+                    .unwrap_or(current_height);
+                // Txs of block `h` that are not done yet, they are retried before moving on.
+                let mut pending: Option<Vec<PendingRelay>> = None;
+                // Back off on consecutive failures instead of checking every second.
+                let mut failures = 0;
+                let mut check_interval = tokio::time::interval(Duration::from_secs(1));
+                loop {
+                    if failures == 0 {
+                        check_interval.tick().await;
+                    } else {
+                        tokio::time::sleep(backoff(&from_setting.retry, failures)).await;
+                    }
+                    let current_height = match src.get_block_number(false).await {
+                        Ok(current) => current,
+                        Err(e) => {
+                            println!("failed to get current height: `{e}`");
+                            failures += 1;
+                            continue;
+                        }
+                    };
+                    let mut failed = false;
+                    while h <= current_height {
+                        let mut txs = match pending.take() {
+                            Some(txs) => txs,
+                            None => match txs_to(&src, h, &watched).await {
+                                Ok(tx_hashes) => {
+                                    tx_hashes.into_iter().map(PendingRelay::new).collect()
+                                }
+                                Err(e) => {
+                                    println!("failed to get txs in block `{h}`: `{e}`");
+                                    failed = true;
+                                    break;
+                                }
+                            },
+                        };
+                        // Keep watching if some of them fail.
+                        relay_pending(&src, from_setting.crypto_type, &dst, &mut txs).await;
+                        if !txs.is_empty() {
+                            pending.replace(txs);
+                            failed = true;
+                            break;
+                        }
+                        h += 1;
+                    }
+                    failures = if failed { failures + 1 } else { 0 };
+                }
+            })??;
+
+            Ok(())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::cldi_cmd;
+    use crate::core::{
+        mock::{context, cross_chain_proof_fixture, MockControllerClient, MockEvmClient},
+        wallet::{Account, MultiCryptoAccount},
+    };
+    use cita_cloud_proto::controller::{CrossChainProof, SystemConfig};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    const TX_HASH: Hash = [0x11; 32];
+    const RELAY_TX_HASH: Hash = [0x22; 32];
+    const VERIFIER: Address = [0x33; 20];
+    const METHOD: &str = "verifyProof(bytes)";
+
+    fn sm_proof() -> CrossChainProof {
+        cross_chain_proof_fixture(include_str!("../../test/cross_chain/sm.json"))
+    }
+
+    fn source(ccp: CrossChainProof) -> MockControllerClient {
+        let sys_conf = SystemConfig {
+            version: ccp.version,
+            chain_id: ccp.chain_id.clone(),
+            ..Default::default()
+        };
+        let mut src = MockControllerClient::default();
+        src.expect_get_cross_chain_proof()
+            .times(1)
+            .returning(move |_| Ok(ccp.clone()));
+        src.expect_get_system_config_by_number()
+            .returning(move |_| Ok(sys_conf.clone()));
+        src
+    }
+
+    // The destination chain is at height 10, which sends the proof by `send_raw` for `sends` times.
+    fn destination_controller(proof: Vec<u8>, sends: usize) -> MockControllerClient {
+        let data = encode_bytes_call(METHOD, &proof).unwrap();
+        let mut controller = MockControllerClient::default();
+        controller.expect_get_block_number().returning(|_| Ok(10));
+        controller
+            .expect_get_system_config()
+            .returning(|| Ok(SystemConfig::default()));
+        controller
+            .expect_send_raw()
+            .times(sends)
+            .returning(move |raw| {
+                let Some(Tx::NormalTx(utx)) = raw.tx else {
+                    panic!("relay tx should be a normal tx");
+                };
+                let tx = utx.transaction.unwrap();
+                assert_eq!(tx.to, VERIFIER.to_vec());
+                assert_eq!(tx.data, data);
+                assert_eq!(tx.valid_until_block, 105);
+                Ok(RELAY_TX_HASH)
+            });
+        controller
+    }
+
+    // The receipt of the relay tx is not found for the first `pending` polls.
+    fn destination_evm(pending: usize) -> MockEvmClient {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let mut evm = MockEvmClient::default();
+        evm.expect_get_receipt().returning(move |hash| {
+            assert_eq!(hash, RELAY_TX_HASH);
+            if polls.fetch_add(1, Ordering::SeqCst) < pending {
+                return Err(tonic::Status::not_found("receipt not found").into());
+            }
+            Ok(Receipt {
+                transaction_hash: RELAY_TX_HASH.to_vec(),
+                ..Default::default()
+            })
+        });
+        evm
+    }
+
+    fn destination(
+        signer: &MultiCryptoAccount,
+        controller: MockControllerClient,
+        evm: MockEvmClient,
+        receipt_timeout: Duration,
+    ) -> Destination<'_, MockControllerClient, MockEvmClient, MultiCryptoAccount> {
+        Destination {
+            controller,
+            evm,
+            signer,
+            verifier: VERIFIER,
+            method: METHOD,
+            quota: 3_000_000,
+            valid_until: Position::FromCurrent(95),
+            receipt_timeout,
+        }
+    }
+
+    #[test]
+    fn test_relay_tx() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let signer = MultiCryptoAccount::from(Account::<SmCrypto>::generate());
+        let ccp = sm_proof();
+        let src = source(ccp.clone());
+        let dst = destination(
+            &signer,
+            destination_controller(ccp.encode_to_vec(), 1),
+            destination_evm(1),
+            Duration::from_secs(60),
+        );
+
+        let receipt = rt
+            .block_on(PendingRelay::new(TX_HASH).relay(&src, CryptoType::Sm, &dst))
+            .unwrap();
+        assert_eq!(receipt.transaction_hash, RELAY_TX_HASH.to_vec());
+    }
+
+    #[test]
+    fn test_relay_invalid_proof() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let signer = MultiCryptoAccount::from(Account::<SmCrypto>::generate());
+        let mut ccp = sm_proof();
+        ccp.proposal
+            .as_mut()
+            .and_then(|p| p.proposal.as_mut())
+            .and_then(|b| b.header.as_mut())
+            .unwrap()
+            .transactions_root = vec![0; 32];
+        let src = source(ccp.clone());
+        let dst = destination(
+            &signer,
+            destination_controller(ccp.encode_to_vec(), 0),
+            destination_evm(0),
+            Duration::from_secs(60),
+        );
+
+        // It's reported and not retried.
+        let mut pending = vec![PendingRelay::new(TX_HASH)];
+        rt.block_on(relay_pending(&src, CryptoType::Sm, &dst, &mut pending));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_relay_receipt_timeout() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let signer = MultiCryptoAccount::from(Account::<SmCrypto>::generate());
+        let ccp = sm_proof();
+        let src = source(ccp.clone());
+        let dst = destination(
+            &signer,
+            destination_controller(ccp.encode_to_vec(), 1),
+            destination_evm(1),
+            Duration::ZERO,
+        );
+
+        let mut pending = vec![PendingRelay::new(TX_HASH)];
+        rt.block_on(relay_pending(&src, CryptoType::Sm, &dst, &mut pending));
+        // The relay tx is kept, and its receipt is polled on retry without sending another.
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].sent, Some((RELAY_TX_HASH, 105)));
+        rt.block_on(relay_pending(&src, CryptoType::Sm, &dst, &mut pending));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_check_relay_receipt() {
+        let tx_hash = [0x11; 32];
+        let receipt = Receipt {
+            transaction_hash: vec![0x22; 32],
+            ..Default::default()
+        };
+        assert!(check_relay_receipt(tx_hash, receipt.clone()).is_ok());
+
+        let reverted = Receipt {
+            error_message: "Reverted".into(),
+            ..receipt
+        };
+        let err = check_relay_receipt(tx_hash, reverted).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(RelayError::Reverted { .. })
+        ));
+    }
+
+    #[test]
+    fn test_relay_cmd() {
+        let cldi_cmd = cldi_cmd();
+        let (mut ctx, _temp_dir) = context();
+
+        let args = [
+            "--verifier",
+            "0xf587c2fa24d23175e09d36625cfc447a4b4d679b",
+            "--method",
+            "verifyProof(bytes)",
+        ];
+        // destination context not found
+        assert!(cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "relay",
+                    "--from-context",
+                    "default",
+                    "--to-context",
+                    "not-exist",
+                    "--tx",
+                    "0x74ac6372ab461de6817d7146a9b8ad17c35525b13a37f4bb0da325fbfd999f3a",
+                ]
+                .into_iter()
+                .chain(args),
+                &mut ctx,
+            )
+            .is_err());
+        // missing both `--tx` and `--watch`
+        assert!(cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "relay",
+                    "--from-context",
+                    "default",
+                    "--to-context",
+                    "default",
+                ]
+                .into_iter()
+                .chain(args),
+                &mut ctx,
+            )
+            .is_err());
+    }
+}
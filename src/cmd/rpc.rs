@@ -14,29 +14,32 @@
 
 use crate::types::clean_0x;
 use crate::{
-    cmd::{evm::store_abi, Command},
-    config::ConsensusType,
+    cmd::{evm::store_abi, Command, ExitStatus},
+    config::{ConsensusType, CryptoType},
     core::{
         context::Context,
         controller::{ControllerBehaviour, TransactionSenderBehaviour},
-        cross_chain::{self, CrossChainResultCode},
+        cross_chain::{self, CrossChainProofBundle, CrossChainResultCode},
         evm::EvmBehaviour,
         executor::ExecutorBehaviour,
+        light_client::{fetch_checkpoint, verify_child_block, CheckpointStore},
     },
-    crypto::{Address, ArrayLike, Hash},
+    crypto::{ArrayLike, EthCrypto, Hash, SmCrypto},
     display::Display,
     utils::{
-        get_block_height_at, parse_addr, parse_data, parse_hash, parse_position, parse_u64,
-        parse_value, Position,
+        get_block_height_at, hex, parse_block_range, parse_data, parse_hash, parse_position,
+        parse_u64, parse_value, Position,
     },
 };
-use anyhow::{anyhow, Context as _};
+use anyhow::{anyhow, ensure, Context as _};
 use cita_cloud_proto::controller::{CrossChainProof, SystemConfig};
+use cita_cloud_proto::evm::ReceiptProof;
 use clap::builder::ArgPredicate;
-use clap::{Arg, ArgAction};
+use clap::{Arg, ArgAction, ArgGroup};
 use prost::Message;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::try_join;
 
 pub fn call_executor<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
@@ -49,14 +52,12 @@ where
             Arg::new("from")
                 .help("default to use current account address")
                 .short('f')
-                .long("from")
-                .value_parser(parse_addr),
+                .long("from"),
         )
         .arg(
             Arg::new("to")
                 .help("the target contract address")
-                .required(true)
-                .value_parser(parse_addr),
+                .required(true),
         )
         .arg(
             Arg::new("data")
@@ -71,11 +72,11 @@ where
                 .value_parser(parse_u64),
         )
         .handler(|_cmd, m, ctx| {
-            let from = match m.get_one::<Address>("from") {
-                Some(from) => from.to_owned(),
+            let from = match m.get_one::<String>("from") {
+                Some(from) => ctx.resolve_addr(from)?,
                 None => *ctx.current_account()?.address(),
             };
-            let to = *m.get_one::<Address>("to").unwrap();
+            let to = ctx.resolve_addr(m.get_one::<String>("to").unwrap())?;
             let data = m.get_one::<Vec<u8>>("data").unwrap().to_owned();
             let height = if let Some(height) = m.get_one::<u64>("height") {
                 *height
@@ -100,8 +101,7 @@ where
         .arg(
             Arg::new("to")
                 .help("the target address of this tx")
-                .required(true)
-                .value_parser(parse_addr),
+                .required(true),
         )
         .arg(
             Arg::new("data")
@@ -134,7 +134,8 @@ where
         )
         .handler(|_cmd, m, ctx| {
             ctx.rt.block_on(async {
-                let to = m.get_one::<Address>("to").unwrap().to_vec();
+                let signer = &ctx.current_signer()?;
+                let to = ctx.resolve_addr(m.get_one::<String>("to").unwrap())?.to_vec();
                 let data = m.get_one::<Vec<u8>>("data").unwrap().to_owned();
                 let value = m.get_one::<[u8; 32]>("value").unwrap().to_vec();
                 let quota = *m.get_one::<u64>("quota").unwrap();
@@ -143,7 +144,6 @@ where
                     get_block_height_at(&ctx.controller, pos).await?
                 };
 
-                let signer = ctx.current_account()?;
                 let tx_hash = ctx
                     .controller
                     .send_tx(signer, to, data, value, quota, valid_until_block)
@@ -193,6 +193,7 @@ where
         )
         .handler(|_cmd, m, ctx| {
             ctx.rt.block_on(async {
+                let signer = &ctx.current_signer()?;
                 let to = Vec::new();
                 let data = m.get_one::<Vec<u8>>("data").unwrap().to_owned();
                 let value = m.get_one::<[u8; 32]>("value").unwrap().to_vec();
@@ -202,7 +203,6 @@ where
                     get_block_height_at(&ctx.controller, pos).await?
                 };
 
-                let signer = ctx.current_account()?;
                 let tx_hash = ctx
                     .controller
                     .send_tx(signer, to, data, value, quota, valid_until_block)
@@ -237,11 +237,11 @@ where
                     let system_config = ctx
                         .rt
                         .block_on(ctx.controller.get_system_config_by_number(height))??;
-                    println!("{}", system_config.display());
+                    println!("{}", ctx.display(&system_config));
                 }
             } else {
                 let system_config = ctx.rt.block_on(ctx.controller.get_system_config())??;
-                println!("{}", system_config.display());
+                println!("{}", ctx.display(&system_config));
             };
             Ok(())
         })
@@ -281,7 +281,7 @@ where
                 return Err(anyhow!("current_height: {}", current_height));
             } else if d {
                 let full_block = ctx.rt.block_on(ctx.controller.get_block_detail_by_number(height))??;
-                println!("{}", full_block.display());
+                println!("{}", ctx.display(&full_block));
             } else {
                 let compact_block_with_stateroot_proof = ctx.rt.block_on(ctx.controller.get_block_by_number(height))??;
                 println!("{}", compact_block_with_stateroot_proof.display());
@@ -354,7 +354,7 @@ where
                 Err(_) => (tx, u64::MAX, u64::MAX),
             };
 
-            println!("{}", tx_with_index.display());
+            println!("{}", ctx.display(&tx_with_index));
 
             Ok(())
         })
@@ -392,6 +392,13 @@ where
                 .short('o')
                 .long("output"),
         )
+        .arg(
+            Arg::new("bundle")
+                .help("Output a json bundle with the chain info and validators, which can be verified offline")
+                .long("bundle")
+                .requires("output")
+                .action(ArgAction::SetTrue),
+        )
         .handler(|_cmd, m, ctx| {
             let tx_hash = *m.get_one::<Hash>("tx_hash").unwrap();
             let c = &ctx.controller;
@@ -401,6 +408,31 @@ where
             println!("{}", cc_proof.display());
             if m.contains_id("output") {
                 let file_path = m.get_one::<String>("output").unwrap();
+                if *m.get_one::<bool>("bundle").unwrap() {
+                    let height = cc_proof
+                        .receipt_proof
+                        .as_ref()
+                        .and_then(|rp| rp.roots_info.as_ref())
+                        .map(|ri| ri.height)
+                        .ok_or_else(|| anyhow!("no roots info in the cross chain proof"))?;
+                    let sys_conf = ctx
+                        .rt
+                        .block_on(c.get_system_config_by_number(height))??;
+                    let fetched_at = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_millis() as u64;
+                    let bundle = CrossChainProofBundle::new(
+                        ctx.current_context_name.clone(),
+                        fetched_at,
+                        ctx.current_setting.crypto_type,
+                        &cc_proof,
+                        &sys_conf,
+                    );
+                    let path = format!("{}/{}-ccp.json", file_path, hex::encode(&tx_hash[..8]));
+                    std::fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+                    return Ok(());
+                }
                 let ccp_bytes = {
                     let mut buf = Vec::with_capacity(cc_proof.encoded_len());
                     cc_proof.encode(&mut buf).unwrap();
@@ -509,23 +541,21 @@ where
             Arg::new("from")
                 .help("default to use current account address")
                 .short('f')
-                .long("from")
-                .value_parser(parse_addr),
+                .long("from"),
         )
         .arg(
             Arg::new("to")
                 .help("the target contract address, default means create contract")
                 .short('t')
-                .long("to")
-                .value_parser(parse_addr),
+                .long("to"),
         )
         .handler(|_cmd, m, ctx| {
-            let from = match m.get_one::<Address>("from") {
-                Some(from) => from.to_vec(),
+            let from = match m.get_one::<String>("from") {
+                Some(from) => ctx.resolve_addr(from)?.to_vec(),
                 None => ctx.current_account()?.address().to_vec(),
             };
-            let to = match m.get_one::<Address>("to") {
-                Some(to) => to.to_vec(),
+            let to = match m.get_one::<String>("to") {
+                Some(to) => ctx.resolve_addr(to)?.to_vec(),
                 None => [0; 20].to_vec(),
             };
             let data = m.get_one::<Vec<u8>>("data").unwrap().to_owned();
@@ -548,19 +578,31 @@ where
         .subcommands([add_node(), store_abi(), parse_proof(), estimate_quota()])
 }
 
+fn crypto_type_arg() -> Arg {
+    Arg::new("crypto-type")
+        .help("The hash algorithm of the proof follows this crypto type. [default: <current-context-crypto-type>]")
+        .long("crypto")
+        .value_parser(["SM", "ETH"])
+        .ignore_case(true)
+}
+
+fn crypto_type_of<Co, Ex, Ev>(m: &clap::ArgMatches, ctx: &Context<Co, Ex, Ev>) -> CryptoType {
+    m.get_one::<String>("crypto-type")
+        .map(|s| s.parse::<CryptoType>().unwrap())
+        .unwrap_or(ctx.current_setting.crypto_type)
+}
+
 pub fn verify_cross_chain_proof<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
 where
     Co: ControllerBehaviour,
     Ev: EvmBehaviour,
 {
     Command::<Context<Co, Ex, Ev>>::new("verify-cross-chain-proof")
-        .about("Verify cross chain proof")
+        .about("Verify cross chain proofs. Exit with status `0` if all are fully verified, `2` if some are verified without chain info or votes, `3` if any fails")
         .arg(
             Arg::new("file-path")
                 .long("file")
                 .short('f')
-                .conflicts_with("proof-data")
-                .required_unless_present("proof-data")
                 .help("file path of cross chain proof file"),
         )
         .arg(
@@ -569,6 +611,27 @@ where
                 .short('d')
                 .help("input proof data on hex form with 0x prefix"),
         )
+        .arg(
+            Arg::new("bundle")
+                .long("bundle")
+                .short('b')
+                .help("file path of a cross chain proof bundle, which is verified offline"),
+        )
+        .arg(
+            Arg::new("dir")
+                .long("dir")
+                .help("verify all the cross chain proof files in this dir, bundles are verified offline"),
+        )
+        .arg(
+            Arg::new("glob")
+                .long("glob")
+                .help("verify all the cross chain proof files matching this pattern, e.g. `proofs/*-ccp`"),
+        )
+        .group(
+            ArgGroup::new("input")
+                .args(["file-path", "proof-data", "bundle", "dir", "glob"])
+                .required(true),
+        )
         .arg(
             Arg::new("validators")
                 .long("validators")
@@ -588,75 +651,380 @@ where
                 .requires_if(ArgPredicate::IsPresent, "version")
                 .help("input chain id"),
         )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("output one compact json result per line")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(crypto_type_arg())
         .handler(|_cmd, m, ctx| {
-            let ccp_bytes = if m.contains_id("file-path") {
-                let file_path = m.get_one::<String>("file-path").unwrap();
-                let mut file = File::options().read(true).open(file_path).unwrap();
-                let mut bytes = Vec::new();
-                file.read_to_end(&mut bytes).unwrap();
-                bytes
-            } else {
-                let proof_data = m.get_one::<String>("proof-data").unwrap();
-                hex::decode(clean_0x(proof_data)).unwrap()
-            };
-            let cc_proof = CrossChainProof::decode(ccp_bytes.as_slice()).unwrap();
+            // (file name, proof bytes)
+            let mut inputs: Vec<(Option<String>, Result<ProofInput, CrossChainResultCode>)> =
+                Vec::new();
+            if let Some(file_path) = m.get_one::<String>("file-path") {
+                inputs.push((Some(file_path.clone()), read_proof_file(file_path)));
+            } else if let Some(bundle_path) = m.get_one::<String>("bundle") {
+                let bundle = read_proof_file(bundle_path).and_then(|input| match input {
+                    ProofInput::Bundle(_) => Ok(input),
+                    ProofInput::Raw(_) => Err(CrossChainResultCode::DecodeError(format!(
+                        "`{bundle_path}` is not a cross chain proof bundle"
+                    ))),
+                });
+                inputs.push((Some(bundle_path.clone()), bundle));
+            } else if let Some(proof_data) = m.get_one::<String>("proof-data") {
+                let bytes = hex::decode(clean_0x(proof_data))
+                    .map(ProofInput::Raw)
+                    .map_err(|e| CrossChainResultCode::DecodeError(format!("proof data: {e}")));
+                inputs.push((None, bytes));
+            } else if let Some(dir) = m.get_one::<String>("dir") {
+                let mut paths = std::fs::read_dir(dir)
+                    .with_context(|| format!("cannot read dir `{dir}`"))?
+                    .map(|entry| entry.map(|e| e.path()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                paths.retain(|p| p.is_file());
+                paths.sort();
+                for path in paths {
+                    let path = path.display().to_string();
+                    let bytes = read_proof_file(&path);
+                    inputs.push((Some(path), bytes));
+                }
+            } else if let Some(pattern) = m.get_one::<String>("glob") {
+                for path in glob::glob(pattern).context("invalid glob pattern")? {
+                    let path = path?;
+                    if path.is_file() {
+                        let path = path.display().to_string();
+                        let bytes = read_proof_file(&path);
+                        inputs.push((Some(path), bytes));
+                    }
+                }
+            }
 
-            let mut online = false;
-            let sys_conf = if m.contains_id("version") {
-                let version = *m.get_one::<u32>("version").unwrap();
+            let sys_conf = if let Some(version) = m.get_one::<u32>("version").copied() {
                 let chain_id = clean_0x(m.get_one::<String>("chain-id").unwrap());
                 let sys_conf = SystemConfig {
                     version,
-                    chain_id: hex::decode(chain_id).unwrap(),
+                    chain_id: hex::decode(chain_id).context("invalid chain id")?,
                     ..Default::default()
                 };
                 Some(sys_conf)
-            } else if let Some(ref receipt_proof) = cc_proof.receipt_proof {
-                if let Some(ref roots_info) = receipt_proof.roots_info {
-                    let height = roots_info.height;
-                    if let Ok(Ok(sys_conf)) = ctx
-                        .rt
-                        .block_on(ctx.controller.get_system_config_by_number(height))
-                    {
-                        online = true;
-                        Some(sys_conf)
-                    } else {
-                        None
-                    }
-                } else {
-                    println!("{}", CrossChainResultCode::NoneRootsInfo.display());
-                    return Ok(());
-                }
             } else {
-                println!("{}", CrossChainResultCode::NoneReceiptProof.display());
-                return Ok(());
+                None
             };
+            let validators = m.get_one::<String>("validators").map(|validators| {
+                validators
+                    .split(',')
+                    .map(|s| s.to_owned())
+                    .collect::<Vec<_>>()
+            });
+            let crypto_type = crypto_type_of(m, ctx);
+            let is_json = *m.get_one::<bool>("json").unwrap();
 
-            let validators = if m.contains_id("validators") {
-                let validators = m.get_one::<String>("validators").unwrap();
-                let validator_vec = validators.split(',').map(|s| s.to_owned()).collect();
-                Some(validator_vec)
-            } else if online {
-                Some(
-                    sys_conf
-                        .clone()
-                        .unwrap()
-                        .validators
-                        .iter()
-                        .map(hex::encode)
-                        .collect(),
-                )
+            let mut status = ExitStatus::SUCCESS;
+            for (file, input) in inputs {
+                let code = match input {
+                    Ok(ProofInput::Bundle(bundle)) => match bundle.verify() {
+                        Ok(_) => CrossChainResultCode::Success,
+                        Err(code) => code,
+                    },
+                    Ok(ProofInput::Raw(ccp_bytes)) => verify_ccp(
+                        ctx,
+                        &ccp_bytes,
+                        crypto_type,
+                        validators.clone(),
+                        sys_conf.clone(),
+                    ),
+                    Err(code) => code,
+                };
+                status = status.max(exit_status_of(&code));
+
+                let mut result = code.to_json();
+                if let Some(file) = file {
+                    result["file"] = file.into();
+                }
+                if is_json {
+                    println!("{result}");
+                } else {
+                    println!("{}", result.display());
+                }
+            }
+
+            status.into_result()
+        })
+}
+
+fn exit_status_of(code: &CrossChainResultCode) -> ExitStatus {
+    match code {
+        CrossChainResultCode::Success => ExitStatus::SUCCESS,
+        code if code.is_success() => ExitStatus::PARTIAL_SUCCESS,
+        _ => ExitStatus::FAILURE,
+    }
+}
+
+enum ProofInput {
+    Raw(Vec<u8>),
+    Bundle(Box<CrossChainProofBundle>),
+}
+
+// Bundles are told apart from raw proofs by content.
+fn read_proof_file(path: &str) -> Result<ProofInput, CrossChainResultCode> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        CrossChainResultCode::DecodeError(format!("cannot read proof file `{path}`: {e}"))
+    })?;
+    match serde_json::from_slice::<CrossChainProofBundle>(&bytes) {
+        Ok(bundle) => Ok(ProofInput::Bundle(Box::new(bundle))),
+        Err(_) => Ok(ProofInput::Raw(bytes)),
+    }
+}
+
+// Use the chain info and validators provided, or fetch them from the chain.
+fn verify_ccp<Co, Ex, Ev>(
+    ctx: &Context<Co, Ex, Ev>,
+    ccp_bytes: &[u8],
+    crypto_type: CryptoType,
+    validators: Option<Vec<String>>,
+    sys_conf: Option<SystemConfig>,
+) -> CrossChainResultCode
+where
+    Co: ControllerBehaviour,
+{
+    let cc_proof = match CrossChainProof::decode(ccp_bytes) {
+        Ok(cc_proof) => cc_proof,
+        Err(e) => return CrossChainResultCode::DecodeError(format!("cross chain proof: {e}")),
+    };
+
+    let mut online = false;
+    let sys_conf = if sys_conf.is_some() {
+        sys_conf
+    } else if let Some(ref receipt_proof) = cc_proof.receipt_proof {
+        if let Some(ref roots_info) = receipt_proof.roots_info {
+            let height = roots_info.height;
+            if let Ok(Ok(sys_conf)) = ctx
+                .rt
+                .block_on(ctx.controller.get_system_config_by_number(height))
+            {
+                online = true;
+                Some(sys_conf)
             } else {
                 None
+            }
+        } else {
+            return CrossChainResultCode::NoneRootsInfo;
+        }
+    } else {
+        return CrossChainResultCode::NoneReceiptProof;
+    };
+
+    let validators = if validators.is_some() {
+        validators
+    } else if online {
+        Some(
+            sys_conf
+                .clone()
+                .unwrap()
+                .validators
+                .iter()
+                .map(hex::encode)
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let result = match crypto_type {
+        CryptoType::Sm => {
+            cross_chain::verify_cross_chain_proof::<SmCrypto>(cc_proof, validators, sys_conf)
+        }
+        CryptoType::Eth => {
+            cross_chain::verify_cross_chain_proof::<EthCrypto>(cc_proof, validators, sys_conf)
+        }
+    };
+    match result {
+        Ok(_) => CrossChainResultCode::Success,
+        Err(code) => code,
+    }
+}
+
+pub fn verify_receipt_proof<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Ev: EvmBehaviour,
+{
+    Command::<Context<Co, Ex, Ev>>::new("verify-receipt-proof")
+        .about("Verify the receipt proof of a transaction")
+        .arg(
+            Arg::new("tx_hash")
+                .help("the tx hash that the receipt should belong to, required if the proof is fetched from the node")
+                .required_unless_present_any(["file-path", "proof-data"])
+                .value_parser(parse_hash),
+        )
+        .arg(
+            Arg::new("file-path")
+                .long("file")
+                .short('f')
+                .conflicts_with("proof-data")
+                .help("file path of a saved receipt proof, e.g. from `get receipt-proof -o`"),
+        )
+        .arg(
+            Arg::new("proof-data")
+                .long("data")
+                .short('d')
+                .help("input receipt proof data on hex form with 0x prefix")
+                .value_parser(parse_data),
+        )
+        .arg(crypto_type_arg())
+        .handler(|_cmd, m, ctx| {
+            let tx_hash = m.get_one::<Hash>("tx_hash").copied();
+            let receipt_proof = if let Some(file_path) = m.get_one::<String>("file-path") {
+                let bytes = std::fs::read(file_path)
+                    .with_context(|| format!("cannot read receipt proof file `{file_path}`"))?;
+                ReceiptProof::decode(bytes.as_slice()).context("invalid receipt proof")?
+            } else if let Some(proof_data) = m.get_one::<Vec<u8>>("proof-data") {
+                ReceiptProof::decode(proof_data.as_slice()).context("invalid receipt proof")?
+            } else {
+                // tx_hash is required in this case
+                ctx.rt
+                    .block_on(ctx.evm.get_receipt_proof(tx_hash.unwrap()))??
             };
 
-            match cross_chain::verify_cross_chain_proof(cc_proof, validators, sys_conf) {
-                Ok(_) => {
-                    let code = CrossChainResultCode::Success;
-                    println!("{}", code.display());
+            let tx_hash = tx_hash.as_ref().map(|h| h.as_slice());
+            let result = match crypto_type_of(m, ctx) {
+                CryptoType::Sm => {
+                    cross_chain::verify_receipt_proof::<SmCrypto>(&receipt_proof, tx_hash)
                 }
-                Err(code) => println!("{}", code.display()),
-            }
+                CryptoType::Eth => {
+                    cross_chain::verify_receipt_proof::<EthCrypto>(&receipt_proof, tx_hash)
+                }
+            };
+            let result = match result {
+                Ok(receipt) => {
+                    let mut result = CrossChainResultCode::Success.to_json();
+                    result["transaction_hash"] = hex(receipt.transaction_hash.as_bytes()).into();
+                    if let Some(roots_info) = &receipt_proof.roots_info {
+                        result["roots_info"] = roots_info.to_json();
+                    }
+                    result
+                }
+                Err(code) => code.to_json(),
+            };
+            println!("{}", result.display());
+
+            Ok(())
+        })
+}
+
+pub fn verify_block<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Co: ControllerBehaviour + Send + Sync,
+{
+    Command::<Context<Co, Ex, Ev>>::new("verify-block")
+        .about("Verify the header chain and consensus proofs of blocks without trusting the node")
+        .arg(
+            Arg::new("range")
+                .help("the height or the inclusive range of blocks to verify, e.g. `100`, `100..200`, `-10..+0`. [default: the latest block]")
+                .value_parser(parse_block_range),
+        )
+        .arg(
+            Arg::new("checkpoint")
+                .help("continue from the saved checkpoint of this chain, and save the last verified block as the new checkpoint")
+                .long("checkpoint")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("trust-hash")
+                .help("the hash of the block right before the range, which is trusted as the starting point if there is no saved checkpoint")
+                .long("trust-hash")
+                .value_parser(parse_hash),
+        )
+        .arg(crypto_type_arg())
+        .handler(|_cmd, m, ctx| {
+            let range = m.get_one::<(Position, Position)>("range").copied();
+            let trust_hash = m.get_one::<Hash>("trust-hash").copied();
+            let crypto_type = crypto_type_of(m, ctx);
+            let use_checkpoint = *m.get_one::<bool>("checkpoint").unwrap();
+            let store = CheckpointStore::open(&ctx.config.data_dir)?;
+
+            ctx.rt.block_on(async {
+                let current = ctx.controller.get_block_number(false).await?;
+                let (begin, end) = match range {
+                    Some((begin, end)) => (begin.with_current(current), end.with_current(current)),
+                    None => (current, current),
+                };
+                ensure!(begin <= end, "invalid block range `{begin}..{end}`");
+                ensure!(
+                    end <= current,
+                    "block `{end}` is beyond the current height `{current}`"
+                );
+
+                let chain_id = ctx.controller.get_system_config().await?.chain_id;
+                let saved = if use_checkpoint {
+                    store.load(&chain_id)?
+                } else {
+                    None
+                };
+                // Genesis block has no proof, it can only be trusted.
+                let mut parent = match saved {
+                    Some(checkpoint) if checkpoint.height >= end => {
+                        println!(
+                            "blocks up to `{}` have been verified, see the checkpoint",
+                            checkpoint.height
+                        );
+                        return anyhow::Ok(());
+                    }
+                    Some(checkpoint) => checkpoint,
+                    None => {
+                        let height = begin.max(1) - 1;
+                        let anchor = match crypto_type {
+                            CryptoType::Sm => {
+                                fetch_checkpoint::<SmCrypto, _>(&ctx.controller, height).await?
+                            }
+                            CryptoType::Eth => {
+                                fetch_checkpoint::<EthCrypto, _>(&ctx.controller, height).await?
+                            }
+                        };
+                        match trust_hash {
+                            Some(trusted) => ensure!(
+                                anchor.block_hash == trusted.as_slice(),
+                                "block `{height}` from the node has hash `{}`, not the trusted `{}`",
+                                hex(&anchor.block_hash),
+                                hex(trusted.as_slice()),
+                            ),
+                            // Don't save what is verified against an untrusted anchor.
+                            None => {
+                                ensure!(
+                                    !use_checkpoint,
+                                    "no saved checkpoint of this chain, give the hash of block `{height}` by `--trust-hash` to start from"
+                                );
+                                println!(
+                                    "WARNING: block `{height}` is trusted as the node returns it, give its hash by `--trust-hash` to verify against your own anchor"
+                                );
+                            }
+                        }
+                        anchor
+                    }
+                };
+
+                while parent.height < end {
+                    let verified = match crypto_type {
+                        CryptoType::Sm => {
+                            verify_child_block::<SmCrypto, _>(&ctx.controller, &parent).await?
+                        }
+                        CryptoType::Eth => {
+                            verify_child_block::<EthCrypto, _>(&ctx.controller, &parent).await?
+                        }
+                    };
+                    println!(
+                        "block `{}` verified, hash: `{}`",
+                        verified.height,
+                        hex(&verified.block_hash)
+                    );
+                    if use_checkpoint {
+                        store.save(&chain_id, &verified)?;
+                    }
+                    parent = verified;
+                }
+
+                anyhow::Ok(())
+            })??;
 
             Ok(())
         })
@@ -671,20 +1039,28 @@ where
     Command::<Context<Co, Ex, Ev>>::new("verify")
         .about("Verify commands")
         .subcommand_required_else_help(true)
-        .subcommands([verify_cross_chain_proof()
-            .name("cross-chain-proof")
-            .alias("ccp")])
+        .subcommands([
+            verify_cross_chain_proof()
+                .name("cross-chain-proof")
+                .alias("ccp"),
+            verify_receipt_proof().name("receipt-proof").alias("rp"),
+            verify_block().name("block"),
+        ])
 }
 
 #[cfg(test)]
 mod tests {
-    use cita_cloud_proto::blockchain::{Block, RawTransaction};
+    use cita_cloud_proto::blockchain::{Block, BlockHeader, CompactBlock, RawTransaction};
+    use cita_cloud_proto::common::{Proof, StateRoot};
     use cita_cloud_proto::controller::{BlockNumber, SystemConfig};
+    use cita_cloud_proto::evm::RootsInfo;
     use cita_cloud_proto::executor::CallResponse;
 
+    use serde_json::json;
+
     use super::*;
     use crate::cmd::cldi_cmd;
-    use crate::core::controller::ProofWithValidators;
+    use crate::core::controller::{CompactBlockWithStaterootProof, ProofWithValidators};
     use crate::core::mock::context;
 
     #[test]
@@ -826,4 +1202,193 @@ mod tests {
             .exec_from(["cldi", "rpc", "parse-proof", "0xf8880280a0851d013b28aa0dbabd0558593a808accd9c6bcf8b41194d9c341fe0001cf6112f863b860948bec9fa600d1a4dd20d60ea679c9e45af2ab22d915ff251d47816b126a1450ea1270cad8a16df198a8870937efa79e09c60ba70acf12ebf0a08888a5edfeeeb34ca98ba30f869a99fa3302ba7d61ea2c966c8e35e32b88a447d2182e22dbcf70"], &mut ctx)
             .unwrap();
     }
+
+    #[test]
+    fn test_verify_receipt_proof() {
+        let cldi_cmd = cldi_cmd();
+        let (mut ctx, _temp_dir) = context();
+
+        ctx.evm
+            .expect_get_receipt_proof()
+            .returning(|_| Ok(ReceiptProof::default()));
+
+        cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "verify",
+                    "receipt-proof",
+                    "0x74ac6372ab461de6817d7146a9b8ad17c35525b13a37f4bb0da325fbfd999f3a",
+                ],
+                &mut ctx,
+            )
+            .unwrap();
+        cldi_cmd
+            .exec_from(["cldi", "verify", "rp", "-d", "0x"], &mut ctx)
+            .unwrap();
+        // neither tx hash nor saved proof
+        assert!(cldi_cmd
+            .exec_from(["cldi", "verify", "rp"], &mut ctx)
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_cross_chain_proofs() {
+        let cldi_cmd = cldi_cmd();
+        let (mut ctx, temp_dir) = context();
+
+        let proofs_dir = temp_dir.path().join("proofs");
+        std::fs::create_dir(&proofs_dir).unwrap();
+        std::fs::write(proofs_dir.join("bad-ccp"), [0xff, 0xff]).unwrap();
+        // a root of the wrong length must not crash the batch
+        let short_root = CrossChainProof {
+            proposal: Some(Default::default()),
+            receipt_proof: Some(ReceiptProof {
+                receipt_proof: vec![0xc0],
+                roots_info: Some(RootsInfo {
+                    height: 1,
+                    state_root: vec![0; 4],
+                    receipt_root: vec![0x12; 4],
+                }),
+                ..Default::default()
+            }),
+            state_root: vec![0x12; 4],
+            ..Default::default()
+        };
+        std::fs::write(
+            proofs_dir.join("short-root-ccp"),
+            short_root.encode_to_vec(),
+        )
+        .unwrap();
+        let proofs_dir = proofs_dir.to_str().unwrap();
+
+        let err = cldi_cmd
+            .exec_from(
+                ["cldi", "verify", "ccp", "--json", "--dir", proofs_dir],
+                &mut ctx,
+            )
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<ExitStatus>(), Some(&ExitStatus::FAILURE));
+
+        let pattern = format!("{proofs_dir}/*-ccp");
+        let err = cldi_cmd
+            .exec_from(["cldi", "verify", "ccp", "--glob", &pattern], &mut ctx)
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<ExitStatus>(), Some(&ExitStatus::FAILURE));
+
+        let bundle = json!({
+            "bundle_version": 1,
+            "context": "default",
+            "fetched_at": 0,
+            "crypto_type": "SM",
+            "version": 0,
+            "chain_id": "0x1234",
+            "validators": [],
+            "proof": "0xffff",
+        });
+        let bundle_path = temp_dir.path().join("bundle-ccp.json");
+        std::fs::write(&bundle_path, bundle.to_string()).unwrap();
+        let err = cldi_cmd
+            .exec_from(
+                ["cldi", "verify", "ccp", "-b", bundle_path.to_str().unwrap()],
+                &mut ctx,
+            )
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<ExitStatus>(), Some(&ExitStatus::FAILURE));
+        // raw proofs are not bundles
+        let err = cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "verify",
+                    "ccp",
+                    "-b",
+                    &format!("{proofs_dir}/bad-ccp"),
+                ],
+                &mut ctx,
+            )
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<ExitStatus>(), Some(&ExitStatus::FAILURE));
+
+        // invalid hex is reported rather than panicking
+        let err = cldi_cmd
+            .exec_from(["cldi", "verify", "ccp", "-d", "0xzz"], &mut ctx)
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<ExitStatus>(), Some(&ExitStatus::FAILURE));
+    }
+
+    #[test]
+    fn test_verify_block() {
+        let cldi_cmd = cldi_cmd();
+        let (mut ctx, _temp_dir) = context();
+
+        ctx.controller
+            .expect_get_block_number()
+            .returning(|_| Ok(100u64));
+        ctx.controller
+            .expect_get_system_config()
+            .returning(|| Ok(SystemConfig::default()));
+        ctx.controller.expect_get_block_by_number().returning(|_| {
+            Ok(CompactBlockWithStaterootProof {
+                compact_block: CompactBlock::default(),
+                state_root: StateRoot::default(),
+                proof: Proof::default(),
+            })
+        });
+
+        // blocks without header can't be verified
+        assert!(cldi_cmd
+            .exec_from(["cldi", "verify", "block", "99..100"], &mut ctx)
+            .is_err());
+        // beyond the current height
+        assert!(cldi_cmd
+            .exec_from(["cldi", "verify", "block", "101"], &mut ctx)
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_block_anchor() {
+        let cldi_cmd = cldi_cmd();
+        let (mut ctx, _temp_dir) = context();
+
+        ctx.controller
+            .expect_get_block_number()
+            .returning(|_| Ok(100u64));
+        ctx.controller
+            .expect_get_system_config()
+            .returning(|| Ok(SystemConfig::default()));
+        ctx.controller.expect_get_block_by_number().returning(|h| {
+            Ok(CompactBlockWithStaterootProof {
+                compact_block: CompactBlock {
+                    header: Some(BlockHeader {
+                        height: h,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                state_root: StateRoot::default(),
+                proof: Proof::default(),
+            })
+        });
+
+        // an unverified anchor is never saved as a checkpoint
+        let err = cldi_cmd
+            .exec_from(["cldi", "verify", "block", "100", "--checkpoint"], &mut ctx)
+            .unwrap_err();
+        assert!(err.to_string().contains("--trust-hash"));
+        let err = cldi_cmd
+            .exec_from(
+                [
+                    "cldi",
+                    "verify",
+                    "block",
+                    "100",
+                    "--trust-hash",
+                    "0x0000000000000000000000000000000000000000000000000000000000000000",
+                ],
+                &mut ctx,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("not the trusted"));
+    }
 }
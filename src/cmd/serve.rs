@@ -0,0 +1,231 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{ensure, Context as _};
+use clap::Arg;
+use ethereum_types::U256;
+use std::net::SocketAddr;
+
+use crate::{
+    cmd::Command,
+    config::CryptoType,
+    core::{
+        context::Context, controller::ControllerBehaviour, evm::EvmBehaviour,
+        executor::ExecutorBehaviour, fake_node::FakeNode, jsonrpc::Gateway,
+    },
+    crypto::{EthCrypto, SmCrypto},
+    utils::{hex, parse_value},
+};
+
+pub fn fake_node<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>> {
+    Command::<Context<Co, Ex, Ev>>::new("fake-node")
+        .about("Serve an in-memory fake node for testing, with current account as its admin")
+        .arg(
+            Arg::new("listen")
+                .help("the address to serve controller, executor and evm services. Use it for both `-r` and `-e`")
+                .short('l')
+                .long("listen")
+                .default_value("127.0.0.1:50004")
+                .value_parser(str::parse::<SocketAddr>),
+        )
+        .arg(
+            Arg::new("block-interval")
+                .help("the initial block interval in seconds")
+                .long("block-interval")
+                .default_value("3")
+                .value_parser(str::parse::<u32>),
+        )
+        .arg(
+            Arg::new("balance")
+                .help("the initial balance of the admin")
+                .long("balance")
+                .default_value("0xffffffffffffffffffffffff")
+                .value_parser(parse_value),
+        )
+        .handler(|_cmd, m, ctx| {
+            let listen = *m.get_one::<SocketAddr>("listen").unwrap();
+            let block_interval = *m.get_one::<u32>("block-interval").unwrap();
+            let balance = U256::from_big_endian(m.get_one::<[u8; 32]>("balance").unwrap());
+            let admin = *ctx.current_account()?.address();
+
+            let (listen, server) = ctx.rt.block_on(async {
+                match ctx.current_setting.crypto_type {
+                    CryptoType::Sm => {
+                        FakeNode::<SmCrypto>::new(admin)
+                            .with_balance(admin, balance)
+                            .with_block_interval(block_interval)
+                            .spawn(listen)
+                            .await
+                    }
+                    CryptoType::Eth => {
+                        FakeNode::<EthCrypto>::new(admin)
+                            .with_balance(admin, balance)
+                            .with_block_interval(block_interval)
+                            .spawn(listen)
+                            .await
+                    }
+                }
+            })??;
+            println!(
+                "fake node of admin `{}` listening on `{listen}`, connect with `cldi -r {listen} -e {listen}`",
+                hex(&admin)
+            );
+            ctx.rt.block_on(server)???;
+
+            Ok(())
+        })
+}
+
+pub fn jsonrpc<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Co: ControllerBehaviour + Clone + Send + Sync + 'static,
+    Ex: ExecutorBehaviour + Clone + Send + Sync + 'static,
+    Ev: EvmBehaviour + Clone + Send + Sync + 'static,
+{
+    Command::<Context<Co, Ex, Ev>>::new("jsonrpc")
+        .about("Serve an Ethereum JSON-RPC gateway to the current context's chain")
+        .arg(
+            Arg::new("listen")
+                .help("the address to serve JSON-RPC over HTTP")
+                .short('l')
+                .long("listen")
+                .default_value("127.0.0.1:8545")
+                .value_parser(str::parse::<SocketAddr>),
+        )
+        .handler(|_cmd, m, ctx| {
+            let listen = *m.get_one::<SocketAddr>("listen").unwrap();
+            // Each tx of a protected context asks for confirmation on stdin, which would
+            // block the gateway.
+            ensure!(
+                !ctx.current_setting.policy.protected,
+                "context `{}` is protected, which confirms every tx interactively and cannot serve the JSON-RPC gateway",
+                ctx.current_context_name
+            );
+            let gateway = Gateway::new(
+                ctx.controller.clone(),
+                ctx.executor.clone(),
+                ctx.evm.clone(),
+            );
+
+            ctx.rt.block_on(async {
+                let listener = tokio::net::TcpListener::bind(listen)
+                    .await
+                    .with_context(|| format!("cannot listen on `{listen}`"))?;
+                println!("JSON-RPC gateway listening on `http://{listen}`");
+                gateway.serve(listener).await
+            })??;
+
+            Ok(())
+        })
+}
+
+pub fn serve_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
+where
+    Co: ControllerBehaviour + Clone + Send + Sync + 'static,
+    Ex: ExecutorBehaviour + Clone + Send + Sync + 'static,
+    Ev: EvmBehaviour + Clone + Send + Sync + 'static,
+{
+    Command::<Context<Co, Ex, Ev>>::new("serve")
+        .about("Serve services backed by cldi")
+        .subcommand_required_else_help(true)
+        .subcommands([fake_node(), jsonrpc()])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ethereum_types::U256;
+
+    use crate::cmd::cldi_cmd;
+    use crate::config::{Config, ContextSetting};
+    use crate::core::{
+        context::Context,
+        controller::ControllerClient,
+        evm::{EvmBehaviour, EvmClient},
+        executor::ExecutorClient,
+        fake_node::FakeNode,
+        mock::context,
+        wallet::Account,
+    };
+    use crate::crypto::SmCrypto;
+
+    #[test]
+    fn test_serve_cmd() {
+        let cldi_cmd = cldi_cmd();
+        let (mut ctx, _temp_dir) = context();
+
+        // invalid listen address
+        assert!(cldi_cmd
+            .exec_from(
+                ["cldi", "serve", "fake-node", "--listen", "localhost"],
+                &mut ctx
+            )
+            .is_err());
+
+        // A protected context would block the gateway on confirming txs.
+        ctx.current_setting.policy.protected = true;
+        let e = cldi_cmd
+            .exec_from(
+                ["cldi", "serve", "jsonrpc", "--listen", "127.0.0.1:0"],
+                &mut ctx,
+            )
+            .unwrap_err();
+        assert!(e.to_string().contains("is protected"));
+    }
+
+    #[test]
+    fn test_send_to_fake_node() {
+        let node_rt = tokio::runtime::Runtime::new().unwrap();
+        let admin = Account::<SmCrypto>::generate();
+        // Produce blocks manually.
+        let node = FakeNode::<SmCrypto>::new(*admin.address())
+            .with_balance(*admin.address(), U256::from(1000u64))
+            .with_block_interval(3600);
+        let (addr, _server) = node_rt
+            .block_on(node.clone().spawn("127.0.0.1:0".parse().unwrap()))
+            .unwrap();
+
+        let test_dir = tempfile::tempdir().unwrap();
+        let addr = addr.to_string();
+        let setting = ContextSetting {
+            controller_addr: addr.clone(),
+            executor_addr: addr.clone(),
+            evm_addr: addr,
+            ..Default::default()
+        };
+        let config = Config {
+            data_dir: test_dir.path().to_path_buf(),
+            context_settings: BTreeMap::from([("default".into(), setting)]),
+            ..Default::default()
+        };
+        let mut ctx: Context<ControllerClient, ExecutorClient, EvmClient> =
+            Context::from_config(config, &[]).unwrap();
+        ctx.wallet.save("default".into(), admin).unwrap();
+
+        let cldi_cmd = cldi_cmd();
+        let to = "0x0101010101010101010101010101010101010101";
+        cldi_cmd
+            .exec_from(["cldi", "send", "-v", "0x64", to], &mut ctx)
+            .unwrap();
+        node.produce_block();
+
+        let balance = ctx
+            .rt
+            .block_on(ctx.evm.get_balance([1; 20], Default::default()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(U256::from_big_endian(&balance.value), U256::from(100u64));
+    }
+}
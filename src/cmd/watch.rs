@@ -12,16 +12,42 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::bail;
+use anyhow::{bail, Result};
 use clap::Arg;
-use std::time::Duration;
+use std::{future::Future, time::Duration};
 
 use crate::{
     cmd::Command,
-    core::{context::Context, controller::ControllerBehaviour},
+    config::RetrySetting,
+    core::{
+        context::Context,
+        controller::ControllerBehaviour,
+        endpoints::{backoff, is_transient_error},
+    },
     utils::{parse_position, Position},
 };
 
+// Watching may outlive a restart of the node, so transient errors are retried forever
+// with the context's backoff, beyond the client's own attempts. Others are returned.
+async fn retry_transient<T, F, Fut>(retry: &RetrySetting, what: &str, mut f: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0u32;
+    loop {
+        match f().await {
+            Ok(v) => return Ok(v),
+            Err(e) if is_transient_error(&e) => {
+                attempt = attempt.saturating_add(1);
+                eprintln!("{what}, will retry: {e:#}");
+                tokio::time::sleep(backoff(retry, attempt)).await;
+            }
+            Err(e) => return Err(e.context(what.to_string())),
+        }
+    }
+}
+
 pub fn watch_cmd<'help, Co, Ex, Ev>() -> Command<'help, Context<Co, Ex, Ev>>
 where
     Co: ControllerBehaviour,
@@ -62,8 +88,12 @@ where
             let mut finalized_txs = 0;
             let mut total_secs = 0;
 
+            let retry = ctx.current_setting.retry;
             let watch_result = ctx.rt.block_on(async {
-                let current_height = ctx.controller.get_block_number(false).await?;
+                let current_height = retry_transient(&retry, "failed to get current height", || {
+                    ctx.controller.get_block_number(false)
+                })
+                .await?;
 
                 let begin = m
                     .get_one::<Position>("begin")
@@ -81,31 +111,22 @@ where
                 let mut h = begin;
 
                 let mut check_interval = tokio::time::interval(Duration::from_millis(500));
-                let mut retry_interval = tokio::time::interval(Duration::from_secs(3));
                 let mut begin_time = None;
                 let mut empty_block_num = 0;
 
             'outter:
                 while h <= end {
                     check_interval.tick().await;
-                    let current_height = match ctx.controller.get_block_number(false).await {
-                        Ok(current) => current,
-                        Err(e) => {
-                            println!("failed to get current height: `{e}`");
-                            retry_interval.tick().await;
-                            continue;
-                        }
-                    };
+                    let current_height = retry_transient(&retry, "failed to get current height", || {
+                        ctx.controller.get_block_number(false)
+                    })
+                    .await?;
 
                     while h <= std::cmp::min(current_height, end) {
-                        let block = match ctx.controller.get_compact_block_by_number(h).await {
-                            Ok(block) => block,
-                            Err(e) => {
-                                println!("failed to get block `{h}`: `{e}`");
-                                retry_interval.tick().await;
-                                continue;
-                            },
-                        };
+                        let block = retry_transient(&retry, &format!("failed to get block `{h}`"), || {
+                            ctx.controller.get_compact_block_by_number(h)
+                        })
+                        .await?;
                         match (block.header, block.body) {
                             (Some(header), Some(body)) => {
                                 let height = header.height;
@@ -188,7 +209,7 @@ where
 #[cfg(test)]
 mod tests {
 
-    use cita_cloud_proto::blockchain::CompactBlock;
+    use cita_cloud_proto::blockchain::{BlockHeader, CompactBlock, CompactBlockBody};
 
     use crate::cmd::cldi_cmd;
     use crate::core::mock::context;
@@ -215,4 +236,52 @@ mod tests {
             )
             .unwrap();
     }
+
+    #[test]
+    fn test_watch_retry() {
+        let cldi_cmd = cldi_cmd();
+        let (mut ctx, _temp_dir) = context();
+        ctx.current_setting.retry.initial_backoff_ms = 1;
+        ctx.current_setting.retry.max_backoff_ms = 1;
+
+        // The node is down for a while in the middle of watching.
+        let mut calls = 0;
+        ctx.controller
+            .expect_get_block_number()
+            .returning(move |_| {
+                calls += 1;
+                if (2..6).contains(&calls) {
+                    Err(tonic::Status::unavailable("node restarting").into())
+                } else {
+                    Ok(2)
+                }
+            });
+        ctx.controller
+            .expect_get_compact_block_by_number()
+            .returning(|h| {
+                Ok(CompactBlock {
+                    header: Some(BlockHeader {
+                        height: h,
+                        ..Default::default()
+                    }),
+                    body: Some(CompactBlockBody {
+                        tx_hashes: vec![vec![0; 32]],
+                    }),
+                    ..Default::default()
+                })
+            });
+        cldi_cmd
+            .exec_from(["cldi", "watch", "-b", "1", "-e", "2"], &mut ctx)
+            .unwrap();
+
+        // Not worth retrying.
+        ctx.controller.checkpoint();
+        ctx.controller
+            .expect_get_block_number()
+            .times(1)
+            .returning(|_| Err(tonic::Status::invalid_argument("bad request").into()));
+        assert!(cldi_cmd
+            .exec_from(["cldi", "watch", "-b", "1", "-e", "2"], &mut ctx)
+            .is_err());
+    }
 }
@@ -19,13 +19,22 @@ use std::path::PathBuf;
 use std::str::FromStr;
 
 use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context as _;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use crate::utils::safe_save;
+use crate::utils::{parse_addr, parse_value, safe_save};
 
 pub const CLOUD_CLI_CONFIG_FILE_NAME: &str = "config.toml";
 pub const CLOUD_CLI_DATA_DIR_NAME: &str = ".cloud-cli";
+/// Overrides the data dir, which defaults to `~/.cloud-cli`.
+pub const CLOUD_CLI_DATA_DIR_ENV: &str = "CLDI_DATA_DIR";
+/// Project-local overrides, discovered from the current dir upwards.
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".cldi.toml";
+
+/// Bump this and add a migration step when the config format changes.
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Config {
@@ -33,6 +42,9 @@ pub struct Config {
     #[serde(skip)]
     pub data_dir: PathBuf,
 
+    /// Missing in configs written before it was added, i.e. version 0.
+    #[serde(default)]
+    pub schema_version: u32,
     pub default_context: String,
     pub context_settings: BTreeMap<String, ContextSetting>,
 }
@@ -44,21 +56,73 @@ impl Config {
 
         let config_file = data_dir.join(CLOUD_CLI_CONFIG_FILE_NAME);
         let mut config = if config_file.exists() {
-            let s = fs::read_to_string(&config_file)?;
-            if let Ok(config) = toml::from_str(&s) {
-                config
-            } else {
-                Self::use_default_config(config_file)?
-            }
+            Self::load(&config_file)?
         } else {
             Self::use_default_config(config_file)?
         };
 
         config.data_dir = data_dir.to_path_buf();
+        config.migrate()?;
+
+        Ok(config)
+    }
+
+    /// Parse the config file without any migration or validation.
+    pub fn load(config_file: &Path) -> Result<Self> {
+        let s = fs::read_to_string(config_file)
+            .with_context(|| format!("cannot read config file `{}`", config_file.display()))?;
+        let mut config = Self::parse(&s)
+            .with_context(|| format!("invalid config file `{}`", config_file.display()))?;
+        if let Some(data_dir) = config_file.parent() {
+            config.data_dir = data_dir.to_path_buf();
+        }
+        Ok(config)
+    }
 
+    pub fn parse(s: &str) -> Result<Self> {
+        // The error contains the line and column.
+        let config = toml::from_str(s)?;
         Ok(config)
     }
 
+    pub fn validate(&self) -> Result<()> {
+        self.check_schema_version()?;
+        ensure!(
+            self.context_settings.contains_key(&self.default_context),
+            "default context `{}` not found",
+            self.default_context
+        );
+        for (name, setting) in &self.context_settings {
+            setting
+                .validate()
+                .with_context(|| format!("invalid context `{name}`"))?;
+        }
+        Ok(())
+    }
+
+    fn check_schema_version(&self) -> Result<()> {
+        ensure!(
+            self.schema_version <= CONFIG_SCHEMA_VERSION,
+            "config schema version `{}` is newer than the supported `{}`, please upgrade cldi",
+            self.schema_version,
+            CONFIG_SCHEMA_VERSION
+        );
+        Ok(())
+    }
+
+    /// Copy the config file to a timestamped backup in the data dir.
+    pub fn backup(&self) -> Result<PathBuf> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let backup = self
+            .data_dir
+            .join(format!("{CLOUD_CLI_CONFIG_FILE_NAME}.{timestamp}.bak"));
+        fs::copy(self.data_dir.join(CLOUD_CLI_CONFIG_FILE_NAME), &backup)
+            .context("cannot backup config file")?;
+        Ok(backup)
+    }
+
     fn use_default_config(config_file: PathBuf) -> Result<Self> {
         let default_config = Self::default();
         fs::write(
@@ -68,6 +132,28 @@ impl Config {
         Ok(default_config)
     }
 
+    // Migrate configs written by older versions, the original one is backed up.
+    fn migrate(&mut self) -> Result<()> {
+        self.check_schema_version()?;
+        if self.schema_version == CONFIG_SCHEMA_VERSION {
+            return Ok(());
+        }
+
+        if self.schema_version < 1 {
+            for setting in self.context_settings.values_mut() {
+                if setting.evm_addr.is_empty() {
+                    // The evm service used to be served at the executor addr.
+                    setting.evm_addr = setting.executor_addr.clone();
+                    setting.extra_evm_addrs = setting.extra_executor_addrs.clone();
+                }
+            }
+        }
+
+        self.schema_version = CONFIG_SCHEMA_VERSION;
+        self.backup()?;
+        self.save()
+    }
+
     // atomically save
     pub fn save(&self) -> Result<()> {
         let path = self.data_dir.join(CLOUD_CLI_CONFIG_FILE_NAME);
@@ -78,10 +164,7 @@ impl Config {
 
 impl Default for Config {
     fn default() -> Self {
-        let data_dir = {
-            let home = home::home_dir().expect("cannot find home dir");
-            home.join(CLOUD_CLI_DATA_DIR_NAME)
-        };
+        let data_dir = data_dir();
         let default_context = "default".to_string();
         let context_settings = {
             let mut m = BTreeMap::new();
@@ -91,11 +174,143 @@ impl Default for Config {
         Self {
             default_context,
             data_dir,
+            schema_version: CONFIG_SCHEMA_VERSION,
             context_settings,
         }
     }
 }
 
+/// The data dir from `CLDI_DATA_DIR`, or `~/.cloud-cli` if not set.
+pub fn data_dir() -> PathBuf {
+    match std::env::var_os(CLOUD_CLI_DATA_DIR_ENV) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home = home::home_dir().expect("cannot find home dir");
+            home.join(CLOUD_CLI_DATA_DIR_NAME)
+        }
+    }
+}
+
+/// Overrides of the context setting, from a project file or env vars.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContextOverride {
+    /// The saved context to start from, instead of the default one.
+    pub context: Option<String>,
+    pub controller_addr: Option<String>,
+    pub executor_addr: Option<String>,
+    pub evm_addr: Option<String>,
+    pub account_name: Option<String>,
+    /// Unlock the account with the password in this file.
+    /// A relative path in a project file is relative to the project file's dir.
+    pub password_file: Option<PathBuf>,
+    pub crypto_type: Option<CryptoType>,
+    pub consensus_type: Option<ConsensusType>,
+}
+
+impl ContextOverride {
+    /// Read the `CLDI_*` env vars.
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).ok();
+        Ok(Self {
+            context: var("CLDI_CONTEXT"),
+            controller_addr: var("CLDI_CONTROLLER_ADDR"),
+            executor_addr: var("CLDI_EXECUTOR_ADDR"),
+            evm_addr: var("CLDI_EVM_ADDR"),
+            account_name: var("CLDI_ACCOUNT"),
+            password_file: var("CLDI_PASSWORD_FILE").map(PathBuf::from),
+            crypto_type: var("CLDI_CRYPTO_TYPE")
+                .map(|s| s.parse())
+                .transpose()
+                .context("invalid env `CLDI_CRYPTO_TYPE`")?,
+            consensus_type: var("CLDI_CONSENSUS_TYPE")
+                .map(|s| s.parse())
+                .transpose()
+                .context("invalid env `CLDI_CONSENSUS_TYPE`")?,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let s = fs::read_to_string(path)
+            .with_context(|| format!("cannot read `{}`", path.display()))?;
+        let mut overrides: Self = toml::from_str(&s)
+            .with_context(|| format!("invalid project config `{}`", path.display()))?;
+        // Not relative to the cwd, which may be any dir under the project.
+        if let (Some(password_file), Some(dir)) = (&mut overrides.password_file, path.parent()) {
+            *password_file = dir.join(&*password_file);
+        }
+        Ok(overrides)
+    }
+
+    /// Find the project config file in the dir or its ancestors.
+    pub fn find_project_file(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, setting: &mut ContextSetting) {
+        if let Some(controller_addr) = &self.controller_addr {
+            setting.controller_addr = controller_addr.clone();
+            setting.extra_controller_addrs.clear();
+        }
+        if let Some(executor_addr) = &self.executor_addr {
+            setting.executor_addr = executor_addr.clone();
+            setting.extra_executor_addrs.clear();
+            // Same as `-e`, evm is served with the executor unless told otherwise.
+            if self.evm_addr.is_none() {
+                setting.evm_addr = executor_addr.clone();
+                setting.extra_evm_addrs.clear();
+            }
+        }
+        if let Some(evm_addr) = &self.evm_addr {
+            setting.evm_addr = evm_addr.clone();
+            setting.extra_evm_addrs.clear();
+        }
+        if let Some(account_name) = &self.account_name {
+            setting.account_name = account_name.clone();
+        }
+        if let Some(crypto_type) = self.crypto_type {
+            setting.crypto_type = crypto_type;
+        }
+        if let Some(consensus_type) = self.consensus_type {
+            setting.consensus_type = consensus_type;
+        }
+    }
+}
+
+/// A named layer of overrides, e.g. a project file.
+#[derive(Debug, Clone)]
+pub struct ConfigLayer {
+    pub source: String,
+    pub overrides: ContextOverride,
+}
+
+impl ConfigLayer {
+    /// The project file found from the dir, then the env vars.
+    pub fn discover(dir: &Path) -> Result<Vec<Self>> {
+        let mut layers = vec![];
+        if let Some(path) = ContextOverride::find_project_file(dir) {
+            layers.push(Self {
+                overrides: ContextOverride::load(&path)?,
+                source: format!("project file `{}`", path.display()),
+            });
+        }
+        let overrides = ContextOverride::from_env()?;
+        if !overrides.is_empty() {
+            layers.push(Self {
+                source: "env".into(),
+                overrides,
+            });
+        }
+        Ok(layers)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum CryptoType {
@@ -110,15 +325,161 @@ pub enum ConsensusType {
     Raft,
 }
 
+/// The node generation that a context talks to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Backend {
+    /// CITA-Cloud nodes with gRPC services.
+    #[default]
+    CitaCloud,
+    /// Legacy CITA 1.x nodes with JSON-RPC.
+    Cita,
+}
+
+/// How to pick a node when a context has multiple endpoints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum EndpointStrategy {
+    /// Stick to the first healthy endpoint.
+    #[default]
+    Failover,
+    RoundRobin,
+    /// Prefer the healthy endpoint with the lowest observed latency.
+    Latency,
+}
+
+/// Connect over https when present.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsSetting {
+    /// PEM CA bundle to verify the server, use the system's roots if absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<PathBuf>,
+    /// PEM client certificate and key for mTLS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+    /// Override the server name for SNI and certificate verification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain_name: Option<String>,
+}
+
+/// gRPC metadata attached to every request, e.g. for an API gateway.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataSetting {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Sources of the bearer token in the `authorization` header, at most one of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+    /// A shell command that outputs the token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_command: Option<String>,
+    /// Seconds before rerunning the token command, default to 300.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_ttl: Option<u64>,
+}
+
+impl MetadataSetting {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How calls are retried on transient errors, e.g. `Unavailable` or `DeadlineExceeded`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrySetting {
+    /// Max attempts of a call on each endpoint, 1 for no retry.
+    pub max_attempts: u32,
+    /// Backoff before the first retry, doubled for each of the following ones.
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Seconds for a call to complete, including all of its retries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
+}
+
+impl Default for RetrySetting {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 200,
+            max_backoff_ms: 5000,
+            deadline: None,
+        }
+    }
+}
+
+/// The identity of the chain that a context is meant for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainPin {
+    /// Hex with `0x` prefix.
+    pub chain_id: String,
+    pub version: u32,
+    /// Hex with `0x` prefix.
+    pub genesis_hash: String,
+}
+
+/// Guard rails for production contexts, enforced when sending txs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicySetting {
+    /// Require typed confirmation for every tx.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub protected: bool,
+    /// Max value of a tx, hex with or without `0x` prefix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<String>,
+    /// The only addresses that txs can be sent to, contract creation is refused if set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_to: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub forbid_bench: bool,
+}
+
+impl PolicySetting {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ContextSetting {
     pub controller_addr: String,
     pub executor_addr: String,
+    /// Empty in configs written before it was added, which is migrated to the executor addr.
+    #[serde(default)]
+    pub evm_addr: String,
 
     pub account_name: String,
     pub crypto_type: CryptoType,
     pub consensus_type: ConsensusType,
     pub connect_timeout: u64,
+    /// Missing in configs written before it was added.
+    #[serde(default)]
+    pub backend: Backend,
+    /// Other nodes of the same chain, used according to the endpoint strategy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_controller_addrs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_executor_addrs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_evm_addrs: Vec<String>,
+    #[serde(default)]
+    pub endpoint_strategy: EndpointStrategy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsSetting>,
+    #[serde(default, skip_serializing_if = "MetadataSetting::is_empty")]
+    pub metadata: MetadataSetting,
+    #[serde(default)]
+    pub retry: RetrySetting,
+    /// Captured on `context save`, txs are refused if the chain doesn't match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_pin: Option<ChainPin>,
+    #[serde(default, skip_serializing_if = "PolicySetting::is_empty")]
+    pub policy: PolicySetting,
 }
 
 impl FromStr for CryptoType {
@@ -147,16 +508,125 @@ impl FromStr for ConsensusType {
     }
 }
 
+impl FromStr for EndpointStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let strategy = match s.to_uppercase().replace(['-', '_'], "").as_str() {
+            "FAILOVER" => EndpointStrategy::Failover,
+            "ROUNDROBIN" => EndpointStrategy::RoundRobin,
+            "LATENCY" => EndpointStrategy::Latency,
+            unknown => bail!("unknown endpoint strategy `{}`", unknown),
+        };
+        Ok(strategy)
+    }
+}
+
+impl FromStr for Backend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let backend = match s.to_uppercase().as_str() {
+            "CITACLOUD" | "CITA-CLOUD" => Backend::CitaCloud,
+            "CITA" => Backend::Cita,
+            unknown => bail!("unknown backend `{}`", unknown),
+        };
+        Ok(backend)
+    }
+}
+
 impl Default for ContextSetting {
     fn default() -> Self {
         Self {
             controller_addr: "localhost:50004".into(),
             executor_addr: "localhost:50002".into(),
+            evm_addr: "localhost:50002".into(),
             account_name: "default".into(),
             crypto_type: CryptoType::Sm,
             consensus_type: ConsensusType::Overlord,
             connect_timeout: 3,
+            backend: Backend::CitaCloud,
+            tls: None,
+            metadata: MetadataSetting::default(),
+            extra_controller_addrs: vec![],
+            extra_executor_addrs: vec![],
+            extra_evm_addrs: vec![],
+            endpoint_strategy: EndpointStrategy::Failover,
+            retry: RetrySetting::default(),
+            chain_pin: None,
+            policy: PolicySetting::default(),
+        }
+    }
+}
+
+impl ContextSetting {
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.controller_addr.is_empty(), "controller addr is empty");
+        ensure!(!self.executor_addr.is_empty(), "executor addr is empty");
+        ensure!(!self.evm_addr.is_empty(), "evm addr is empty");
+        if let Some(tls) = &self.tls {
+            ensure!(
+                tls.client_cert.is_some() == tls.client_key.is_some(),
+                "client cert and client key must be set together"
+            );
+        }
+        let token_sources = [
+            self.metadata.token_file.is_some(),
+            self.metadata.token_env.is_some(),
+            self.metadata.token_command.is_some(),
+        ];
+        ensure!(
+            token_sources.iter().filter(|&&s| s).count() <= 1,
+            "only one of token file, token env and token command can be set"
+        );
+        ensure!(
+            self.retry.max_attempts >= 1,
+            "max attempts must be at least 1"
+        );
+        if let Some(max_value) = &self.policy.max_value {
+            parse_value(max_value).context("invalid max value in policy")?;
+        }
+        for addr in &self.policy.allowed_to {
+            parse_addr(addr).context("invalid allowed address in policy")?;
         }
+        Ok(())
+    }
+
+    /// The names of fields that differ between two settings.
+    pub fn changed_fields(&self, other: &Self) -> Vec<String> {
+        let to_map = |setting: &Self| match serde_json::to_value(setting) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => unreachable!("setting must be serialized to a map"),
+        };
+        let (this, other) = (to_map(self), to_map(other));
+        this.keys()
+            .chain(other.keys())
+            .filter(|key| this.get(*key) != other.get(*key))
+            .cloned()
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// The controller addr followed by the extra ones.
+    pub fn controller_addrs(&self) -> Vec<String> {
+        std::iter::once(self.controller_addr.clone())
+            .chain(self.extra_controller_addrs.iter().cloned())
+            .collect()
+    }
+
+    /// The executor addr followed by the extra ones.
+    pub fn executor_addrs(&self) -> Vec<String> {
+        std::iter::once(self.executor_addr.clone())
+            .chain(self.extra_executor_addrs.iter().cloned())
+            .collect()
+    }
+
+    /// The evm addr followed by the extra ones.
+    pub fn evm_addrs(&self) -> Vec<String> {
+        std::iter::once(self.evm_addr.clone())
+            .chain(self.extra_evm_addrs.iter().cloned())
+            .collect()
     }
 }
 
@@ -177,3 +647,97 @@ impl std::fmt::Display for ConsensusType {
         }
     }
 }
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::CitaCloud => write!(f, "CITACLOUD"),
+            Self::Cita => write!(f, "CITA"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_evm_addr() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let old_config = r#"
+default_context = "default"
+
+[context_settings.default]
+controller_addr = "localhost:50004"
+executor_addr = "localhost:50002"
+account_name = "default"
+crypto_type = "SM"
+consensus_type = "OVERLORD"
+connect_timeout = 3
+"#;
+        fs::write(data_dir.path().join(CLOUD_CLI_CONFIG_FILE_NAME), old_config).unwrap();
+
+        let config = Config::open(data_dir.path()).unwrap();
+        let setting = &config.context_settings["default"];
+        assert_eq!(setting.evm_addr, "localhost:50002");
+        assert_eq!(setting.backend, Backend::CitaCloud);
+
+        // The migrated config is saved, and the original one is backed up.
+        let saved = fs::read_to_string(data_dir.path().join(CLOUD_CLI_CONFIG_FILE_NAME)).unwrap();
+        assert!(saved.contains("evm_addr"));
+        assert!(saved.contains(&format!("schema_version = {CONFIG_SCHEMA_VERSION}")));
+        let backups = fs::read_dir(data_dir.path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().ends_with(".bak")
+            })
+            .count();
+        assert_eq!(backups, 1);
+    }
+
+    #[test]
+    fn test_context_override() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let sub_dir = project_dir.path().join("sub");
+        fs::create_dir(&sub_dir).unwrap();
+        fs::write(
+            project_dir.path().join(PROJECT_CONFIG_FILE_NAME),
+            "executor_addr = \"localhost:60002\"\ncrypto_type = \"ETH\"\npassword_file = \"secrets/pw\"\n",
+        )
+        .unwrap();
+
+        let path = ContextOverride::find_project_file(&sub_dir).unwrap();
+        let overrides = ContextOverride::load(&path).unwrap();
+        assert_eq!(
+            overrides.password_file.as_deref(),
+            Some(project_dir.path().join("secrets/pw").as_path())
+        );
+        let mut setting = ContextSetting::default();
+        overrides.apply(&mut setting);
+        assert_eq!(setting.evm_addr, "localhost:60002");
+        assert_eq!(setting.crypto_type, CryptoType::Eth);
+        assert_eq!(
+            ContextSetting::default().changed_fields(&setting),
+            ["crypto_type", "evm_addr", "executor_addr"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config_file = data_dir.path().join(CLOUD_CLI_CONFIG_FILE_NAME);
+        let broken = "default_context = \"default\"\n[context_settings.default\n";
+        fs::write(&config_file, broken).unwrap();
+
+        // Not reset to the default config.
+        let err = Config::open(data_dir.path()).unwrap_err();
+        assert!(format!("{err:#}").contains("line 2"));
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), broken);
+
+        let mut config = Config::default();
+        assert!(config.validate().is_ok());
+        config.default_context = "missing".into();
+        assert!(config.validate().is_err());
+    }
+}
@@ -12,13 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod address_book;
 pub mod admin;
+pub mod agent;
+pub mod backend;
 pub mod client;
 pub mod context;
 pub mod controller;
 pub mod cross_chain;
+pub mod endpoints;
 pub mod evm;
 pub mod executor;
+pub mod fake_node;
+pub mod guard;
+pub mod http;
+pub mod interceptor;
+pub mod jsonrpc;
+pub mod legacy_rpc;
+pub mod light_client;
 #[cfg(test)]
 pub mod mock;
+pub mod probe;
+pub mod remote_signer;
+pub mod signer;
 pub mod wallet;
@@ -0,0 +1,201 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Named addresses, given as `@name` where an address is accepted.
+//!
+//! A name is either global or bound to a context, so that the same contract name can point
+//! at its deployment on each chain. Names of the current context shadow the global ones.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, ensure, Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    crypto::Address,
+    utils::{hex, parse_addr, safe_save},
+};
+
+pub const ADDRESS_BOOK_FILE_NAME: &str = "address-book.toml";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "SerializedAddressBook", into = "SerializedAddressBook")]
+pub struct AddressBook {
+    global: BTreeMap<String, Address>,
+    contexts: BTreeMap<String, BTreeMap<String, Address>>,
+}
+
+impl AddressBook {
+    /// Empty if there is no address book yet.
+    pub fn open(data_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = Self::path(data_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("cannot read address book `{}`", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("invalid address book `{}`", path.display()))
+    }
+
+    pub fn save(&self, data_dir: impl AsRef<Path>) -> Result<()> {
+        let content = toml::to_string_pretty(self)?;
+        safe_save(Self::path(data_dir), content.as_bytes(), true)
+    }
+
+    fn path(data_dir: impl AsRef<Path>) -> PathBuf {
+        data_dir.as_ref().join(ADDRESS_BOOK_FILE_NAME)
+    }
+
+    /// `context` is `None` for a global name.
+    pub fn insert(&mut self, context: Option<&str>, name: &str, addr: Address) -> Result<()> {
+        ensure!(
+            !name.is_empty() && !name.starts_with('@') && parse_addr(name).is_err(),
+            "invalid name `{name}`, it must not be empty, start with `@` or be an address"
+        );
+        let names = match context {
+            Some(context) => self.contexts.entry(context.into()).or_default(),
+            None => &mut self.global,
+        };
+        names.insert(name.into(), addr);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, context: Option<&str>, name: &str) -> Result<Address> {
+        let names = match context {
+            Some(context) => self.contexts.get_mut(context),
+            None => Some(&mut self.global),
+        };
+        let addr = names
+            .and_then(|names| names.remove(name))
+            .ok_or_else(|| match context {
+                Some(context) => anyhow!("name `{name}` not found in context `{context}`"),
+                None => anyhow!("global name `{name}` not found"),
+            })?;
+        self.contexts.retain(|_, names| !names.is_empty());
+        Ok(addr)
+    }
+
+    /// Names of the context and the global ones it doesn't shadow.
+    pub fn names(&self, context: &str) -> BTreeMap<&str, Address> {
+        let mut names: BTreeMap<&str, Address> = self
+            .global
+            .iter()
+            .map(|(name, addr)| (name.as_str(), *addr))
+            .collect();
+        if let Some(context_names) = self.contexts.get(context) {
+            names.extend(
+                context_names
+                    .iter()
+                    .map(|(name, addr)| (name.as_str(), *addr)),
+            );
+        }
+        names
+    }
+
+    pub fn global(&self) -> &BTreeMap<String, Address> {
+        &self.global
+    }
+
+    pub fn contexts(&self) -> &BTreeMap<String, BTreeMap<String, Address>> {
+        &self.contexts
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedAddressBook {
+    #[serde(default)]
+    global: BTreeMap<String, String>,
+    #[serde(default)]
+    contexts: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl TryFrom<SerializedAddressBook> for AddressBook {
+    type Error = anyhow::Error;
+
+    fn try_from(serialized: SerializedAddressBook) -> Result<Self, Self::Error> {
+        fn parse_names(names: BTreeMap<String, String>) -> Result<BTreeMap<String, Address>> {
+            names
+                .into_iter()
+                .map(|(name, addr)| {
+                    let addr = parse_addr(&addr)
+                        .with_context(|| format!("invalid address of `{name}`"))?;
+                    Ok((name, addr))
+                })
+                .collect()
+        }
+        Ok(Self {
+            global: parse_names(serialized.global)?,
+            contexts: serialized
+                .contexts
+                .into_iter()
+                .map(|(context, names)| Ok((context, parse_names(names)?)))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl From<AddressBook> for SerializedAddressBook {
+    fn from(book: AddressBook) -> Self {
+        fn hex_names(names: BTreeMap<String, Address>) -> BTreeMap<String, String> {
+            names
+                .into_iter()
+                .map(|(name, addr)| (name, hex(&addr)))
+                .collect()
+        }
+        Self {
+            global: hex_names(book.global),
+            contexts: book
+                .contexts
+                .into_iter()
+                .map(|(context, names)| (context, hex_names(names)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_book() {
+        let dir = tempfile::tempdir().unwrap();
+        let token_a = parse_addr("0x0102030405060708091011121314151617181920").unwrap();
+        let token_b = parse_addr("0x2019181716151413121110090807060504030201").unwrap();
+
+        let mut book = AddressBook::open(dir.path()).unwrap();
+        book.insert(None, "token", token_a).unwrap();
+        book.insert(Some("testnet"), "token", token_b).unwrap();
+        assert!(book.insert(None, "@token", token_a).is_err());
+        assert!(book
+            .insert(None, "0x0102030405060708091011121314151617181920", token_a)
+            .is_err());
+        book.save(dir.path()).unwrap();
+
+        let mut book = AddressBook::open(dir.path()).unwrap();
+        assert_eq!(book.names("default")["token"], token_a);
+        // Shadowed by the context's own name.
+        assert_eq!(book.names("testnet")["token"], token_b);
+
+        book.remove(Some("testnet"), "token").unwrap();
+        assert_eq!(book.names("testnet")["token"], token_a);
+        assert!(book.remove(Some("testnet"), "token").is_err());
+        assert!(book.contexts().is_empty());
+    }
+}
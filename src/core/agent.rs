@@ -0,0 +1,445 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A key agent holding unlocked accounts, so that they can sign without
+//! decrypting the keystore or getting the password every time.
+//!
+//! It speaks line-delimited JSON over a unix socket that only the owner can access.
+//! Each request is a JSON object with a `method`, and the response is either
+//! `{"ok": <result>}` or `{"error": "<message>"}`.
+//! Secret keys never leave the agent, it only returns signatures.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader as StdBufReader, Write},
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        net::UnixStream as StdUnixStream,
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::Notify,
+};
+
+use super::{
+    controller::SignerBehaviour,
+    wallet::{MultiCryptoAccount, Wallet},
+};
+use crate::{
+    config::CryptoType,
+    crypto::{Address, Crypto, EthCrypto, SmCrypto},
+    utils::{hex, parse_addr, parse_data},
+};
+
+pub const AGENT_SOCKET_FILE_NAME: &str = "agent.sock";
+/// Overrides the agent socket, which defaults to `<data-dir>/agent.sock`.
+pub const AGENT_SOCKET_ENV: &str = "CLDI_AGENT_SOCK";
+/// How long the client waits for the agent to read a request or answer it.
+const AGENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn agent_socket_path(data_dir: &Path) -> PathBuf {
+    match std::env::var_os(AGENT_SOCKET_ENV) {
+        Some(path) => PathBuf::from(path),
+        None => data_dir.join(AGENT_SOCKET_FILE_NAME),
+    }
+}
+
+// No Debug to avoid leaking the password.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Request {
+    /// Unlock the account from the agent's keystore and hold it for `ttl` secs.
+    Add {
+        name: String,
+        password: String,
+        ttl: Option<u64>,
+    },
+    Remove {
+        name: String,
+    },
+    List,
+    Sign {
+        address: String,
+        msg: String,
+    },
+    Stop,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Response {
+    Ok(Json),
+    Error(String),
+}
+
+/// An account held by the agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentAccount {
+    pub name: String,
+    pub address: String,
+    pub crypto_type: CryptoType,
+    /// Unix timestamp in secs.
+    pub expires_at: u64,
+}
+
+struct Held {
+    account: MultiCryptoAccount,
+    expires_at: u64,
+}
+
+struct Agent {
+    wallet_dir: PathBuf,
+    default_ttl: Duration,
+    accounts: Mutex<BTreeMap<String, Held>>,
+    stop: Notify,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Serve the agent on the socket until stopped.
+/// Accounts are unlocked from the keystore in `wallet_dir`.
+pub async fn serve(socket: &Path, wallet_dir: PathBuf, default_ttl: Duration) -> Result<()> {
+    if let Ok(metadata) = std::fs::symlink_metadata(socket) {
+        if !metadata.file_type().is_socket() {
+            bail!("`{}` exists and is not a socket", socket.display());
+        }
+        if StdUnixStream::connect(socket).is_ok() {
+            bail!("agent is already running at `{}`", socket.display());
+        }
+        // Left by an agent that didn't exit cleanly.
+        std::fs::remove_file(socket)
+            .with_context(|| format!("cannot remove stale socket `{}`", socket.display()))?;
+    }
+    let listener = bind_private(socket)?;
+
+    let agent = Arc::new(Agent {
+        wallet_dir,
+        default_ttl,
+        accounts: Mutex::new(BTreeMap::new()),
+        stop: Notify::new(),
+    });
+    let mut purge_interval = tokio::time::interval(Duration::from_secs(1));
+    let ret = loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted.context("failed to accept connection") {
+                    Ok((stream, _)) => stream,
+                    Err(e) => break Err(e),
+                };
+                let agent = agent.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = agent.handle_connection(stream).await {
+                        eprintln!("agent connection error: {e:#}");
+                    }
+                });
+            }
+            _ = purge_interval.tick() => agent.purge_expired(),
+            _ = agent.stop.notified() => break Ok(()),
+        }
+    };
+    // Drop the keys as soon as possible.
+    agent.accounts.lock().unwrap().clear();
+    let _ = std::fs::remove_file(socket);
+    ret
+}
+
+// Bind in a dir that only the owner can access, and move the socket into place after
+// restricting its permissions, so that others never get a chance to connect.
+fn bind_private(socket: &Path) -> Result<UnixListener> {
+    let parent = match socket.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    // Created with mode 0700.
+    let private_dir = tempfile::Builder::new()
+        .prefix(".agent-")
+        .tempdir_in(parent)
+        .with_context(|| format!("cannot create a private dir in `{}`", parent.display()))?;
+    let private_socket = private_dir.path().join(AGENT_SOCKET_FILE_NAME);
+    let listener = UnixListener::bind(&private_socket)
+        .with_context(|| format!("cannot listen on `{}`", socket.display()))?;
+    std::fs::set_permissions(&private_socket, std::fs::Permissions::from_mode(0o600))
+        .context("cannot restrict socket permissions")?;
+    std::fs::rename(&private_socket, socket)
+        .with_context(|| format!("cannot move socket to `{}`", socket.display()))?;
+    Ok(listener)
+}
+
+impl Agent {
+    async fn handle_connection(&self, stream: UnixStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let resp = match serde_json::from_str::<Request>(&line) {
+                Ok(req) => match self.handle(req) {
+                    Ok(result) => Response::Ok(result),
+                    Err(e) => Response::Error(format!("{e:#}")),
+                },
+                Err(e) => Response::Error(format!("invalid request: {e}")),
+            };
+            let mut resp = serde_json::to_vec(&resp)?;
+            resp.push(b'\n');
+            writer.write_all(&resp).await?;
+        }
+        Ok(())
+    }
+
+    fn handle(&self, req: Request) -> Result<Json> {
+        self.purge_expired();
+        match req {
+            Request::Add {
+                name,
+                password,
+                ttl,
+            } => {
+                // Reopen to see the accounts created after the agent started.
+                let wallet = Wallet::open(&self.wallet_dir)?;
+                let account = wallet.get(&name)?.unlock(password.as_bytes())?;
+                let ttl = ttl.map(Duration::from_secs).unwrap_or(self.default_ttl);
+                let expires_at = unix_now() + ttl.as_secs();
+                self.accounts.lock().unwrap().insert(
+                    name,
+                    Held {
+                        account,
+                        expires_at,
+                    },
+                );
+                Ok(json!({ "expires_at": expires_at }))
+            }
+            Request::Remove { name } => {
+                self.accounts
+                    .lock()
+                    .unwrap()
+                    .remove(&name)
+                    .ok_or_else(|| anyhow!("account `{name}` not found in agent"))?;
+                Ok(Json::Null)
+            }
+            Request::List => {
+                let accounts = self.accounts.lock().unwrap();
+                let accounts = accounts
+                    .iter()
+                    .map(|(name, held)| AgentAccount {
+                        name: name.clone(),
+                        address: hex(held.account.address()),
+                        crypto_type: held.account.crypto_type(),
+                        expires_at: held.expires_at,
+                    })
+                    .collect::<Vec<_>>();
+                Ok(serde_json::to_value(accounts)?)
+            }
+            Request::Sign { address, msg } => {
+                let address = parse_addr(&address)?;
+                let msg = parse_data(&msg)?;
+                let accounts = self.accounts.lock().unwrap();
+                let held = accounts
+                    .values()
+                    .find(|held| *held.account.address() == address)
+                    .ok_or_else(|| anyhow!("account `{}` not found in agent", hex(&address)))?;
+                let signature = held.account.sign(&msg)?;
+                Ok(json!(hex(&signature)))
+            }
+            Request::Stop => {
+                self.stop.notify_one();
+                Ok(Json::Null)
+            }
+        }
+    }
+
+    fn purge_expired(&self) {
+        let now = unix_now();
+        self.accounts
+            .lock()
+            .unwrap()
+            .retain(|_, held| held.expires_at > now);
+    }
+}
+
+/// A blocking client, since signing is not async.
+#[derive(Debug, Clone)]
+pub struct AgentClient {
+    socket: PathBuf,
+}
+
+impl AgentClient {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket }
+    }
+
+    pub fn request(&self, req: &Request) -> Result<Json> {
+        let mut stream = StdUnixStream::connect(&self.socket)
+            .with_context(|| format!("cannot connect to agent at `{}`", self.socket.display()))?;
+        stream.set_read_timeout(Some(AGENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(AGENT_TIMEOUT))?;
+        let mut line = serde_json::to_vec(req)?;
+        line.push(b'\n');
+        stream.write_all(&line)?;
+
+        let mut line = String::new();
+        StdBufReader::new(stream).read_line(&mut line)?;
+        match serde_json::from_str(&line).context("invalid agent response")? {
+            Response::Ok(result) => Ok(result),
+            Response::Error(e) => bail!("agent error: {e}"),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<AgentAccount>> {
+        let accounts = self.request(&Request::List)?;
+        Ok(serde_json::from_value(accounts)?)
+    }
+
+    /// Get a signer for the address if the agent is running and holds it.
+    pub fn signer(&self, address: &Address) -> Option<AgentSigner> {
+        let address_hex = hex(address);
+        let held = self
+            .list()
+            .ok()?
+            .into_iter()
+            .find(|account| account.address == address_hex)?;
+        Some(AgentSigner {
+            client: self.clone(),
+            address: *address,
+            crypto_type: held.crypto_type,
+        })
+    }
+}
+
+/// Sign by the agent, the hash is computed locally since it's not a secret.
+#[derive(Debug, Clone)]
+pub struct AgentSigner {
+    client: AgentClient,
+    address: Address,
+    crypto_type: CryptoType,
+}
+
+impl AgentSigner {
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+}
+
+impl SignerBehaviour for AgentSigner {
+    fn hash(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        let hash = match self.crypto_type {
+            CryptoType::Sm => SmCrypto::hash(msg),
+            CryptoType::Eth => EthCrypto::hash(msg),
+        };
+        Ok(hash.to_vec())
+    }
+
+    fn address(&self) -> &[u8] {
+        self.address.as_slice()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        let signature = self.client.request(&Request::Sign {
+            address: hex(&self.address),
+            msg: hex(msg),
+        })?;
+        let signature = signature
+            .as_str()
+            .ok_or_else(|| anyhow!("invalid signature from agent"))?;
+        parse_data(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::wallet::Account;
+
+    #[test]
+    fn test_agent() {
+        let wallet_dir = tempfile::tempdir().unwrap();
+        let mut wallet = Wallet::open(wallet_dir.path()).unwrap();
+        let account = Account::<SmCrypto>::generate();
+        let address = *account.address();
+        let public_key = account.public_key().clone();
+        wallet.save("test".into(), account.lock(b"pw")).unwrap();
+
+        let socket = wallet_dir.path().join(AGENT_SOCKET_FILE_NAME);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.spawn({
+            let socket = socket.clone();
+            let wallet_dir = wallet_dir.path().to_path_buf();
+            async move { serve(&socket, wallet_dir, Duration::from_secs(60)).await }
+        });
+        while !socket.exists() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let client = AgentClient::new(socket.clone());
+        assert!(client.signer(&address).is_none());
+        let wrong_password = Request::Add {
+            name: "test".into(),
+            password: "wrong".into(),
+            ttl: None,
+        };
+        assert!(client.request(&wrong_password).is_err());
+        client
+            .request(&Request::Add {
+                name: "test".into(),
+                password: "pw".into(),
+                ttl: None,
+            })
+            .unwrap();
+
+        let signer = client.signer(&address).unwrap();
+        let msg = signer.hash(b"msg").unwrap();
+        let signature = signer.sign(&msg).unwrap();
+        assert_eq!(SmCrypto::recover(&msg, &signature), Some(public_key));
+
+        // Expired accounts are dropped.
+        client
+            .request(&Request::Add {
+                name: "test".into(),
+                password: "pw".into(),
+                ttl: Some(0),
+            })
+            .unwrap();
+        assert!(client.list().unwrap().is_empty());
+        assert!(signer.sign(&msg).is_err());
+
+        client.request(&Request::Stop).unwrap();
+        rt.block_on(server).unwrap().unwrap();
+        assert!(!socket.exists());
+
+        // Only a stale socket is removed.
+        std::fs::write(&socket, "not a socket").unwrap();
+        let err = rt
+            .block_on(serve(
+                &socket,
+                wallet_dir.path().to_path_buf(),
+                Duration::from_secs(60),
+            ))
+            .unwrap_err();
+        assert!(err.to_string().contains("not a socket"));
+        assert!(socket.exists());
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use anyhow::{ensure, Result};
+use cita_cloud_proto::{
+    blockchain::{Block, CompactBlock, RawTransaction, Transaction as CloudNormalTransaction},
+    common::NodeStatus,
+    controller::{BlockNumber as CloudBlockNumber, CrossChainProof, SystemConfig},
+    evm::{
+        Balance, BlockNumber, ByteAbi, ByteCode, ByteQuota, Nonce, Receipt, ReceiptProof, RootsInfo,
+    },
+    executor::CallResponse,
+};
+use tonic::transport::Channel;
+
+use super::{
+    client::{ConnectOptions, GrpcClientBehaviour},
+    controller::{
+        CompactBlockWithStaterootProof, ControllerBehaviour, ProofWithValidators, SignerBehaviour,
+    },
+    evm::EvmBehaviour,
+    executor::ExecutorBehaviour,
+    interceptor::MetadataInterceptor,
+    legacy_rpc::LegacyClient,
+};
+use crate::{
+    config::{Backend, ContextSetting},
+    crypto::{Address, Hash},
+};
+
+/// A client of the backend selected by the context setting.
+#[derive(Debug, Clone)]
+pub enum BackendClient<T> {
+    CitaCloud(T),
+    Cita(LegacyClient),
+}
+
+macro_rules! dispatch {
+    ($self:ident, $behaviour:ident::$method:ident($($arg:expr),*)) => {
+        match $self {
+            Self::CitaCloud(client) => $behaviour::$method(client, $($arg),*).await,
+            Self::Cita(client) => $behaviour::$method(client, $($arg),*).await,
+        }
+    };
+}
+
+#[tonic::async_trait]
+impl<T> GrpcClientBehaviour for BackendClient<T>
+where
+    T: GrpcClientBehaviour + Send,
+{
+    fn from_channel(ch: Channel, interceptor: MetadataInterceptor) -> Self {
+        Self::CitaCloud(T::from_channel(ch, interceptor))
+    }
+
+    fn connect_with_options(
+        addrs: &[String],
+        setting: &ContextSetting,
+        opts: &ConnectOptions,
+    ) -> Result<Self> {
+        match setting.backend {
+            Backend::CitaCloud => {
+                T::connect_with_options(addrs, setting, opts).map(Self::CitaCloud)
+            }
+            // A CITA 1.x node serves everything at the controller address.
+            Backend::Cita => {
+                ensure!(
+                    setting.tls.is_none(),
+                    "TLS is not supported for CITA 1.x nodes"
+                );
+                ensure!(
+                    setting.extra_controller_addrs.is_empty(),
+                    "multiple endpoints are not supported for CITA 1.x nodes"
+                );
+                LegacyClient::new(
+                    &setting.controller_addr,
+                    Duration::from_secs(setting.connect_timeout),
+                )
+                .map(Self::Cita)
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<T> ControllerBehaviour for BackendClient<T>
+where
+    T: ControllerBehaviour + Send + Sync,
+{
+    async fn send_raw(&self, raw: RawTransaction) -> Result<Hash> {
+        dispatch!(self, ControllerBehaviour::send_raw(raw))
+    }
+
+    async fn get_system_config(&self) -> Result<SystemConfig> {
+        dispatch!(self, ControllerBehaviour::get_system_config())
+    }
+
+    async fn get_system_config_by_number(&self, block_number: u64) -> Result<SystemConfig> {
+        dispatch!(
+            self,
+            ControllerBehaviour::get_system_config_by_number(block_number)
+        )
+    }
+
+    async fn get_block_number(&self, for_pending: bool) -> Result<u64> {
+        dispatch!(self, ControllerBehaviour::get_block_number(for_pending))
+    }
+
+    async fn get_block_hash(&self, block_number: u64) -> Result<Hash> {
+        dispatch!(self, ControllerBehaviour::get_block_hash(block_number))
+    }
+
+    async fn get_height_by_hash(&self, hash: Hash) -> Result<CloudBlockNumber> {
+        dispatch!(self, ControllerBehaviour::get_height_by_hash(hash))
+    }
+
+    async fn get_compact_block_by_number(&self, block_number: u64) -> Result<CompactBlock> {
+        dispatch!(
+            self,
+            ControllerBehaviour::get_compact_block_by_number(block_number)
+        )
+    }
+
+    async fn get_block_by_number(
+        &self,
+        block_number: u64,
+    ) -> Result<CompactBlockWithStaterootProof> {
+        dispatch!(self, ControllerBehaviour::get_block_by_number(block_number))
+    }
+
+    async fn get_block_detail_by_number(&self, block_number: u64) -> Result<Block> {
+        dispatch!(
+            self,
+            ControllerBehaviour::get_block_detail_by_number(block_number)
+        )
+    }
+
+    async fn get_tx(&self, tx_hash: Hash) -> Result<RawTransaction> {
+        dispatch!(self, ControllerBehaviour::get_tx(tx_hash))
+    }
+
+    async fn get_tx_index(&self, tx_hash: Hash) -> Result<u64> {
+        dispatch!(self, ControllerBehaviour::get_tx_index(tx_hash))
+    }
+
+    async fn get_tx_block_number(&self, tx_hash: Hash) -> Result<u64> {
+        dispatch!(self, ControllerBehaviour::get_tx_block_number(tx_hash))
+    }
+
+    async fn get_node_status(&self) -> Result<NodeStatus> {
+        dispatch!(self, ControllerBehaviour::get_node_status())
+    }
+
+    async fn add_node(&self, multiaddr: String) -> Result<u32> {
+        dispatch!(self, ControllerBehaviour::add_node(multiaddr))
+    }
+
+    async fn parse_overlord_proof(&self, proof_bytes: Vec<u8>) -> Result<ProofWithValidators> {
+        dispatch!(self, ControllerBehaviour::parse_overlord_proof(proof_bytes))
+    }
+
+    async fn get_cross_chain_proof(&self, hash: Hash) -> Result<CrossChainProof> {
+        dispatch!(self, ControllerBehaviour::get_cross_chain_proof(hash))
+    }
+
+    async fn sign_and_send_tx<S>(&self, signer: &S, tx: CloudNormalTransaction) -> Result<Hash>
+    where
+        S: SignerBehaviour + Send + Sync,
+    {
+        dispatch!(self, ControllerBehaviour::sign_and_send_tx(signer, tx))
+    }
+}
+
+#[tonic::async_trait]
+impl<T> ExecutorBehaviour for BackendClient<T>
+where
+    T: ExecutorBehaviour + Send + Sync,
+{
+    async fn call(
+        &self,
+        from: Address,
+        to: Address,
+        data: Vec<u8>,
+        height: u64,
+    ) -> Result<CallResponse> {
+        dispatch!(self, ExecutorBehaviour::call(from, to, data, height))
+    }
+}
+
+#[tonic::async_trait]
+impl<T> EvmBehaviour for BackendClient<T>
+where
+    T: EvmBehaviour + Send + Sync,
+{
+    async fn get_receipt(&self, hash: Hash) -> Result<Receipt> {
+        dispatch!(self, EvmBehaviour::get_receipt(hash))
+    }
+
+    async fn get_code(&self, addr: Address, block_number: BlockNumber) -> Result<ByteCode> {
+        dispatch!(self, EvmBehaviour::get_code(addr, block_number))
+    }
+
+    async fn get_balance(&self, addr: Address, block_number: BlockNumber) -> Result<Balance> {
+        dispatch!(self, EvmBehaviour::get_balance(addr, block_number))
+    }
+
+    async fn get_tx_count(&self, addr: Address, block_number: BlockNumber) -> Result<Nonce> {
+        dispatch!(self, EvmBehaviour::get_tx_count(addr, block_number))
+    }
+
+    async fn get_abi(&self, addr: Address, block_number: BlockNumber) -> Result<ByteAbi> {
+        dispatch!(self, EvmBehaviour::get_abi(addr, block_number))
+    }
+
+    async fn estimate_quota(
+        &self,
+        from: Vec<u8>,
+        to: Vec<u8>,
+        method: Vec<u8>,
+    ) -> Result<ByteQuota> {
+        dispatch!(self, EvmBehaviour::estimate_quota(from, to, method))
+    }
+
+    async fn get_receipt_proof(&self, hash: Hash) -> Result<ReceiptProof> {
+        dispatch!(self, EvmBehaviour::get_receipt_proof(hash))
+    }
+
+    async fn get_roots_info(&self, block_number: BlockNumber) -> Result<RootsInfo> {
+        dispatch!(self, EvmBehaviour::get_roots_info(block_number))
+    }
+
+    async fn get_storage_at(
+        &self,
+        addr: Address,
+        position: Hash,
+        block_number: BlockNumber,
+    ) -> Result<Hash> {
+        dispatch!(
+            self,
+            EvmBehaviour::get_storage_at(addr, position, block_number)
+        )
+    }
+}
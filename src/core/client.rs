@@ -12,64 +12,155 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::Result;
-use std::time::Duration;
-use tonic::transport::Channel;
-use tonic::transport::Endpoint;
+use anyhow::{bail, Context as _, Result};
+use std::{fs, path::Path, time::Duration};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
 
-use super::{controller::ControllerClient, evm::EvmClient, executor::ExecutorClient};
+use super::{
+    controller::ControllerClient, evm::EvmClient, executor::ExecutorClient,
+    interceptor::MetadataInterceptor,
+};
+use crate::config::{ContextSetting, TlsSetting};
 
 const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
 
+/// Options shared by all the connections of a context.
+#[derive(Clone, Default)]
+pub struct ConnectOptions {
+    pub tls: Option<TlsSetting>,
+    pub interceptor: MetadataInterceptor,
+}
+
+impl ConnectOptions {
+    pub fn from_setting(setting: &ContextSetting) -> Result<Self> {
+        Ok(Self {
+            tls: setting.tls.clone(),
+            interceptor: MetadataInterceptor::from_setting(&setting.metadata)?,
+        })
+    }
+}
+
 #[tonic::async_trait]
 pub trait GrpcClientBehaviour: Sized {
-    fn from_channel(ch: Channel) -> Self;
+    fn from_channel(ch: Channel, interceptor: MetadataInterceptor) -> Self;
 
-    async fn connect(addr: &str) -> Result<Self> {
-        let addr = format!("http://{addr}");
-        let ch = Endpoint::from_shared(addr)?.connect().await?;
-        Ok(Self::from_channel(ch))
+    async fn connect(addr: &str, opts: &ConnectOptions) -> Result<Self> {
+        let ch = endpoint(addr, opts.tls.as_ref())?.connect().await?;
+        Ok(Self::from_channel(ch, opts.interceptor.clone()))
     }
 
     // TODO: maybe add async.
     // Endpoint::connect_lazy, although no async fn, does require running in a async runtime
-    fn connect_lazy(addr: &str, dur: Duration) -> Result<Self> {
-        let addr = format!("http://{addr}");
-        let ch = Endpoint::from_shared(addr)?
+    fn connect_lazy(addr: &str, dur: Duration, opts: &ConnectOptions) -> Result<Self> {
+        let ch = endpoint(addr, opts.tls.as_ref())?
             .tcp_keepalive(TCP_KEEPALIVE.into())
             .timeout(dur)
             .connect_lazy();
-        Ok(Self::from_channel(ch))
+        Ok(Self::from_channel(ch, opts.interceptor.clone()))
+    }
+
+    /// Connect lazily to the addrs with the other options of the context setting.
+    /// A plain client takes exactly one addr, see `EndpointPool` for more.
+    fn connect_by_setting(addrs: &[String], setting: &ContextSetting) -> Result<Self> {
+        Self::connect_with_options(addrs, setting, &ConnectOptions::from_setting(setting)?)
+    }
+
+    /// The same as `connect_by_setting`, but with the options already built from the
+    /// setting, so that the clients of a context share them, e.g. the token refreshing.
+    fn connect_with_options(
+        addrs: &[String],
+        setting: &ContextSetting,
+        opts: &ConnectOptions,
+    ) -> Result<Self> {
+        let [addr] = addrs else {
+            bail!("expect exactly one endpoint, but got {}", addrs.len());
+        };
+        Self::connect_lazy(addr, Duration::from_secs(setting.connect_timeout), opts)
     }
 
-    async fn connect_timeout(addr: &str, dur: Duration) -> Result<Self> {
-        let addr = format!("http://{addr}");
-        let ch = Endpoint::from_shared(addr)?
+    async fn connect_timeout(addr: &str, dur: Duration, opts: &ConnectOptions) -> Result<Self> {
+        let ch = endpoint(addr, opts.tls.as_ref())?
             .tcp_keepalive(TCP_KEEPALIVE.into())
             .timeout(dur)
             .connect()
             .await?;
-        Ok(Self::from_channel(ch))
+        Ok(Self::from_channel(ch, opts.interceptor.clone()))
     }
 }
 
+fn endpoint(addr: &str, tls: Option<&TlsSetting>) -> Result<Endpoint> {
+    let endpoint = match tls {
+        Some(tls) => {
+            Endpoint::from_shared(format!("https://{addr}"))?.tls_config(client_tls_config(tls)?)?
+        }
+        None => Endpoint::from_shared(format!("http://{addr}"))?,
+    };
+    Ok(endpoint)
+}
+
+fn client_tls_config(tls: &TlsSetting) -> Result<ClientTlsConfig> {
+    let read_pem =
+        |path: &Path| fs::read(path).with_context(|| format!("cannot read `{}`", path.display()));
+
+    let mut config = match &tls.ca_cert {
+        Some(ca_cert) => {
+            ClientTlsConfig::new().ca_certificate(Certificate::from_pem(read_pem(ca_cert)?))
+        }
+        None => ClientTlsConfig::new().with_native_roots(),
+    };
+    match (&tls.client_cert, &tls.client_key) {
+        (Some(cert), Some(key)) => {
+            config = config.identity(Identity::from_pem(read_pem(cert)?, read_pem(key)?));
+        }
+        (None, None) => (),
+        _ => bail!("client cert and key must be provided together for mTLS"),
+    }
+    if let Some(domain_name) = &tls.domain_name {
+        config = config.domain_name(domain_name);
+    }
+
+    Ok(config)
+}
+
 #[tonic::async_trait]
 impl GrpcClientBehaviour for ControllerClient {
-    fn from_channel(ch: Channel) -> Self {
-        Self::new(ch)
+    fn from_channel(ch: Channel, interceptor: MetadataInterceptor) -> Self {
+        Self::with_interceptor(ch, interceptor)
     }
 }
 
 #[tonic::async_trait]
 impl GrpcClientBehaviour for ExecutorClient {
-    fn from_channel(ch: Channel) -> Self {
-        Self::new(ch)
+    fn from_channel(ch: Channel, interceptor: MetadataInterceptor) -> Self {
+        Self::with_interceptor(ch, interceptor)
     }
 }
 
 #[tonic::async_trait]
 impl GrpcClientBehaviour for EvmClient {
-    fn from_channel(ch: Channel) -> Self {
-        Self::new(ch)
+    fn from_channel(ch: Channel, interceptor: MetadataInterceptor) -> Self {
+        Self::with_interceptor(ch, interceptor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_tls_config() {
+        let tls = TlsSetting {
+            client_cert: Some("client.pem".into()),
+            ..Default::default()
+        };
+        assert!(client_tls_config(&tls).is_err());
+
+        let tls = TlsSetting {
+            ca_cert: Some("/non-existent/ca.pem".into()),
+            ..Default::default()
+        };
+        assert!(client_tls_config(&tls).is_err());
+
+        assert!(endpoint("localhost:50004", None).is_ok());
     }
 }
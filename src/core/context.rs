@@ -12,15 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::{anyhow, ensure, Context as _, Result};
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
 use rustyline::DefaultEditor;
-use std::{future::Future, time::Duration};
+use std::{collections::BTreeMap, future::Future};
 
 use super::{
-    client::GrpcClientBehaviour,
-    wallet::{MultiCryptoAccount, Wallet},
+    address_book::AddressBook,
+    agent::{agent_socket_path, AgentClient},
+    client::{ConnectOptions, GrpcClientBehaviour},
+    signer::Signer,
+    wallet::{MaybeLocked, Wallet},
+};
+use crate::{
+    config::{Config, ConfigLayer, ContextSetting},
+    crypto::Address,
+    display::{annotate_addrs, Display},
+    utils::{parse_addr, read_password_file},
 };
-use crate::config::{Config, ContextSetting};
 
 pub struct Context<Co, Ex, Ev> {
     /// Those gRPC client are connected lazily.
@@ -29,9 +37,14 @@ pub struct Context<Co, Ex, Ev> {
     pub evm: Ev,
 
     pub wallet: Wallet,
+    pub address_book: AddressBook,
 
     pub config: Config,
+    /// The name of the context that current setting comes from.
+    pub current_context_name: String,
     pub current_setting: ContextSetting,
+    /// Where the overridden fields of current setting come from, e.g. env.
+    pub setting_sources: BTreeMap<String, String>,
 
     // rustyline::Editor, used for interactive cmd.
     pub editor: DefaultEditor,
@@ -39,7 +52,8 @@ pub struct Context<Co, Ex, Ev> {
 }
 
 impl<Co, Ex, Ev> Context<Co, Ex, Ev> {
-    pub fn from_config(config: Config) -> Result<Self>
+    /// Create the context from the default context setting with the layers applied in order.
+    pub fn from_config(config: Config, layers: &[ConfigLayer]) -> Result<Self>
     where
         Co: GrpcClientBehaviour,
         Ex: GrpcClientBehaviour,
@@ -47,68 +61,169 @@ impl<Co, Ex, Ev> Context<Co, Ex, Ev> {
     {
         let rt = CtrlCSignalCapturedRuntime(tokio::runtime::Runtime::new()?);
         let editor = rustyline::DefaultEditor::new()?;
-        let wallet = Wallet::open(&config.data_dir)?;
+        let mut wallet = Wallet::open(&config.data_dir)?;
+        let address_book = AddressBook::open(&config.data_dir)?;
 
-        let default_context_setting = config
-            .context_settings
-            .get(&config.default_context)
-            .cloned()
-            .unwrap_or_else(|| {
-                println!(
-                    "The configured default context setting `{}` is missing.",
-                    config.default_context
-                );
+        // The last layer that picks a context wins.
+        let (context_name, context_source) = layers
+            .iter()
+            .rev()
+            .find_map(|layer| {
+                layer
+                    .overrides
+                    .context
+                    .clone()
+                    .map(|name| (name, Some(&layer.source)))
+            })
+            .unwrap_or_else(|| (config.default_context.clone(), None));
+        let mut default_context_setting = match config.context_settings.get(&context_name) {
+            Some(setting) => setting.clone(),
+            None => {
+                if let Some(source) = context_source {
+                    bail!("context `{context_name}` from {source} not found");
+                }
+                println!("The configured default context setting `{context_name}` is missing.");
                 println!("Using a local default context..");
                 ContextSetting::default()
-            });
+            }
+        };
+
+        let mut setting_sources = BTreeMap::new();
+        for layer in layers {
+            let before = default_context_setting.clone();
+            layer.overrides.apply(&mut default_context_setting);
+            for field in before.changed_fields(&default_context_setting) {
+                setting_sources.insert(field, layer.source.clone());
+            }
+        }
+        // Applied after all the layers so that the password matches the final account.
+        if let Some(layer) = layers
+            .iter()
+            .rev()
+            .find(|layer| layer.overrides.password_file.is_some())
+        {
+            let pw = read_password_file(layer.overrides.password_file.as_ref().unwrap())?;
+            let account_name = &default_context_setting.account_name;
+            wallet
+                .unlock(account_name, pw.as_bytes())
+                .with_context(|| {
+                    format!(
+                        "cannot unlock account `{account_name}` with the password file from {}",
+                        layer.source
+                    )
+                })?;
+        }
         // connect_lazy must run in async environment.
-        let (controller, executor, evm) = rt.block_on(async {
-            let co = Co::connect_lazy(
-                &default_context_setting.controller_addr,
-                Duration::from_secs(default_context_setting.connect_timeout),
-            )?;
-            let ex = Ex::connect_lazy(
-                &default_context_setting.executor_addr,
-                Duration::from_secs(default_context_setting.connect_timeout),
-            )?;
-            let ev = Ev::connect_lazy(
-                &default_context_setting.executor_addr,
-                Duration::from_secs(default_context_setting.connect_timeout),
-            )?;
-            anyhow::Ok((co, ex, ev))
-        })??;
+        let (controller, executor, evm) =
+            rt.block_on(async { connect(&default_context_setting) })??;
 
         Ok(Self {
             controller,
             executor,
             evm,
             wallet,
+            address_book,
+            current_context_name: context_name,
             config,
             current_setting: default_context_setting,
+            setting_sources,
             editor,
             rt,
         })
     }
 
-    pub fn current_account(&self) -> Result<&MultiCryptoAccount> {
-        let current_name = &self.current_setting.account_name;
-        let current = self
+    /// The current account for signing, a watch-only account is rejected before any tx is built.
+    pub fn current_signer(&self) -> Result<Signer<'_>> {
+        let signer = self.current_account()?;
+        signer.ensure_can_sign()?;
+        Ok(signer)
+    }
+
+    /// The signer of the current account, falls back to the key agent if it's locked in the wallet.
+    pub fn current_account(&self) -> Result<Signer<'_>> {
+        self.account_of(&self.current_setting, "current account")
+    }
+
+    /// The signer of another context's account, e.g. the destination chain of relaying.
+    /// Like [`Self::current_signer`], a watch-only account is rejected.
+    pub fn context_signer(&self, setting: &ContextSetting) -> Result<Signer<'_>> {
+        let signer = self.account_of(setting, "account")?;
+        signer.ensure_can_sign()?;
+        Ok(signer)
+    }
+
+    fn account_of(&self, setting: &ContextSetting, role: &str) -> Result<Signer<'_>> {
+        let name = &setting.account_name;
+        let account = self
             .wallet
-            .get(current_name)
-            // Specify that it's the **current** account not found
-            .map_err(|_| anyhow!("current account `{}` not found", current_name))?;
+            .get(name)
+            .map_err(|_| anyhow!("{role} `{name}` not found"))?;
 
         ensure!(
-            current.crypto_type() == self.current_setting.crypto_type,
-            "current account `{}`'s crypto type `{}` mismatched with target chain's crypto type `{}`",
-            current_name,
-            current.crypto_type(),
-            self.current_setting.crypto_type,
+            account.crypto_type() == setting.crypto_type,
+            "{role} `{}`'s crypto type `{}` mismatched with target chain's crypto type `{}`",
+            name,
+            account.crypto_type(),
+            setting.crypto_type,
         );
 
-        current
-            .unlocked()
-            .with_context(|| format!("cannot get current account `{current_name}` "))
+        match account {
+            MaybeLocked::Unlocked(account) => Ok(Signer::Local(account)),
+            MaybeLocked::Remote(account) => Ok(Signer::Remote(account)),
+            MaybeLocked::WatchOnly(account) => Ok(Signer::WatchOnly { name, account }),
+            MaybeLocked::Locked(_) => {
+                let agent = AgentClient::new(agent_socket_path(&self.config.data_dir));
+                agent
+                    .signer(account.address())
+                    .map(Signer::Agent)
+                    .ok_or_else(|| AccountLocked(name.clone()).into())
+            }
+        }
+    }
+
+    /// Resolve a hex address, `@name` in the address book of current context,
+    /// or a wallet account name.
+    pub fn resolve_addr(&self, s: &str) -> Result<Address> {
+        self.resolve_addr_in(&self.current_context_name, s)
+    }
+
+    /// Like [`Self::resolve_addr`], with the address book of the given context.
+    pub fn resolve_addr_in(&self, context_name: &str, s: &str) -> Result<Address> {
+        if let Some(name) = s.strip_prefix('@') {
+            return self
+                .address_book
+                .names(context_name)
+                .get(name)
+                .copied()
+                .ok_or_else(|| anyhow!("name `{s}` not found in the address book"));
+        }
+        parse_addr(s).or_else(|e| match self.wallet.get(s) {
+            Ok(account) => Ok(*account.address()),
+            Err(_) => Err(e.context(format!("`{s}` is neither an address nor an account name"))),
+        })
+    }
+
+    /// The name of a known address, the address book comes before the wallet.
+    pub fn addr_name(&self, addr: &[u8]) -> Option<String> {
+        let book_name = self
+            .address_book
+            .names(&self.current_context_name)
+            .into_iter()
+            .find(|(_, known)| known.as_slice() == addr)
+            .map(|(name, _)| format!("@{name}"));
+        book_name.or_else(|| {
+            self.wallet
+                .list()
+                .find(|(_, account)| account.address().as_slice() == addr)
+                .map(|(name, _)| name.clone())
+        })
+    }
+
+    /// Like [`Display::display`], with the names of known addresses.
+    pub fn display(&self, v: &impl Display) -> String {
+        let mut json = v.to_json();
+        annotate_addrs(&mut json, &|addr| self.addr_name(addr));
+        json.display()
     }
 
     pub fn current_controller_addr(&self) -> &str {
@@ -136,21 +251,7 @@ impl<Co, Ex, Ev> Context<Co, Ex, Ev> {
             return Ok(());
         }
 
-        let (controller, executor, evm) = self.rt.block_on(async {
-            let co = Co::connect_lazy(
-                &setting.controller_addr,
-                Duration::from_secs(setting.connect_timeout),
-            )?;
-            let ex = Ex::connect_lazy(
-                &setting.executor_addr,
-                Duration::from_secs(setting.connect_timeout),
-            )?;
-            let ev = Ev::connect_lazy(
-                &setting.executor_addr,
-                Duration::from_secs(setting.connect_timeout),
-            )?;
-            anyhow::Ok((co, ex, ev))
-        })??;
+        let (controller, executor, evm) = self.rt.block_on(async { connect(&setting) })??;
         self.controller = controller;
         self.executor = executor;
         self.evm = evm;
@@ -160,10 +261,30 @@ impl<Co, Ex, Ev> Context<Co, Ex, Ev> {
     }
 }
 
+// The clients of a context share the options, so that e.g. the token is resolved and
+// refreshed only once.
+fn connect<Co, Ex, Ev>(setting: &ContextSetting) -> Result<(Co, Ex, Ev)>
+where
+    Co: GrpcClientBehaviour,
+    Ex: GrpcClientBehaviour,
+    Ev: GrpcClientBehaviour,
+{
+    let opts = ConnectOptions::from_setting(setting)?;
+    let co = Co::connect_with_options(&setting.controller_addrs(), setting, &opts)?;
+    let ex = Ex::connect_with_options(&setting.executor_addrs(), setting, &opts)?;
+    let ev = Ev::connect_with_options(&setting.evm_addrs(), setting, &opts)?;
+    Ok((co, ex, ev))
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error("Canceled")]
 pub struct Canceled;
 
+/// The account is locked, interactive mode will ask for its password.
+#[derive(Debug, thiserror::Error)]
+#[error("account `{0}` is locked, please unlock it first(e.g. `cldi --ask-password [subcommand]` or `cldi agent add`)")]
+pub struct AccountLocked(pub String);
+
 pub struct CtrlCSignalCapturedRuntime(tokio::runtime::Runtime);
 
 impl CtrlCSignalCapturedRuntime {
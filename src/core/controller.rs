@@ -19,8 +19,9 @@ use anyhow::Result;
 
 use cita_cloud_proto::common::NodeStatus;
 use prost::Message;
-use tonic::transport::Channel;
+use tonic::{service::interceptor::InterceptedService, transport::Channel};
 
+use super::interceptor::MetadataInterceptor;
 use crate::crypto::{ArrayLike, Hash};
 use cita_cloud_proto::controller::CrossChainProof;
 use cita_cloud_proto::{
@@ -36,8 +37,9 @@ use overlord::types::{AggregatedSignature, Hash as OverlordHash, Proof as Overlo
 use rlp::Decodable;
 use rlp::Rlp;
 
-pub type ControllerClient =
-    cita_cloud_proto::controller::rpc_service_client::RpcServiceClient<Channel>;
+pub type ControllerClient = cita_cloud_proto::controller::rpc_service_client::RpcServiceClient<
+    InterceptedService<Channel, MetadataInterceptor>,
+>;
 
 pub struct CompactBlockWithStaterootProof {
     pub compact_block: CompactBlock,
@@ -98,6 +100,21 @@ pub trait ControllerBehaviour {
     async fn add_node(&self, multiaddr: String) -> Result<u32>;
     async fn parse_overlord_proof(&self, proof_bytes: Vec<u8>) -> Result<ProofWithValidators>;
     async fn get_cross_chain_proof(&self, hash: Hash) -> Result<CrossChainProof>;
+
+    /// Sign and send a normal tx. Backends with a different tx format should override this.
+    async fn sign_and_send_tx<S>(&self, signer: &S, tx: CloudNormalTransaction) -> Result<Hash>
+    where
+        S: SignerBehaviour + Send + Sync,
+    {
+        let raw = signer.sign_raw_tx(tx)?;
+        self.send_raw(raw).await
+    }
+
+    /// Check `count` txs like `tx` at once, before they are sent over other connections,
+    /// e.g. by bench. Only controllers guarding txs override this.
+    async fn check_txs(&self, _tx: &CloudNormalTransaction, _count: u64) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[tonic::async_trait]
@@ -281,11 +298,11 @@ impl ControllerBehaviour for ControllerClient {
 }
 
 pub trait SignerBehaviour {
-    fn hash(&self, msg: &[u8]) -> Vec<u8>;
+    fn hash(&self, msg: &[u8]) -> Result<Vec<u8>>;
     fn address(&self) -> &[u8];
-    fn sign(&self, msg: &[u8]) -> Vec<u8>;
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>>;
 
-    fn sign_raw_tx(&self, tx: CloudNormalTransaction) -> RawTransaction {
+    fn sign_raw_tx(&self, tx: CloudNormalTransaction) -> Result<RawTransaction> {
         // calc tx hash
         let tx_hash = {
             // build tx bytes
@@ -294,12 +311,12 @@ pub trait SignerBehaviour {
                 tx.encode(&mut buf).unwrap();
                 buf
             };
-            self.hash(tx_bytes.as_slice())
+            self.hash(tx_bytes.as_slice())?
         };
 
         // sign tx hash
         let sender = self.address().to_vec();
-        let signature = self.sign(tx_hash.as_slice()).to_vec();
+        let signature = self.sign(tx_hash.as_slice())?;
 
         // build raw tx
         let raw_tx = {
@@ -316,10 +333,10 @@ pub trait SignerBehaviour {
             }
         };
 
-        raw_tx
+        Ok(raw_tx)
     }
 
-    fn sign_raw_utxo(&self, utxo: CloudUtxoTransaction) -> RawTransaction {
+    fn sign_raw_utxo(&self, utxo: CloudUtxoTransaction) -> Result<RawTransaction> {
         // calc utxo hash
         let utxo_hash = {
             // build utxo bytes
@@ -328,12 +345,12 @@ pub trait SignerBehaviour {
                 utxo.encode(&mut buf).unwrap();
                 buf
             };
-            self.hash(utxo_bytes.as_slice())
+            self.hash(utxo_bytes.as_slice())?
         };
 
         // sign utxo hash
         let sender = self.address().to_vec();
-        let signature = self.sign(utxo_hash.as_slice()).to_vec();
+        let signature = self.sign(utxo_hash.as_slice())?;
 
         // build raw utxo
         let raw_utxo = {
@@ -350,7 +367,7 @@ pub trait SignerBehaviour {
             }
         };
 
-        raw_utxo
+        Ok(raw_utxo)
     }
 }
 
@@ -400,15 +417,16 @@ where
     where
         S: SignerBehaviour + Send + Sync,
     {
-        let raw = signer.sign_raw_tx(raw_tx);
-        self.send_raw(raw).await.context("failed to send raw")
+        self.sign_and_send_tx(signer, raw_tx)
+            .await
+            .context("failed to send raw")
     }
 
     async fn send_raw_utxo<S>(&self, signer: &S, raw_utxo: CloudUtxoTransaction) -> Result<Hash>
     where
         S: SignerBehaviour + Send + Sync,
     {
-        let raw = signer.sign_raw_utxo(raw_utxo);
+        let raw = signer.sign_raw_utxo(raw_utxo)?;
         self.send_raw(raw).await.context("failed to send raw")
     }
 
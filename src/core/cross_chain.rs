@@ -12,11 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::crypto::sm::sm3_hash;
-use crate::crypto::ArrayLike;
+use crate::config::CryptoType;
+use crate::crypto::{ArrayLike, Crypto, EthCrypto, SmCrypto};
 use crate::types::clean_0x;
+use crate::types::receipt::Receipt;
 use bytes::Bytes;
 use cita_cloud_proto::controller::{CrossChainProof, SystemConfig};
+use cita_cloud_proto::evm::ReceiptProof;
 use ethabi::ethereum_types::H256;
 use ophelia::{BlsSignatureVerify, HashValue};
 use ophelia_blst::{BlsPublicKey, BlsSignature};
@@ -24,8 +26,11 @@ use overlord::extract_voters;
 use overlord::types::{Node, Proof, Vote, VoteType};
 use prost::Message;
 use rlp::{Decodable, Rlp};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
+pub const CROSS_CHAIN_PROOF_BUNDLE_VERSION: u32 = 1;
+
 #[derive(Clone)]
 pub enum CrossChainResultCode {
     Success,
@@ -50,6 +55,7 @@ pub enum CrossChainResultCode {
     OutsideBlockBody,
     NoneBlockHeader,
     ChainIdVersionCheckError,
+    ReceiptTxHashCheckError,
 }
 
 impl CrossChainResultCode {
@@ -77,8 +83,14 @@ impl CrossChainResultCode {
             CrossChainResultCode::OutsideBlockBody => 113,
             CrossChainResultCode::NoneBlockHeader => 114,
             CrossChainResultCode::ChainIdVersionCheckError => 115,
+            CrossChainResultCode::ReceiptTxHashCheckError => 116,
         }
     }
+
+    /// Whether the proof is valid, though chain info or votes may be unchecked.
+    pub fn is_success(&self) -> bool {
+        self.code() < 100
+    }
 }
 
 impl fmt::Display for CrossChainResultCode {
@@ -116,6 +128,9 @@ impl fmt::Display for CrossChainResultCode {
             CrossChainResultCode::ChainIdVersionCheckError => {
                 write!(f, "Chain id or version check error")
             }
+            CrossChainResultCode::ReceiptTxHashCheckError => {
+                write!(f, "Receipt transaction hash check error")
+            }
             CrossChainResultCode::SuccessWithOutVotes => write!(f, "Success with out votes"),
             CrossChainResultCode::SuccessWithOutChainInfoAndVotes => {
                 write!(f, "Success with out chain info and votes")
@@ -134,7 +149,76 @@ impl fmt::Debug for CrossChainResultCode {
     }
 }
 
-pub fn verify_cross_chain_proof(
+/// A cross chain proof with the chain info and validators at its height,
+/// so that it can be verified offline. It's saved as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossChainProofBundle {
+    pub bundle_version: u32,
+    /// The context which the proof is fetched from.
+    pub context: String,
+    /// Unix timestamp in milliseconds.
+    pub fetched_at: u64,
+    pub crypto_type: CryptoType,
+    pub version: u32,
+    pub chain_id: String,
+    pub validators: Vec<String>,
+    /// The protobuf-encoded `CrossChainProof` in hex.
+    pub proof: String,
+}
+
+impl CrossChainProofBundle {
+    pub fn new(
+        context: String,
+        fetched_at: u64,
+        crypto_type: CryptoType,
+        ccp: &CrossChainProof,
+        sys_conf: &SystemConfig,
+    ) -> Self {
+        Self {
+            bundle_version: CROSS_CHAIN_PROOF_BUNDLE_VERSION,
+            context,
+            fetched_at,
+            crypto_type,
+            version: sys_conf.version,
+            chain_id: format!("0x{}", hex::encode(&sys_conf.chain_id)),
+            validators: sys_conf
+                .validators
+                .iter()
+                .map(|v| format!("0x{}", hex::encode(v)))
+                .collect(),
+            proof: format!("0x{}", hex::encode(ccp.encode_to_vec())),
+        }
+    }
+
+    pub fn verify(&self) -> Result<(), CrossChainResultCode> {
+        if self.bundle_version != CROSS_CHAIN_PROOF_BUNDLE_VERSION {
+            return Err(CrossChainResultCode::DecodeError(format!(
+                "unsupported bundle version `{}`",
+                self.bundle_version
+            )));
+        }
+        let ccp_bytes = hex::decode(clean_0x(&self.proof))
+            .map_err(|_| CrossChainResultCode::DecodeError("hex proof in bundle".to_string()))?;
+        let ccp = CrossChainProof::decode(ccp_bytes.as_slice())
+            .map_err(|_| CrossChainResultCode::DecodeError("proof in bundle".to_string()))?;
+        let sys_conf = SystemConfig {
+            version: self.version,
+            chain_id: hex::decode(clean_0x(&self.chain_id))
+                .map_err(|_| CrossChainResultCode::DecodeError("chain id in bundle".to_string()))?,
+            ..Default::default()
+        };
+        let validators = Some(self.validators.clone());
+
+        match self.crypto_type {
+            CryptoType::Sm => verify_cross_chain_proof::<SmCrypto>(ccp, validators, Some(sys_conf)),
+            CryptoType::Eth => {
+                verify_cross_chain_proof::<EthCrypto>(ccp, validators, Some(sys_conf))
+            }
+        }
+    }
+}
+
+pub fn verify_cross_chain_proof<C: Crypto>(
     ccp: CrossChainProof,
     validators: Option<Vec<String>>,
     sys_conf: Option<SystemConfig>,
@@ -143,10 +227,10 @@ pub fn verify_cross_chain_proof(
     let receipt_proof = ccp
         .receipt_proof
         .ok_or(CrossChainResultCode::NoneReceiptProof)?;
-    let receipt = receipt_proof.receipt;
 
     let roots_info = receipt_proof
         .roots_info
+        .clone()
         .ok_or(CrossChainResultCode::NoneRootsInfo)?;
     let app_hash: Vec<u8> = roots_info
         .state_root
@@ -158,21 +242,8 @@ pub fn verify_cross_chain_proof(
         return Err(CrossChainResultCode::StatRootCheckError);
     }
 
-    let receipt_proof: cita_merklehash::Proof = rlp::decode(&receipt_proof.receipt_proof)
-        .map_err(|_| CrossChainResultCode::DecodeError("receipt proof".to_string()))?;
-    let receipt_proof: static_merkle_tree::Proof<H256> = receipt_proof.into();
-
-    if !receipt_proof.verify(
-        &H256::from_slice(&roots_info.receipt_root),
-        H256(sm3_hash(&receipt)),
-        cita_merklehash::merge,
-    ) {
-        return Err(CrossChainResultCode::ReceiptProofCheckError);
-    }
+    let receipt = verify_receipt_proof::<C>(&receipt_proof, None)?;
 
-    let receipt: crate::types::receipt::Receipt = rlp::decode(&receipt).map_err(|_| {
-        CrossChainResultCode::DecodeError("proposal in cross chain proof".to_string())
-    })?;
     let compact_block = proposal
         .proposal
         .clone()
@@ -193,7 +264,7 @@ pub fn verify_cross_chain_proof(
             acc
         },
     );
-    if sm3_hash(&tx_list).to_vec()
+    if C::hash(&tx_list).to_vec()
         != compact_block
             .header
             .ok_or(CrossChainResultCode::NoneBlockHeader)?
@@ -220,70 +291,21 @@ pub fn verify_cross_chain_proof(
     }
 
     if let Some(validators) = validators {
-        let mut ol_nodes = Vec::new();
-        for v in validators {
-            ol_nodes.push(Node {
-                address: Bytes::copy_from_slice(&hex::decode(clean_0x(&v)).map_err(|_| {
-                    CrossChainResultCode::DecodeError(format!("hex address: {v:?}"))
-                })?),
-                propose_weight: 1,
-                vote_weight: 1,
+        let validators = validators
+            .iter()
+            .map(|v| {
+                hex::decode(clean_0x(v))
+                    .map_err(|_| CrossChainResultCode::DecodeError(format!("hex address: {v:?}")))
             })
-        }
+            .collect::<Result<Vec<_>, _>>()?;
 
         let mut proposal_bytes = Vec::with_capacity(proposal.encoded_len());
         proposal
             .encode(&mut proposal_bytes)
             .map_err(|_| CrossChainResultCode::EncodeError("proposal".to_string()))?;
+        let proposal_hash = C::hash(&proposal_bytes);
 
-        let proposal_hash = Bytes::from(sm3_hash(&proposal_bytes).to_vec());
-        let proof = Proof::decode(&Rlp::new(&ccp.proof))
-            .map_err(|_| CrossChainResultCode::DecodeError("decode proof failed".to_string()))?;
-
-        if proof.block_hash != proposal_hash || proof.height != roots_info.height {
-            return Err(CrossChainResultCode::ProposalHeightOrHashCheckError);
-        }
-        let signed_voters = extract_voters(&mut ol_nodes, &proof.signature.address_bitmap)
-            .map_err(|e| {
-                CrossChainResultCode::ConsensusInnerError(format!("extract_voters failed: {e:?}"))
-            })?;
-
-        let vote = Vote {
-            height: proof.height,
-            round: proof.round,
-            vote_type: VoteType::Precommit,
-            block_hash: Bytes::from(proof.block_hash.to_vec()),
-        };
-        let vote_bytes = rlp::encode(&vote);
-        let vote_hash = Bytes::from(sm3_hash(vote_bytes.as_ref()).to_vec());
-        let mut pub_keys = Vec::new();
-
-        for voter in signed_voters {
-            let pub_key = BlsPublicKey::try_from(voter.as_ref()).map_err(|_| {
-                CrossChainResultCode::BlsInnerError(format!(
-                    "can't parse {} to Bls public key",
-                    hex::encode(voter.as_ref())
-                ))
-            })?;
-            pub_keys.push(pub_key.clone());
-        }
-
-        let aggregate_key = BlsPublicKey::aggregate(pub_keys).map_err(|e| {
-            CrossChainResultCode::BlsInnerError(format!("BlsPublicKey::aggregate failed: {e:?}"))
-        })?;
-        let aggregated_signature = BlsSignature::try_from(proof.signature.signature.as_ref())
-            .map_err(|e| {
-                CrossChainResultCode::BlsInnerError(format!("BlsSignature::try_from failed: {e:?}"))
-            })?;
-        let hash = HashValue::try_from(vote_hash.as_ref()).map_err(|e| {
-            CrossChainResultCode::ThirdsInnerError(format!("try_from failed: {e:?}"))
-        })?;
-
-        aggregated_signature
-            .verify(&hash, &aggregate_key, &"".to_string())
-            .map_err(|e| {
-                CrossChainResultCode::BlsInnerError(format!("Verify BlsSignature failed: {e:?}"))
-            })?;
+        verify_overlord_proof::<C>(&ccp.proof, &proposal_hash, roots_info.height, &validators)?;
         if check_chain_info {
             Ok(())
         } else {
@@ -295,3 +317,436 @@ pub fn verify_cross_chain_proof(
         Err(CrossChainResultCode::SuccessWithOutChainInfoAndVotes)
     }
 }
+
+/// Merge two merkle tree nodes, the same as `cita_merklehash::merge` but with the hash of `C`.
+pub fn merge<C: Crypto>(left: &H256, right: &H256) -> H256 {
+    let mut content = [0u8; 64];
+    content[..32].copy_from_slice(left.as_bytes());
+    content[32..].copy_from_slice(right.as_bytes());
+    H256(C::hash(&content))
+}
+
+/// Verify that the receipt in `receipt_proof` is included in its `RootsInfo.receipt_root`,
+/// and belongs to `tx_hash` if given. Returns the decoded receipt on success.
+pub fn verify_receipt_proof<C: Crypto>(
+    receipt_proof: &ReceiptProof,
+    tx_hash: Option<&[u8]>,
+) -> Result<Receipt, CrossChainResultCode> {
+    let roots_info = receipt_proof
+        .roots_info
+        .as_ref()
+        .ok_or(CrossChainResultCode::NoneRootsInfo)?;
+
+    let proof: cita_merklehash::Proof = rlp::decode(&receipt_proof.receipt_proof)
+        .map_err(|_| CrossChainResultCode::DecodeError("receipt proof".to_string()))?;
+    let proof: static_merkle_tree::Proof<H256> = proof.into();
+
+    if roots_info.receipt_root.len() != H256::len_bytes() {
+        return Err(CrossChainResultCode::DecodeError(format!(
+            "receipt root of {} bytes",
+            roots_info.receipt_root.len()
+        )));
+    }
+    if !proof.verify(
+        &H256::from_slice(&roots_info.receipt_root),
+        H256(C::hash(&receipt_proof.receipt)),
+        merge::<C>,
+    ) {
+        return Err(CrossChainResultCode::ReceiptProofCheckError);
+    }
+
+    let receipt: Receipt = rlp::decode(&receipt_proof.receipt)
+        .map_err(|_| CrossChainResultCode::DecodeError("receipt".to_string()))?;
+    if let Some(tx_hash) = tx_hash {
+        if receipt.transaction_hash.as_bytes() != tx_hash {
+            return Err(CrossChainResultCode::ReceiptTxHashCheckError);
+        }
+    }
+
+    Ok(receipt)
+}
+
+/// Verify that an Overlord `proof` commits to `proposal_hash` at `height`,
+/// and that its aggregated BLS signature is made by more than 2/3 of the given validators.
+pub fn verify_overlord_proof<C: Crypto>(
+    proof: &[u8],
+    proposal_hash: &[u8],
+    height: u64,
+    validators: &[Vec<u8>],
+) -> Result<(), CrossChainResultCode> {
+    let mut ol_nodes = validators
+        .iter()
+        .map(|v| Node {
+            address: Bytes::copy_from_slice(v),
+            propose_weight: 1,
+            vote_weight: 1,
+        })
+        .collect::<Vec<_>>();
+
+    let proof = Proof::decode(&Rlp::new(proof))
+        .map_err(|_| CrossChainResultCode::DecodeError("decode proof failed".to_string()))?;
+
+    if proof.block_hash.as_ref() != proposal_hash || proof.height != height {
+        return Err(CrossChainResultCode::ProposalHeightOrHashCheckError);
+    }
+    let signed_voters =
+        extract_voters(&mut ol_nodes, &proof.signature.address_bitmap).map_err(|e| {
+            CrossChainResultCode::ConsensusInnerError(format!("extract_voters failed: {e:?}"))
+        })?;
+    // Every validator has a vote weight of 1.
+    if signed_voters.len() * 3 <= validators.len() * 2 {
+        return Err(CrossChainResultCode::ThirdsInnerError(format!(
+            "only {} of {} validators signed, less than 2/3",
+            signed_voters.len(),
+            validators.len()
+        )));
+    }
+
+    let vote = Vote {
+        height: proof.height,
+        round: proof.round,
+        vote_type: VoteType::Precommit,
+        block_hash: Bytes::from(proof.block_hash.to_vec()),
+    };
+    let vote_bytes = rlp::encode(&vote);
+    let vote_hash = Bytes::from(C::hash(vote_bytes.as_ref()).to_vec());
+    let mut pub_keys = Vec::new();
+
+    for voter in signed_voters {
+        let pub_key = BlsPublicKey::try_from(voter.as_ref()).map_err(|_| {
+            CrossChainResultCode::BlsInnerError(format!(
+                "can't parse {} to Bls public key",
+                hex::encode(voter.as_ref())
+            ))
+        })?;
+        pub_keys.push(pub_key.clone());
+    }
+
+    let aggregate_key = BlsPublicKey::aggregate(pub_keys).map_err(|e| {
+        CrossChainResultCode::BlsInnerError(format!("BlsPublicKey::aggregate failed: {e:?}"))
+    })?;
+    let aggregated_signature =
+        BlsSignature::try_from(proof.signature.signature.as_ref()).map_err(|e| {
+            CrossChainResultCode::BlsInnerError(format!("BlsSignature::try_from failed: {e:?}"))
+        })?;
+    let hash = HashValue::try_from(vote_hash.as_ref())
+        .map_err(|e| CrossChainResultCode::ThirdsInnerError(format!("try_from failed: {e:?}")))?;
+
+    aggregated_signature
+        .verify(&hash, &aggregate_key, &"".to_string())
+        .map_err(|e| {
+            CrossChainResultCode::BlsInnerError(format!("Verify BlsSignature failed: {e:?}"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mock::cross_chain_proof_fixture;
+    use cita_cloud_proto::blockchain::{BlockHeader, CompactBlock, CompactBlockBody};
+    use cita_cloud_proto::common::ProposalInner;
+    use cita_cloud_proto::evm::RootsInfo;
+
+    // RLP of an empty merkle proof, which is the proof of a single-leaf tree.
+    const EMPTY_MERKLE_PROOF: [u8; 1] = [0xc0];
+    const TX_HASH: [u8; 32] = [0x11; 32];
+
+    fn receipt_proof<C: Crypto>() -> ReceiptProof {
+        let receipt = Receipt {
+            transaction_hash: H256(TX_HASH),
+            ..Default::default()
+        };
+        let receipt = rlp::encode(&receipt).to_vec();
+        let receipt_root = C::hash(&receipt).to_vec();
+
+        ReceiptProof {
+            receipt,
+            receipt_proof: EMPTY_MERKLE_PROOF.to_vec(),
+            roots_info: Some(RootsInfo {
+                height: 100,
+                state_root: [0x22; 32].to_vec(),
+                receipt_root,
+            }),
+        }
+    }
+
+    fn cross_chain_proof<C: Crypto>() -> CrossChainProof {
+        let receipt_proof = receipt_proof::<C>();
+        let roots_info = receipt_proof.roots_info.clone().unwrap();
+        let state_root = roots_info
+            .state_root
+            .iter()
+            .zip(roots_info.receipt_root.iter())
+            .map(|(x, y)| x ^ y)
+            .collect();
+        let compact_block = CompactBlock {
+            version: 0,
+            header: Some(BlockHeader {
+                height: 100,
+                transactions_root: C::hash(&TX_HASH).to_vec(),
+                ..Default::default()
+            }),
+            body: Some(CompactBlockBody {
+                tx_hashes: vec![TX_HASH.to_vec()],
+            }),
+        };
+
+        CrossChainProof {
+            version: 0,
+            chain_id: [0x33; 32].to_vec(),
+            proposal: Some(ProposalInner {
+                pre_state_root: [0x44; 32].to_vec(),
+                proposal: Some(compact_block),
+            }),
+            receipt_proof: Some(receipt_proof),
+            proof: vec![],
+            state_root,
+        }
+    }
+
+    #[test]
+    fn test_bundle() {
+        let ccp = cross_chain_proof::<EthCrypto>();
+        let sys_conf = SystemConfig {
+            version: ccp.version,
+            chain_id: ccp.chain_id.clone(),
+            ..Default::default()
+        };
+        let bundle = CrossChainProofBundle::new("test".into(), 0, CryptoType::Eth, &ccp, &sys_conf);
+        let bundle: CrossChainProofBundle =
+            serde_json::from_str(&serde_json::to_string(&bundle).unwrap()).unwrap();
+        // chain info is checked, but there are no votes in this proof
+        assert_eq!(
+            bundle.verify().unwrap_err().code(),
+            CrossChainResultCode::SuccessNoneVotes.code()
+        );
+
+        let mut wrong_chain = bundle.clone();
+        wrong_chain.chain_id = "0x1234".into();
+        assert_eq!(
+            wrong_chain.verify().unwrap_err().code(),
+            CrossChainResultCode::ChainIdVersionCheckError.code()
+        );
+
+        let mut wrong_crypto = bundle;
+        wrong_crypto.crypto_type = CryptoType::Sm;
+        assert_eq!(
+            wrong_crypto.verify().unwrap_err().code(),
+            CrossChainResultCode::ReceiptProofCheckError.code()
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let (left, right) = (H256([1; 32]), H256([2; 32]));
+        let mut content = [1u8; 64];
+        content[32..].copy_from_slice(&[2; 32]);
+
+        assert_eq!(
+            merge::<SmCrypto>(&left, &right),
+            H256(SmCrypto::hash(&content))
+        );
+        assert_eq!(
+            merge::<EthCrypto>(&left, &right),
+            H256(EthCrypto::hash(&content))
+        );
+        assert_ne!(
+            merge::<SmCrypto>(&left, &right),
+            merge::<SmCrypto>(&right, &left)
+        );
+    }
+
+    #[test]
+    fn test_verify_receipt_proof() {
+        let sm_proof = receipt_proof::<SmCrypto>();
+        let eth_proof = receipt_proof::<EthCrypto>();
+
+        let receipt = verify_receipt_proof::<SmCrypto>(&sm_proof, Some(&TX_HASH)).unwrap();
+        assert_eq!(receipt.transaction_hash, H256(TX_HASH));
+        let receipt = verify_receipt_proof::<EthCrypto>(&eth_proof, Some(&TX_HASH)).unwrap();
+        assert_eq!(receipt.transaction_hash, H256(TX_HASH));
+
+        // hash algorithm mismatched
+        assert_eq!(
+            verify_receipt_proof::<EthCrypto>(&sm_proof, None)
+                .unwrap_err()
+                .code(),
+            CrossChainResultCode::ReceiptProofCheckError.code()
+        );
+        assert_eq!(
+            verify_receipt_proof::<SmCrypto>(&eth_proof, None)
+                .unwrap_err()
+                .code(),
+            CrossChainResultCode::ReceiptProofCheckError.code()
+        );
+        // receipt of another tx
+        assert_eq!(
+            verify_receipt_proof::<SmCrypto>(&sm_proof, Some(&[0x12; 32]))
+                .unwrap_err()
+                .code(),
+            CrossChainResultCode::ReceiptTxHashCheckError.code()
+        );
+        // malformed root is reported rather than panicking
+        let mut short_root = sm_proof;
+        short_root.roots_info.as_mut().unwrap().receipt_root = vec![0x12; 4];
+        assert_eq!(
+            verify_receipt_proof::<SmCrypto>(&short_root, None)
+                .unwrap_err()
+                .code(),
+            CrossChainResultCode::DecodeError(String::new()).code()
+        );
+    }
+
+    #[test]
+    fn test_verify_cross_chain_proof() {
+        let success_without_votes = CrossChainResultCode::SuccessWithOutChainInfoNoneVotes.code();
+
+        let sm_ccp = cross_chain_proof::<SmCrypto>();
+        assert_eq!(
+            verify_cross_chain_proof::<SmCrypto>(sm_ccp.clone(), None, None)
+                .unwrap_err()
+                .code(),
+            success_without_votes
+        );
+        let eth_ccp = cross_chain_proof::<EthCrypto>();
+        assert_eq!(
+            verify_cross_chain_proof::<EthCrypto>(eth_ccp.clone(), None, None)
+                .unwrap_err()
+                .code(),
+            success_without_votes
+        );
+
+        assert_eq!(
+            verify_cross_chain_proof::<EthCrypto>(sm_ccp.clone(), None, None)
+                .unwrap_err()
+                .code(),
+            CrossChainResultCode::ReceiptProofCheckError.code()
+        );
+        assert_eq!(
+            verify_cross_chain_proof::<SmCrypto>(eth_ccp, None, None)
+                .unwrap_err()
+                .code(),
+            CrossChainResultCode::ReceiptProofCheckError.code()
+        );
+
+        // tampered tx root
+        let mut tampered = sm_ccp;
+        tampered
+            .proposal
+            .as_mut()
+            .unwrap()
+            .proposal
+            .as_mut()
+            .unwrap()
+            .header
+            .as_mut()
+            .unwrap()
+            .transactions_root = [0; 32].to_vec();
+        assert_eq!(
+            verify_cross_chain_proof::<SmCrypto>(tampered, None, None)
+                .unwrap_err()
+                .code(),
+            CrossChainResultCode::TransactionRootCheckError.code()
+        );
+    }
+
+    #[test]
+    fn test_verify_fixtures() {
+        let sm_ccp = cross_chain_proof_fixture(include_str!("../../test/cross_chain/sm.json"));
+        let eth_ccp = cross_chain_proof_fixture(include_str!("../../test/cross_chain/eth.json"));
+
+        let receipt =
+            verify_receipt_proof::<SmCrypto>(sm_ccp.receipt_proof.as_ref().unwrap(), None).unwrap();
+        assert_eq!(
+            hex::encode(receipt.transaction_hash),
+            "0481f9f33e85496e6b17de93b612e27952cfae45f7502094e27e7ae7e2d8bc99"
+        );
+        assert_eq!(receipt.quota_used, 23000u64.into());
+        let receipt =
+            verify_receipt_proof::<EthCrypto>(eth_ccp.receipt_proof.as_ref().unwrap(), None)
+                .unwrap();
+        assert_eq!(
+            hex::encode(receipt.transaction_hash),
+            "d890e08891442a645af9bde8a6cfa24c6333676fb8ee43820e7b1898237a48ed"
+        );
+        assert_eq!(receipt.account_nonce, 9u64.into());
+
+        fn check<C: Crypto, Mismatched: Crypto>(ccp: &CrossChainProof) {
+            let sys_conf = SystemConfig {
+                version: ccp.version,
+                chain_id: ccp.chain_id.clone(),
+                ..Default::default()
+            };
+            assert_eq!(
+                verify_cross_chain_proof::<C>(ccp.clone(), None, Some(sys_conf))
+                    .unwrap_err()
+                    .code(),
+                CrossChainResultCode::SuccessNoneVotes.code()
+            );
+            assert_eq!(
+                verify_cross_chain_proof::<Mismatched>(ccp.clone(), None, None)
+                    .unwrap_err()
+                    .code(),
+                CrossChainResultCode::ReceiptProofCheckError.code()
+            );
+        }
+        check::<SmCrypto, EthCrypto>(&sm_ccp);
+        check::<EthCrypto, SmCrypto>(&eth_ccp);
+    }
+
+    #[test]
+    fn test_verify_overlord_proof_quorum() {
+        use overlord::types::AggregatedSignature;
+
+        let validators = (1..=4).map(|i| vec![i; 48]).collect::<Vec<_>>();
+        let proof_with_bitmap = |bitmap: u8| {
+            let proof = Proof {
+                height: 100,
+                round: 0,
+                block_hash: Bytes::from(vec![0x55; 32]),
+                signature: AggregatedSignature {
+                    signature: Bytes::from(vec![0; 96]),
+                    address_bitmap: Bytes::from(vec![bitmap]),
+                },
+            };
+            rlp::encode(&proof).to_vec()
+        };
+
+        // 1 of 4
+        let err = verify_overlord_proof::<SmCrypto>(
+            &proof_with_bitmap(0b1000_0000),
+            &[0x55; 32],
+            100,
+            &validators,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.code(),
+            CrossChainResultCode::ThirdsInnerError(String::new()).code()
+        );
+        // 2 of 4 is not more than 2/3 either
+        let err = verify_overlord_proof::<SmCrypto>(
+            &proof_with_bitmap(0b1100_0000),
+            &[0x55; 32],
+            100,
+            &validators,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.code(),
+            CrossChainResultCode::ThirdsInnerError(String::new()).code()
+        );
+        // 3 of 4 reaches the quorum, and fails on the fake keys
+        let err = verify_overlord_proof::<SmCrypto>(
+            &proof_with_bitmap(0b1110_0000),
+            &[0x55; 32],
+            100,
+            &validators,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.code(),
+            CrossChainResultCode::BlsInnerError(String::new()).code()
+        );
+    }
+}
@@ -0,0 +1,762 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, ensure, Result};
+use cita_cloud_proto::{
+    blockchain::{Block, CompactBlock, RawTransaction},
+    common::NodeStatus,
+    controller::{BlockNumber as CloudBlockNumber, CrossChainProof, SystemConfig},
+    evm::{
+        Balance, BlockNumber, ByteAbi, ByteCode, ByteQuota, Nonce, Receipt, ReceiptProof, RootsInfo,
+    },
+    executor::CallResponse,
+};
+use rand::Rng;
+use tonic::transport::Channel;
+
+use super::{
+    client::{ConnectOptions, GrpcClientBehaviour},
+    controller::{CompactBlockWithStaterootProof, ControllerBehaviour, ProofWithValidators},
+    evm::EvmBehaviour,
+    executor::ExecutorBehaviour,
+    interceptor::MetadataInterceptor,
+};
+use crate::{
+    config::{ContextSetting, EndpointStrategy, RetrySetting},
+    crypto::{Address, Hash},
+};
+
+/// How long a failed endpoint is deprioritized.
+const UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(30);
+/// How long to wait for the probe of a failed endpoint whose cooldown is over.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Clients of the same service on different nodes.
+///
+/// A call fails over to the next endpoint on transient errors, and is retried
+/// with backoff after all endpoints have failed, according to the retry setting.
+/// Other errors are returned as is. `send_raw` is only retried if the tx has not
+/// been sent, i.e. the connection could not be established.
+///
+/// A failed endpoint is put last for a cooldown. After that, it's probed by a TCP
+/// connection before taking calls again, and cools down once more if it's still down.
+pub struct EndpointPool<T> {
+    inner: Arc<Pool<T>>,
+}
+
+impl<T> Clone for EndpointPool<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+struct Pool<T> {
+    endpoints: Vec<Endpoint<T>>,
+    strategy: EndpointStrategy,
+    retry: RetrySetting,
+    // The preferred endpoint for failover, or the counter for round-robin.
+    next: AtomicUsize,
+}
+
+struct Endpoint<T> {
+    addr: String,
+    client: T,
+    health: Mutex<Health>,
+}
+
+#[derive(Default)]
+struct Health {
+    // Exponentially weighted moving average of call latencies.
+    latency: Option<Duration>,
+    unhealthy_until: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Retry {
+    OnTransientError,
+    OnlyIfUnsent,
+}
+
+impl<T> EndpointPool<T> {
+    pub fn new(
+        endpoints: Vec<(String, T)>,
+        strategy: EndpointStrategy,
+        retry: RetrySetting,
+    ) -> Result<Self> {
+        ensure!(!endpoints.is_empty(), "no endpoint is given");
+        let endpoints = endpoints
+            .into_iter()
+            .map(|(addr, client)| Endpoint {
+                addr,
+                client,
+                health: Mutex::new(Health::default()),
+            })
+            .collect();
+        Ok(Self {
+            inner: Arc::new(Pool {
+                endpoints,
+                strategy,
+                retry,
+                next: AtomicUsize::new(0),
+            }),
+        })
+    }
+
+    // The order of endpoints to try for a call.
+    fn order(&self) -> Vec<usize> {
+        let pool = &self.inner;
+        let n = pool.endpoints.len();
+        let start = match pool.strategy {
+            EndpointStrategy::Failover => pool.next.load(Ordering::Relaxed),
+            EndpointStrategy::RoundRobin => pool.next.fetch_add(1, Ordering::Relaxed),
+            EndpointStrategy::Latency => 0,
+        };
+        let mut order: Vec<usize> = (0..n).map(|i| (start + i) % n).collect();
+
+        let now = Instant::now();
+        let health = |i: usize| {
+            let health = pool.endpoints[i].health.lock().unwrap();
+            let is_unhealthy = health.unhealthy_until.is_some_and(|until| until > now);
+            (is_unhealthy, health.latency)
+        };
+        // Sorts are stable, so the strategy's order is kept for ties.
+        if pool.strategy == EndpointStrategy::Latency {
+            // Unmeasured endpoints go first to get measured.
+            order.sort_by_key(|&i| health(i).1.unwrap_or_default());
+        }
+        order.sort_by_key(|&i| health(i).0);
+        order
+    }
+
+    async fn request<R, F, Fut>(&self, retry: Retry, f: F) -> Result<R>
+    where
+        T: Clone,
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        match self.inner.retry.deadline {
+            Some(secs) => {
+                let deadline = Duration::from_secs(secs);
+                tokio::time::timeout(deadline, self.try_request(retry, f))
+                    .await
+                    .map_err(|_| anyhow!("call didn't complete within {secs}s"))?
+            }
+            None => self.try_request(retry, f).await,
+        }
+    }
+
+    async fn try_request<R, F, Fut>(&self, retry: Retry, f: F) -> Result<R>
+    where
+        T: Clone,
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        let pool = &self.inner;
+        let max_attempts = pool.retry.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let mut last_err = None;
+            for i in self.order() {
+                let endpoint = &pool.endpoints[i];
+                if endpoint.is_recheck_due() {
+                    if let Err(e) = endpoint.probe().await {
+                        endpoint.fail();
+                        last_err.replace(e);
+                        continue;
+                    }
+                }
+                let begin = Instant::now();
+                match f(endpoint.client.clone()).await {
+                    Ok(resp) => {
+                        endpoint.succeed(begin.elapsed());
+                        if pool.strategy == EndpointStrategy::Failover {
+                            pool.next.store(i, Ordering::Relaxed);
+                        }
+                        return Ok(resp);
+                    }
+                    Err(e) if is_transient_error(&e) => {
+                        endpoint.fail();
+                        let e = e.context(format!("endpoint `{}` is unavailable", endpoint.addr));
+                        if retry == Retry::OnlyIfUnsent && !is_connect_error(&e) {
+                            return Err(e);
+                        }
+                        last_err.replace(e);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            if attempt >= max_attempts {
+                return Err(last_err.unwrap());
+            }
+            tokio::time::sleep(backoff(&pool.retry, attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+impl<T> Endpoint<T> {
+    fn succeed(&self, latency: Duration) {
+        let mut health = self.health.lock().unwrap();
+        health.latency = Some(match health.latency {
+            Some(avg) => (avg * 4 + latency) / 5,
+            None => latency,
+        });
+        health.unhealthy_until = None;
+    }
+
+    fn fail(&self) {
+        let mut health = self.health.lock().unwrap();
+        health.unhealthy_until = Some(Instant::now() + UNHEALTHY_COOLDOWN);
+    }
+
+    // Failed before and the cooldown is over.
+    fn is_recheck_due(&self) -> bool {
+        let health = self.health.lock().unwrap();
+        health
+            .unhealthy_until
+            .is_some_and(|until| until <= Instant::now())
+    }
+
+    // The endpoint is healthy again if it accepts connections. The probe doesn't
+    // affect the latency, which is for calls only.
+    async fn probe(&self) -> Result<()> {
+        let connect = tokio::net::TcpStream::connect(self.addr.as_str());
+        match tokio::time::timeout(PROBE_TIMEOUT, connect).await {
+            Ok(Ok(_)) => {
+                self.health.lock().unwrap().unhealthy_until = None;
+                Ok(())
+            }
+            Ok(Err(e)) => Err(anyhow::Error::new(e)
+                .context(format!("endpoint `{}` is still unavailable", self.addr))),
+            Err(_) => Err(anyhow!(
+                "endpoint `{}` is still unavailable, probe timed out",
+                self.addr
+            )),
+        }
+    }
+}
+
+/// Exponential backoff with equal jitter, so that clients don't retry in lockstep.
+pub fn backoff(setting: &RetrySetting, attempt: u32) -> Duration {
+    let exp = setting
+        .initial_backoff_ms
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(setting.max_backoff_ms);
+    let jittered = exp / 2 + rand::thread_rng().gen_range(0..=exp / 2);
+    Duration::from_millis(jittered)
+}
+
+/// Errors worth retrying, i.e. the endpoint is unreachable or too slow.
+pub fn is_transient_error(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| {
+        cause.is::<tonic::transport::Error>()
+            || cause.downcast_ref::<tonic::Status>().is_some_and(|status| {
+                matches!(
+                    status.code(),
+                    tonic::Code::Unavailable | tonic::Code::DeadlineExceeded
+                )
+            })
+    })
+}
+
+/// The service answers that the requested thing is not found, e.g. a receipt of a pending tx.
+pub fn is_not_found(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| {
+        cause
+            .downcast_ref::<tonic::Status>()
+            .is_some_and(|status| status.code() == tonic::Code::NotFound)
+    })
+}
+
+// Whether the request failed before being sent, i.e. no connection to the endpoint.
+//
+// Only the typed errors of connecting are trusted, which tonic keeps as the source of
+// the `Unavailable` status. Anything else, e.g. a reset stream, may happen after
+// the request is sent.
+fn is_connect_error(e: &anyhow::Error) -> bool {
+    use std::io::ErrorKind;
+
+    e.chain().any(|cause| {
+        // Failed to connect, including connect timeout and TLS handshake.
+        cause.is::<tonic::ConnectError>()
+            || cause.downcast_ref::<std::io::Error>().is_some_and(|e| {
+                matches!(
+                    e.kind(),
+                    ErrorKind::ConnectionRefused
+                        | ErrorKind::AddrNotAvailable
+                        | ErrorKind::NotConnected
+                )
+            })
+    })
+}
+
+#[tonic::async_trait]
+impl<T> GrpcClientBehaviour for EndpointPool<T>
+where
+    T: GrpcClientBehaviour + Send + Sync,
+{
+    fn from_channel(ch: Channel, interceptor: MetadataInterceptor) -> Self {
+        let client = T::from_channel(ch, interceptor);
+        Self::new(
+            vec![(String::new(), client)],
+            EndpointStrategy::Failover,
+            RetrySetting::default(),
+        )
+        .unwrap()
+    }
+
+    fn connect_with_options(
+        addrs: &[String],
+        setting: &ContextSetting,
+        opts: &ConnectOptions,
+    ) -> Result<Self> {
+        let timeout = Duration::from_secs(setting.connect_timeout);
+        let endpoints = addrs
+            .iter()
+            .map(|addr| Ok((addr.clone(), T::connect_lazy(addr, timeout, opts)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(endpoints, setting.endpoint_strategy, setting.retry)
+    }
+}
+
+#[tonic::async_trait]
+impl<T> ControllerBehaviour for EndpointPool<T>
+where
+    T: ControllerBehaviour + Clone + Send + Sync,
+{
+    async fn send_raw(&self, raw: RawTransaction) -> Result<Hash> {
+        self.request(Retry::OnlyIfUnsent, |c| {
+            let raw = raw.clone();
+            async move { c.send_raw(raw).await }
+        })
+        .await
+    }
+
+    async fn get_system_config(&self) -> Result<SystemConfig> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_system_config().await
+        })
+        .await
+    }
+
+    async fn get_system_config_by_number(&self, block_number: u64) -> Result<SystemConfig> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_system_config_by_number(block_number).await
+        })
+        .await
+    }
+
+    async fn get_block_number(&self, for_pending: bool) -> Result<u64> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_block_number(for_pending).await
+        })
+        .await
+    }
+
+    async fn get_block_hash(&self, block_number: u64) -> Result<Hash> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_block_hash(block_number).await
+        })
+        .await
+    }
+
+    async fn get_height_by_hash(&self, hash: Hash) -> Result<CloudBlockNumber> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_height_by_hash(hash).await
+        })
+        .await
+    }
+
+    async fn get_compact_block_by_number(&self, block_number: u64) -> Result<CompactBlock> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_compact_block_by_number(block_number).await
+        })
+        .await
+    }
+
+    async fn get_block_by_number(
+        &self,
+        block_number: u64,
+    ) -> Result<CompactBlockWithStaterootProof> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_block_by_number(block_number).await
+        })
+        .await
+    }
+
+    async fn get_block_detail_by_number(&self, block_number: u64) -> Result<Block> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_block_detail_by_number(block_number).await
+        })
+        .await
+    }
+
+    async fn get_tx(&self, tx_hash: Hash) -> Result<RawTransaction> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_tx(tx_hash).await
+        })
+        .await
+    }
+
+    async fn get_tx_index(&self, tx_hash: Hash) -> Result<u64> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_tx_index(tx_hash).await
+        })
+        .await
+    }
+
+    async fn get_tx_block_number(&self, tx_hash: Hash) -> Result<u64> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_tx_block_number(tx_hash).await
+        })
+        .await
+    }
+
+    async fn get_node_status(&self) -> Result<NodeStatus> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_node_status().await
+        })
+        .await
+    }
+
+    async fn add_node(&self, multiaddr: String) -> Result<u32> {
+        // Adding node is a mutation, don't repeat it on another node.
+        self.request(Retry::OnlyIfUnsent, |c| {
+            let multiaddr = multiaddr.clone();
+            async move { c.add_node(multiaddr).await }
+        })
+        .await
+    }
+
+    async fn parse_overlord_proof(&self, proof_bytes: Vec<u8>) -> Result<ProofWithValidators> {
+        self.request(Retry::OnTransientError, |c| {
+            let proof_bytes = proof_bytes.clone();
+            async move { c.parse_overlord_proof(proof_bytes).await }
+        })
+        .await
+    }
+
+    async fn get_cross_chain_proof(&self, hash: Hash) -> Result<CrossChainProof> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_cross_chain_proof(hash).await
+        })
+        .await
+    }
+}
+
+#[tonic::async_trait]
+impl<T> ExecutorBehaviour for EndpointPool<T>
+where
+    T: ExecutorBehaviour + Clone + Send + Sync,
+{
+    async fn call(
+        &self,
+        from: Address,
+        to: Address,
+        data: Vec<u8>,
+        height: u64,
+    ) -> Result<CallResponse> {
+        self.request(Retry::OnTransientError, |c| {
+            let data = data.clone();
+            async move { c.call(from, to, data, height).await }
+        })
+        .await
+    }
+}
+
+#[tonic::async_trait]
+impl<T> EvmBehaviour for EndpointPool<T>
+where
+    T: EvmBehaviour + Clone + Send + Sync,
+{
+    async fn get_receipt(&self, hash: Hash) -> Result<Receipt> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_receipt(hash).await
+        })
+        .await
+    }
+
+    async fn get_code(&self, addr: Address, block_number: BlockNumber) -> Result<ByteCode> {
+        self.request(Retry::OnTransientError, |c| {
+            let block_number = block_number.clone();
+            async move { c.get_code(addr, block_number).await }
+        })
+        .await
+    }
+
+    async fn get_balance(&self, addr: Address, block_number: BlockNumber) -> Result<Balance> {
+        self.request(Retry::OnTransientError, |c| {
+            let block_number = block_number.clone();
+            async move { c.get_balance(addr, block_number).await }
+        })
+        .await
+    }
+
+    async fn get_tx_count(&self, addr: Address, block_number: BlockNumber) -> Result<Nonce> {
+        self.request(Retry::OnTransientError, |c| {
+            let block_number = block_number.clone();
+            async move { c.get_tx_count(addr, block_number).await }
+        })
+        .await
+    }
+
+    async fn get_abi(&self, addr: Address, block_number: BlockNumber) -> Result<ByteAbi> {
+        self.request(Retry::OnTransientError, |c| {
+            let block_number = block_number.clone();
+            async move { c.get_abi(addr, block_number).await }
+        })
+        .await
+    }
+
+    async fn estimate_quota(
+        &self,
+        from: Vec<u8>,
+        to: Vec<u8>,
+        method: Vec<u8>,
+    ) -> Result<ByteQuota> {
+        self.request(Retry::OnTransientError, |c| {
+            let (from, to, method) = (from.clone(), to.clone(), method.clone());
+            async move { c.estimate_quota(from, to, method).await }
+        })
+        .await
+    }
+
+    async fn get_receipt_proof(&self, hash: Hash) -> Result<ReceiptProof> {
+        self.request(Retry::OnTransientError, |c| async move {
+            c.get_receipt_proof(hash).await
+        })
+        .await
+    }
+
+    async fn get_roots_info(&self, block_number: BlockNumber) -> Result<RootsInfo> {
+        self.request(Retry::OnTransientError, |c| {
+            let block_number = block_number.clone();
+            async move { c.get_roots_info(block_number).await }
+        })
+        .await
+    }
+
+    async fn get_storage_at(
+        &self,
+        addr: Address,
+        position: Hash,
+        block_number: BlockNumber,
+    ) -> Result<Hash> {
+        self.request(Retry::OnTransientError, |c| {
+            let block_number = block_number.clone();
+            async move { c.get_storage_at(addr, position, block_number).await }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mock::MockControllerClient;
+
+    fn unavailable() -> anyhow::Error {
+        tonic::Status::unavailable("node is down").into()
+    }
+
+    fn mock_client(block_number: Option<u64>) -> MockControllerClient {
+        let mut client = MockControllerClient::default();
+        client.expect_clone().returning(move || {
+            let mut client = MockControllerClient::default();
+            client
+                .expect_get_block_number()
+                .returning(move |_| block_number.ok_or_else(unavailable));
+            client.expect_send_raw().returning(|_| Err(unavailable()));
+            client
+        });
+        client
+    }
+
+    #[test]
+    fn test_endpoint_pool() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let pool = EndpointPool::new(
+                vec![
+                    ("down".into(), mock_client(None)),
+                    ("up".into(), mock_client(Some(42))),
+                ],
+                EndpointStrategy::Failover,
+                RetrySetting::default(),
+            )
+            .unwrap();
+            // Fail over to the healthy one, and stick to it.
+            assert_eq!(pool.get_block_number(false).await.unwrap(), 42);
+            assert_eq!(pool.order(), vec![1, 0]);
+
+            // Not retried since the request may have been sent.
+            let err = pool.send_raw(RawTransaction::default()).await.unwrap_err();
+            assert!(err.to_string().contains("`up`"));
+
+            let pool = EndpointPool::new(
+                vec![
+                    ("a".into(), mock_client(Some(1))),
+                    ("b".into(), mock_client(Some(2))),
+                ],
+                EndpointStrategy::RoundRobin,
+                RetrySetting::default(),
+            )
+            .unwrap();
+            assert_eq!(pool.get_block_number(false).await.unwrap(), 1);
+            assert_eq!(pool.get_block_number(false).await.unwrap(), 2);
+            assert_eq!(pool.get_block_number(false).await.unwrap(), 1);
+        });
+    }
+
+    // A client that fails with `DeadlineExceeded` for the first `n` calls.
+    fn flaky_client(calls: Arc<AtomicUsize>, n: usize) -> MockControllerClient {
+        let mut client = MockControllerClient::default();
+        client.expect_clone().returning(move || {
+            let calls = calls.clone();
+            let mut client = MockControllerClient::default();
+            client.expect_get_block_number().returning(move |_| {
+                if calls.fetch_add(1, Ordering::SeqCst) < n {
+                    Err(tonic::Status::deadline_exceeded("node is busy").into())
+                } else {
+                    Ok(42)
+                }
+            });
+            client
+        });
+        client
+    }
+
+    #[test]
+    fn test_retry() {
+        let retry = RetrySetting {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 2,
+            deadline: None,
+        };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            // Recovered after failing twice.
+            let calls = Arc::new(AtomicUsize::new(0));
+            let client = flaky_client(calls.clone(), 2);
+            let pool = EndpointPool::new(
+                vec![("node".into(), client)],
+                EndpointStrategy::Failover,
+                retry,
+            )
+            .unwrap();
+            assert_eq!(pool.get_block_number(false).await.unwrap(), 42);
+            assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+            // Give up after max attempts.
+            let calls = Arc::new(AtomicUsize::new(0));
+            let client = flaky_client(calls.clone(), 3);
+            let pool = EndpointPool::new(
+                vec![("node".into(), client)],
+                EndpointStrategy::Failover,
+                retry,
+            )
+            .unwrap();
+            assert!(pool.get_block_number(false).await.is_err());
+            assert_eq!(calls.load(Ordering::SeqCst), 3);
+        });
+    }
+
+    #[test]
+    fn test_probe() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let up = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let down = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let down_addr = down.local_addr().unwrap().to_string();
+            drop(down);
+
+            let pool = EndpointPool::new(
+                vec![
+                    (down_addr, mock_client(Some(1))),
+                    (up.local_addr().unwrap().to_string(), mock_client(Some(2))),
+                ],
+                EndpointStrategy::Failover,
+                RetrySetting::default(),
+            )
+            .unwrap();
+            // Both failed before and their cooldowns are over.
+            for endpoint in &pool.inner.endpoints {
+                endpoint.health.lock().unwrap().unhealthy_until = Some(Instant::now());
+            }
+            // The one still down is skipped and cools down again.
+            assert_eq!(pool.get_block_number(false).await.unwrap(), 2);
+            assert!(
+                pool.inner.endpoints[0]
+                    .health
+                    .lock()
+                    .unwrap()
+                    .unhealthy_until
+                    > Some(Instant::now())
+            );
+            assert!(pool.inner.endpoints[1]
+                .health
+                .lock()
+                .unwrap()
+                .unhealthy_until
+                .is_none());
+            assert_eq!(pool.order(), vec![1, 0]);
+        });
+    }
+
+    #[test]
+    fn test_is_connect_error() {
+        use std::io::{Error, ErrorKind};
+
+        let refused = anyhow::Error::new(Error::from(ErrorKind::ConnectionRefused))
+            .context(tonic::Status::unavailable("tcp connect error"));
+        assert!(is_connect_error(&refused));
+        // The message alone doesn't tell whether the request is sent.
+        assert!(!is_connect_error(
+            &tonic::Status::unavailable("tcp connect error").into()
+        ));
+        let reset = anyhow::Error::new(Error::from(ErrorKind::ConnectionReset));
+        assert!(!is_connect_error(&reset));
+
+        // What tonic's channel returns when it cannot connect.
+        let timeout = tonic::ConnectError(Box::new(Error::from(ErrorKind::TimedOut)));
+        let status = tonic::Status::from_error(Box::new(timeout));
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+        assert!(is_connect_error(&status.into()));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let setting = RetrySetting {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1000,
+            ..Default::default()
+        };
+        let backoff = |attempt| backoff(&setting, attempt).as_millis();
+        assert!((50..=100).contains(&backoff(1)));
+        assert!((200..=400).contains(&backoff(3)));
+        assert!((500..=1000).contains(&backoff(10)));
+    }
+}
@@ -19,9 +19,10 @@ use cita_cloud_proto::evm::{
     GetTransactionCountRequest, ReceiptProof, RootsInfo,
 };
 use cita_cloud_proto::executor::CallRequest;
-use tonic::transport::Channel;
+use tonic::{service::interceptor::InterceptedService, transport::Channel};
 
 use super::controller::{SignerBehaviour, TransactionSenderBehaviour};
+use super::interceptor::MetadataInterceptor;
 use crate::types::H256;
 use crate::{
     crypto::{Address, ArrayLike, Hash},
@@ -55,7 +56,9 @@ pub mod constant {
     pub const AMEND_BALANCE: &str = "0x05";
 }
 
-pub type EvmClient = cita_cloud_proto::evm::rpc_service_client::RpcServiceClient<Channel>;
+pub type EvmClient = cita_cloud_proto::evm::rpc_service_client::RpcServiceClient<
+    InterceptedService<Channel, MetadataInterceptor>,
+>;
 
 #[tonic::async_trait]
 pub trait EvmBehaviour {
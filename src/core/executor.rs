@@ -13,13 +13,16 @@
 // limitations under the License.
 
 use anyhow::{Context, Result};
-use tonic::transport::Channel;
+use tonic::{service::interceptor::InterceptedService, transport::Channel};
 
+use super::interceptor::MetadataInterceptor;
 use crate::crypto::{Address, ArrayLike};
 use cita_cloud_proto::executor::{CallRequest, CallResponse};
 
 pub type ExecutorClient =
-    cita_cloud_proto::executor::executor_service_client::ExecutorServiceClient<Channel>;
+    cita_cloud_proto::executor::executor_service_client::ExecutorServiceClient<
+        InterceptedService<Channel, MetadataInterceptor>,
+    >;
 
 #[tonic::async_trait]
 pub trait ExecutorBehaviour {
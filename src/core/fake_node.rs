@@ -0,0 +1,922 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An in-memory fake CITA-Cloud node for testing.
+//!
+//! It serves the controller, executor and evm services on a single port,
+//! produces a block every `block_interval` seconds, and keeps balances,
+//! contract codes, ABIs and receipts of the txs it has executed.
+//!
+//! It is NOT a real chain:
+//! - there is no consensus, the block proof is empty as Raft's, and so validators
+//!   are normal addresses rather than BLS public keys of Overlord,
+//! - no EVM is run, contract creation only stores the code, and calls return empty data,
+//! - only the latest state is kept, the block number of evm queries is ignored,
+//! - the state root is a hash chain of the committed txs.
+
+use std::{
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use cita_cloud_proto::{
+    blockchain::{
+        raw_transaction::Tx, Block, BlockHeader, CompactBlock, CompactBlockBody, RawTransaction,
+        RawTransactions, Transaction as CloudNormalTransaction, UnverifiedTransaction,
+        UnverifiedUtxoTransaction, UtxoTransaction as CloudUtxoTransaction, Witness,
+    },
+    common::{
+        Empty, Hash as CloudHash, HashResponse, Hashes, NodeNetInfo, NodeStatus, PeerStatus, Proof,
+        StateRoot, StatusCode,
+    },
+    controller::{
+        rpc_service_server::{RpcService, RpcServiceServer},
+        BlockNumber, CrossChainProof, Flag, SystemConfig, TransactionIndex,
+    },
+    evm::{
+        rpc_service_server::{
+            RpcService as EvmRpcService, RpcServiceServer as EvmRpcServiceServer,
+        },
+        Balance, ByteAbi, ByteCode, ByteQuota, GetAbiRequest, GetBalanceRequest, GetCodeRequest,
+        GetStorageAtRequest, GetTransactionCountRequest, Nonce, Receipt, ReceiptProof, RootsInfo,
+    },
+    executor::{
+        executor_service_server::{ExecutorService, ExecutorServiceServer},
+        CallRequest, CallResponse,
+    },
+};
+use ethereum_types::U256;
+use prost::Message;
+use tokio::{net::TcpListener, task::JoinHandle};
+use tonic::{
+    transport::{server::TcpIncoming, Server},
+    Request, Response, Status,
+};
+
+use super::{controller::UtxoType, evm::constant::ABI_ADDRESS};
+use crate::{
+    crypto::{Address, ArrayLike, Crypto, Hash, ADDR_BYTES_LEN},
+    utils::{hex, parse_addr},
+};
+
+const FAKE_NODE_VERSION: &str = "fake-node";
+// The quota used by every tx since no EVM is run.
+const TX_QUOTA_USED: u64 = 21000;
+// Validators of Raft, which the fake node's empty proofs stand for, are normal addresses.
+const VALIDATOR_ADDR_LEN: usize = ADDR_BYTES_LEN;
+
+struct FakeBlock {
+    block: Block,
+    hash: Hash,
+    // The system config when this block is committed.
+    system_config: SystemConfig,
+}
+
+impl FakeBlock {
+    fn compact(&self) -> CompactBlock {
+        let tx_hashes = self
+            .block
+            .body
+            .iter()
+            .flat_map(|body| body.body.iter())
+            .map(|raw| tx_hash_of(raw).to_vec())
+            .collect();
+        CompactBlock {
+            version: self.block.version,
+            header: self.block.header.clone(),
+            body: Some(CompactBlockBody { tx_hashes }),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ChainState {
+    blocks: Vec<FakeBlock>,
+    block_heights: HashMap<Hash, u64>,
+
+    pool: Vec<RawTransaction>,
+    pool_hashes: HashSet<Hash>,
+    // tx hash -> (height, index)
+    tx_positions: HashMap<Hash, (u64, u64)>,
+    receipts: HashMap<Hash, Receipt>,
+
+    balances: HashMap<Address, U256>,
+    nonces: HashMap<Address, U256>,
+    codes: HashMap<Address, Vec<u8>>,
+    abis: HashMap<Address, Vec<u8>>,
+
+    system_config: SystemConfig,
+}
+
+impl ChainState {
+    fn height(&self) -> u64 {
+        self.blocks.len() as u64 - 1
+    }
+
+    fn block(&self, height: u64) -> Result<&FakeBlock, Status> {
+        self.blocks
+            .get(height as usize)
+            .ok_or_else(|| Status::not_found(format!("block `{height}` not found")))
+    }
+
+    fn state_root(&self) -> Vec<u8> {
+        self.blocks
+            .last()
+            .map(|b| b.block.state_root.clone())
+            .unwrap_or_else(|| vec![0; 32])
+    }
+
+    fn tx(&self, tx_hash: &Hash) -> Result<&RawTransaction, Status> {
+        let &(height, index) = self
+            .tx_positions
+            .get(tx_hash)
+            .ok_or_else(|| Status::not_found(format!("tx `{}` not found", hex(tx_hash))))?;
+        Ok(&self.block(height)?.block.body.as_ref().unwrap().body[index as usize])
+    }
+}
+
+/// The fake node, cheap to clone. Use [`FakeNode::spawn`] to run it.
+pub struct FakeNode<C> {
+    state: Arc<Mutex<ChainState>>,
+    _crypto: PhantomData<fn() -> C>,
+}
+
+impl<C> Clone for FakeNode<C> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            _crypto: PhantomData,
+        }
+    }
+}
+
+impl<C: Crypto> FakeNode<C> {
+    /// Create a node with a genesis block, `admin` is also the only validator.
+    pub fn new(admin: Address) -> Self {
+        let system_config = SystemConfig {
+            version: 0,
+            chain_id: rand::random::<[u8; 32]>().to_vec(),
+            admin: admin.to_vec(),
+            block_interval: 3,
+            validators: vec![admin.to_vec()],
+            emergency_brake: false,
+            quota_limit: 1_073_741_824,
+            block_limit: 100,
+            version_pre_hash: vec![0; 32],
+            chain_id_pre_hash: vec![0; 32],
+            admin_pre_hash: vec![0; 32],
+            block_interval_pre_hash: vec![0; 32],
+            validators_pre_hash: vec![0; 32],
+            emergency_brake_pre_hash: vec![0; 32],
+            quota_limit_pre_hash: vec![0; 32],
+            block_limit_pre_hash: vec![0; 32],
+            ..Default::default()
+        };
+        let mut state = ChainState {
+            system_config,
+            ..Default::default()
+        };
+        commit_block::<C>(&mut state, vec![]);
+
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            _crypto: PhantomData,
+        }
+    }
+
+    pub fn with_balance(self, addr: Address, balance: U256) -> Self {
+        self.lock().balances.insert(addr, balance);
+        self
+    }
+
+    pub fn with_block_interval(self, block_interval: u32) -> Self {
+        self.lock().system_config.block_interval = block_interval;
+        self
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.lock().height()
+    }
+
+    /// Execute the txs in pool and commit them in a new block.
+    pub fn produce_block(&self) {
+        let mut state = self.lock();
+        let txs = std::mem::take(&mut state.pool);
+        state.pool_hashes.clear();
+        commit_block::<C>(&mut state, txs);
+    }
+
+    /// Serve on `addr` in background, returns the address it listens on, e.g. the random
+    /// port for port 0, and the handle of the server.
+    pub async fn spawn(self, addr: SocketAddr) -> Result<(SocketAddr, JoinHandle<Result<()>>)> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("cannot listen on `{addr}`"))?;
+        let addr = listener.local_addr()?;
+        Ok((addr, tokio::spawn(self.serve_with_listener(listener))))
+    }
+
+    async fn serve_with_listener(self, listener: TcpListener) -> Result<()> {
+        let producer = {
+            let node = self.clone();
+            tokio::spawn(async move {
+                loop {
+                    // Block interval may be changed by the admin.
+                    let block_interval = node.lock().system_config.block_interval.max(1);
+                    tokio::time::sleep(Duration::from_secs(block_interval.into())).await;
+                    node.produce_block();
+                }
+            })
+        };
+        let incoming = TcpIncoming::from_listener(listener, true, None).map_err(|e| anyhow!(e))?;
+        let res = Server::builder()
+            .add_service(RpcServiceServer::new(self.clone()))
+            .add_service(ExecutorServiceServer::new(self.clone()))
+            .add_service(EvmRpcServiceServer::new(self))
+            .serve_with_incoming(incoming)
+            .await;
+        producer.abort();
+
+        res.context("fake node stopped")
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ChainState> {
+        // Nothing panics while holding the lock.
+        self.state.lock().unwrap()
+    }
+
+    // Check the tx and add it to the pool under one lock, so that the same tx sent
+    // concurrently can't enter the pool twice.
+    fn add_to_pool(&self, raw: RawTransaction) -> Result<Hash, Status> {
+        let mut state = self.lock();
+        let tx_hash = Self::check_tx(&state, &raw)?;
+        state.pool.push(raw);
+        state.pool_hashes.insert(tx_hash);
+        Ok(tx_hash)
+    }
+
+    fn check_tx(state: &ChainState, raw: &RawTransaction) -> Result<Hash, Status> {
+        let config = &state.system_config;
+        let tx_hash = match &raw.tx {
+            Some(Tx::NormalTx(UnverifiedTransaction {
+                transaction: Some(tx),
+                transaction_hash,
+                witness: Some(witness),
+            })) => {
+                let tx_hash = check_witness::<C, _>(tx, transaction_hash, witness)?;
+                if config.emergency_brake {
+                    return Err(Status::failed_precondition("emergency brake is on"));
+                }
+                if tx.chain_id != config.chain_id {
+                    return Err(Status::invalid_argument("chain id mismatched"));
+                }
+                if tx.version != config.version {
+                    return Err(Status::invalid_argument("version mismatched"));
+                }
+                let height = state.height();
+                if tx.valid_until_block <= height
+                    || tx.valid_until_block > height + u64::from(config.block_limit)
+                {
+                    return Err(Status::invalid_argument(format!(
+                        "invalid valid_until_block `{}` at height `{height}`",
+                        tx.valid_until_block
+                    )));
+                }
+                if tx.quota > u64::from(config.quota_limit) {
+                    return Err(Status::invalid_argument("quota exceeds the limit"));
+                }
+                tx_hash
+            }
+            Some(Tx::UtxoTx(UnverifiedUtxoTransaction {
+                transaction: Some(utxo),
+                transaction_hash,
+                witnesses,
+            })) => {
+                let witness = witnesses
+                    .first()
+                    .ok_or_else(|| Status::invalid_argument("utxo has no witness"))?;
+                let tx_hash = check_witness::<C, _>(utxo, transaction_hash, witness)?;
+                if witness.sender != config.admin {
+                    return Err(Status::permission_denied("utxo is not sent by admin"));
+                }
+                if utxo.pre_tx_hash != *pre_hash_of(config, utxo.lock_id)? {
+                    return Err(Status::invalid_argument("utxo pre tx hash mismatched"));
+                }
+                tx_hash
+            }
+            _ => return Err(Status::invalid_argument("incomplete raw transaction")),
+        };
+        if state.pool_hashes.contains(&tx_hash) || state.tx_positions.contains_key(&tx_hash) {
+            return Err(Status::already_exists(format!(
+                "tx `{}` already exists",
+                hex(&tx_hash)
+            )));
+        }
+
+        Ok(tx_hash)
+    }
+}
+
+fn check_witness<C: Crypto, M: Message>(
+    tx: &M,
+    tx_hash: &[u8],
+    witness: &Witness,
+) -> Result<Hash, Status> {
+    let expected = C::hash(&tx.encode_to_vec());
+    if tx_hash != expected.as_slice() {
+        return Err(Status::invalid_argument("tx hash mismatched"));
+    }
+    let pk = C::recover(tx_hash, &witness.signature)
+        .ok_or_else(|| Status::invalid_argument("invalid signature"))?;
+    if C::pk2addr(&pk).as_slice() != witness.sender {
+        return Err(Status::invalid_argument("signature mismatched with sender"));
+    }
+
+    Ok(expected)
+}
+
+fn tx_hash_of(raw: &RawTransaction) -> &[u8] {
+    match &raw.tx {
+        Some(Tx::NormalTx(tx)) => &tx.transaction_hash,
+        Some(Tx::UtxoTx(utxo)) => &utxo.transaction_hash,
+        None => &[],
+    }
+}
+
+fn pre_hash_of(config: &SystemConfig, lock_id: u64) -> Result<&Vec<u8>, Status> {
+    let pre_hash = match lock_id {
+        id if id == UtxoType::Admin as u64 => &config.admin_pre_hash,
+        id if id == UtxoType::BlockInterval as u64 => &config.block_interval_pre_hash,
+        id if id == UtxoType::Validators as u64 => &config.validators_pre_hash,
+        id if id == UtxoType::EmergencyBrake as u64 => &config.emergency_brake_pre_hash,
+        id if id == UtxoType::QuotaLimit as u64 => &config.quota_limit_pre_hash,
+        id => return Err(Status::invalid_argument(format!("unknown lock id `{id}`"))),
+    };
+    Ok(pre_hash)
+}
+
+fn u256_bytes(v: U256) -> Vec<u8> {
+    let mut bytes = vec![0; 32];
+    v.to_big_endian(&mut bytes);
+    bytes
+}
+
+// Apply the utxo output, whose formats are the same as `AdminBehaviour`'s.
+fn apply_utxo(config: &mut SystemConfig, utxo: &CloudUtxoTransaction, tx_hash: &[u8]) {
+    let output = &utxo.output;
+    let applied = match utxo.lock_id {
+        id if id == UtxoType::Admin as u64 => {
+            config.admin = output.clone();
+            config.admin_pre_hash = tx_hash.to_vec();
+            true
+        }
+        id if id == UtxoType::BlockInterval as u64 => match output.as_slice().try_into() {
+            Ok(bytes) => {
+                config.block_interval = u32::from_be_bytes(bytes);
+                config.block_interval_pre_hash = tx_hash.to_vec();
+                true
+            }
+            Err(_) => false,
+        },
+        id if id == UtxoType::Validators as u64 => {
+            if !output.is_empty() && output.len() % VALIDATOR_ADDR_LEN == 0 {
+                config.validators = output
+                    .chunks(VALIDATOR_ADDR_LEN)
+                    .map(<[u8]>::to_vec)
+                    .collect();
+                config.validators_pre_hash = tx_hash.to_vec();
+                true
+            } else {
+                false
+            }
+        }
+        id if id == UtxoType::EmergencyBrake as u64 => {
+            config.emergency_brake = !output.is_empty();
+            config.emergency_brake_pre_hash = tx_hash.to_vec();
+            true
+        }
+        id if id == UtxoType::QuotaLimit as u64 => {
+            match <[u8; 8]>::try_from(output.as_slice())
+                .ok()
+                .and_then(|bytes| u64::from_be_bytes(bytes).try_into().ok())
+            {
+                Some(quota_limit) => {
+                    config.quota_limit = quota_limit;
+                    config.quota_limit_pre_hash = tx_hash.to_vec();
+                    true
+                }
+                None => false,
+            }
+        }
+        _ => false,
+    };
+    if !applied {
+        eprintln!("fake node: ignored invalid utxo `{}`", hex(tx_hash));
+    }
+}
+
+fn commit_block<C: Crypto>(state: &mut ChainState, txs: Vec<RawTransaction>) {
+    let height = state.blocks.len() as u64;
+    let (prevhash, proposer) = match state.blocks.last() {
+        Some(parent) => (
+            parent.hash.to_vec(),
+            state
+                .system_config
+                .validators
+                .first()
+                .cloned()
+                .unwrap_or_default(),
+        ),
+        None => (vec![0; 32], vec![0; ADDR_BYTES_LEN]),
+    };
+    let tx_hashes: Vec<u8> = txs.iter().flat_map(tx_hash_of).copied().collect();
+    let header = BlockHeader {
+        prevhash,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64,
+        height,
+        transactions_root: C::hash(&tx_hashes).to_vec(),
+        proposer,
+    };
+    let block_hash = C::hash(&header.encode_to_vec());
+    let state_root = C::hash(&[state.state_root(), tx_hashes].concat()).to_vec();
+
+    let mut cumulative_quota_used = U256::zero();
+    for (index, raw) in txs.iter().enumerate() {
+        let tx_hash: Hash = tx_hash_of(raw).try_into().unwrap();
+        state.tx_positions.insert(tx_hash, (height, index as u64));
+        match &raw.tx {
+            Some(Tx::NormalTx(UnverifiedTransaction {
+                transaction: Some(tx),
+                witness: Some(witness),
+                ..
+            })) => {
+                let sender: Address = witness.sender.as_slice().try_into().unwrap();
+                let (contract_address, error_message) = execute_tx::<C>(state, &sender, tx);
+                cumulative_quota_used += U256::from(TX_QUOTA_USED);
+                let receipt = Receipt {
+                    transaction_hash: tx_hash.to_vec(),
+                    transaction_index: index as u64,
+                    block_hash: block_hash.to_vec(),
+                    block_number: height,
+                    cumulative_quota_used: u256_bytes(cumulative_quota_used),
+                    quota_used: u256_bytes(TX_QUOTA_USED.into()),
+                    contract_address,
+                    logs: vec![],
+                    state_root: state_root.clone(),
+                    logs_bloom: vec![0; 256],
+                    error_message,
+                };
+                state.receipts.insert(tx_hash, receipt);
+            }
+            Some(Tx::UtxoTx(UnverifiedUtxoTransaction {
+                transaction: Some(utxo),
+                ..
+            })) => apply_utxo(&mut state.system_config, utxo, &tx_hash),
+            // Checked before entering the pool.
+            _ => unreachable!(),
+        }
+    }
+
+    let block = Block {
+        version: state.system_config.version,
+        header: Some(header),
+        body: Some(RawTransactions { body: txs }),
+        proof: vec![],
+        state_root,
+    };
+    state.block_heights.insert(block_hash, height);
+    state.blocks.push(FakeBlock {
+        block,
+        hash: block_hash,
+        system_config: state.system_config.clone(),
+    });
+}
+
+// Returns (contract address, error message).
+fn execute_tx<C: Crypto>(
+    state: &mut ChainState,
+    sender: &Address,
+    tx: &CloudNormalTransaction,
+) -> (Vec<u8>, String) {
+    let nonce = state.nonces.entry(*sender).or_default();
+    let current_nonce = *nonce;
+    *nonce += U256::one();
+
+    let value = U256::from_big_endian(&tx.value);
+    let balance = state.balances.get(sender).copied().unwrap_or_default();
+    if value > balance {
+        return (vec![], "Not enough balance".into());
+    }
+
+    let abi_address = parse_addr(ABI_ADDRESS).unwrap();
+    let mut contract_address = vec![];
+    let to = if tx.to.is_empty() {
+        let addr: Address = C::hash(&[sender.as_slice(), &u256_bytes(current_nonce)].concat())
+            [32 - ADDR_BYTES_LEN..]
+            .try_into()
+            .unwrap();
+        state.codes.insert(addr, tx.data.clone());
+        contract_address = addr.to_vec();
+        addr
+    } else {
+        match Address::try_from_slice(&tx.to) {
+            Ok(to) => to,
+            Err(_) => return (vec![], "Invalid to address".into()),
+        }
+    };
+    if to == abi_address && tx.data.len() >= ADDR_BYTES_LEN {
+        // [<contract address><abi>]
+        let (addr, abi) = tx.data.split_at(ADDR_BYTES_LEN);
+        state.abis.insert(addr.try_into().unwrap(), abi.to_vec());
+    }
+
+    if !value.is_zero() {
+        state.balances.insert(*sender, balance - value);
+        *state.balances.entry(to).or_default() += value;
+    }
+
+    (contract_address, String::new())
+}
+
+fn address_of(addr: Option<cita_cloud_proto::common::Address>) -> Result<Address, Status> {
+    addr.and_then(|addr| Address::try_from_slice(&addr.address).ok())
+        .ok_or_else(|| Status::invalid_argument("invalid address"))
+}
+
+fn hash_of(hash: &CloudHash) -> Result<Hash, Status> {
+    Hash::try_from_slice(&hash.hash).map_err(|_| Status::invalid_argument("invalid hash"))
+}
+
+#[tonic::async_trait]
+impl<C: Crypto> RpcService for FakeNode<C> {
+    async fn get_block_number(&self, _: Request<Flag>) -> Result<Response<BlockNumber>, Status> {
+        Ok(Response::new(BlockNumber {
+            block_number: self.block_number(),
+        }))
+    }
+
+    async fn send_raw_transaction(
+        &self,
+        request: Request<RawTransaction>,
+    ) -> Result<Response<CloudHash>, Status> {
+        let tx_hash = self.add_to_pool(request.into_inner())?;
+        Ok(Response::new(CloudHash {
+            hash: tx_hash.to_vec(),
+        }))
+    }
+
+    async fn send_raw_transactions(
+        &self,
+        request: Request<RawTransactions>,
+    ) -> Result<Response<Hashes>, Status> {
+        let mut hashes = vec![];
+        for raw in request.into_inner().body {
+            let hash = self.send_raw_transaction(Request::new(raw)).await?;
+            hashes.push(hash.into_inner());
+        }
+        Ok(Response::new(Hashes { hashes }))
+    }
+
+    async fn get_block_by_hash(
+        &self,
+        request: Request<CloudHash>,
+    ) -> Result<Response<CompactBlock>, Status> {
+        let height = self.get_height_by_hash(request).await?.into_inner();
+        self.get_block_by_number(Request::new(height)).await
+    }
+
+    async fn get_height_by_hash(
+        &self,
+        request: Request<CloudHash>,
+    ) -> Result<Response<BlockNumber>, Status> {
+        let hash = hash_of(&request.into_inner())?;
+        let state = self.lock();
+        let block_number = *state
+            .block_heights
+            .get(&hash)
+            .ok_or_else(|| Status::not_found(format!("block `{}` not found", hex(&hash))))?;
+        Ok(Response::new(BlockNumber { block_number }))
+    }
+
+    async fn get_block_by_number(
+        &self,
+        request: Request<BlockNumber>,
+    ) -> Result<Response<CompactBlock>, Status> {
+        let state = self.lock();
+        let block = state.block(request.into_inner().block_number)?;
+        Ok(Response::new(block.compact()))
+    }
+
+    async fn get_state_root_by_number(
+        &self,
+        request: Request<BlockNumber>,
+    ) -> Result<Response<StateRoot>, Status> {
+        let state = self.lock();
+        let block = state.block(request.into_inner().block_number)?;
+        Ok(Response::new(StateRoot {
+            state_root: block.block.state_root.clone(),
+        }))
+    }
+
+    async fn get_proof_by_number(
+        &self,
+        request: Request<BlockNumber>,
+    ) -> Result<Response<Proof>, Status> {
+        let state = self.lock();
+        let block = state.block(request.into_inner().block_number)?;
+        Ok(Response::new(Proof {
+            proof: block.block.proof.clone(),
+        }))
+    }
+
+    async fn get_block_detail_by_number(
+        &self,
+        request: Request<BlockNumber>,
+    ) -> Result<Response<Block>, Status> {
+        let state = self.lock();
+        let block = state.block(request.into_inner().block_number)?;
+        Ok(Response::new(block.block.clone()))
+    }
+
+    async fn get_transaction(
+        &self,
+        request: Request<CloudHash>,
+    ) -> Result<Response<RawTransaction>, Status> {
+        let tx_hash = hash_of(&request.into_inner())?;
+        let state = self.lock();
+        Ok(Response::new(state.tx(&tx_hash)?.clone()))
+    }
+
+    async fn get_system_config(&self, _: Request<Empty>) -> Result<Response<SystemConfig>, Status> {
+        Ok(Response::new(self.lock().system_config.clone()))
+    }
+
+    async fn get_system_config_by_number(
+        &self,
+        request: Request<BlockNumber>,
+    ) -> Result<Response<SystemConfig>, Status> {
+        let state = self.lock();
+        let block = state.block(request.into_inner().block_number)?;
+        Ok(Response::new(block.system_config.clone()))
+    }
+
+    async fn get_block_hash(
+        &self,
+        request: Request<BlockNumber>,
+    ) -> Result<Response<CloudHash>, Status> {
+        let state = self.lock();
+        let block = state.block(request.into_inner().block_number)?;
+        Ok(Response::new(CloudHash {
+            hash: block.hash.to_vec(),
+        }))
+    }
+
+    async fn get_transaction_block_number(
+        &self,
+        request: Request<CloudHash>,
+    ) -> Result<Response<BlockNumber>, Status> {
+        let tx_hash = hash_of(&request.into_inner())?;
+        let state = self.lock();
+        let &(block_number, _) = state
+            .tx_positions
+            .get(&tx_hash)
+            .ok_or_else(|| Status::not_found(format!("tx `{}` not found", hex(&tx_hash))))?;
+        Ok(Response::new(BlockNumber { block_number }))
+    }
+
+    async fn get_transaction_index(
+        &self,
+        request: Request<CloudHash>,
+    ) -> Result<Response<TransactionIndex>, Status> {
+        let tx_hash = hash_of(&request.into_inner())?;
+        let state = self.lock();
+        let &(_, tx_index) = state
+            .tx_positions
+            .get(&tx_hash)
+            .ok_or_else(|| Status::not_found(format!("tx `{}` not found", hex(&tx_hash))))?;
+        Ok(Response::new(TransactionIndex { tx_index }))
+    }
+
+    async fn add_node(&self, _: Request<NodeNetInfo>) -> Result<Response<StatusCode>, Status> {
+        Err(Status::unimplemented("fake node has no network"))
+    }
+
+    async fn get_node_status(&self, _: Request<Empty>) -> Result<Response<NodeStatus>, Status> {
+        let state = self.lock();
+        let self_status = PeerStatus {
+            height: state.height(),
+            address: state.system_config.admin.clone(),
+            node_net_info: None,
+        };
+        Ok(Response::new(NodeStatus {
+            is_sync: true,
+            version: FAKE_NODE_VERSION.into(),
+            self_status: Some(self_status),
+            ..Default::default()
+        }))
+    }
+
+    async fn get_cross_chain_proof(
+        &self,
+        _: Request<CloudHash>,
+    ) -> Result<Response<CrossChainProof>, Status> {
+        Err(Status::unimplemented("fake node has no cross chain proof"))
+    }
+}
+
+#[tonic::async_trait]
+impl<C: Crypto> ExecutorService for FakeNode<C> {
+    async fn exec(&self, _: Request<Block>) -> Result<Response<HashResponse>, Status> {
+        Err(Status::unimplemented("fake node executes blocks by itself"))
+    }
+
+    async fn call(&self, _: Request<CallRequest>) -> Result<Response<CallResponse>, Status> {
+        Ok(Response::new(CallResponse { value: vec![] }))
+    }
+}
+
+#[tonic::async_trait]
+impl<C: Crypto> EvmRpcService for FakeNode<C> {
+    async fn get_transaction_receipt(
+        &self,
+        request: Request<CloudHash>,
+    ) -> Result<Response<Receipt>, Status> {
+        let tx_hash = hash_of(&request.into_inner())?;
+        let state = self.lock();
+        let receipt = state
+            .receipts
+            .get(&tx_hash)
+            .ok_or_else(|| Status::not_found(format!("receipt `{}` not found", hex(&tx_hash))))?;
+        Ok(Response::new(receipt.clone()))
+    }
+
+    async fn get_code(
+        &self,
+        request: Request<GetCodeRequest>,
+    ) -> Result<Response<ByteCode>, Status> {
+        let addr = address_of(request.into_inner().address)?;
+        let byte_code = self.lock().codes.get(&addr).cloned().unwrap_or_default();
+        Ok(Response::new(ByteCode { byte_code }))
+    }
+
+    async fn get_balance(
+        &self,
+        request: Request<GetBalanceRequest>,
+    ) -> Result<Response<Balance>, Status> {
+        let addr = address_of(request.into_inner().address)?;
+        let balance = self.lock().balances.get(&addr).copied().unwrap_or_default();
+        Ok(Response::new(Balance {
+            value: u256_bytes(balance),
+        }))
+    }
+
+    async fn get_transaction_count(
+        &self,
+        request: Request<GetTransactionCountRequest>,
+    ) -> Result<Response<Nonce>, Status> {
+        let addr = address_of(request.into_inner().address)?;
+        let nonce = self.lock().nonces.get(&addr).copied().unwrap_or_default();
+        Ok(Response::new(Nonce {
+            nonce: u256_bytes(nonce),
+        }))
+    }
+
+    async fn get_abi(&self, request: Request<GetAbiRequest>) -> Result<Response<ByteAbi>, Status> {
+        let addr = address_of(request.into_inner().address)?;
+        let bytes_abi = self.lock().abis.get(&addr).cloned().unwrap_or_default();
+        Ok(Response::new(ByteAbi { bytes_abi }))
+    }
+
+    async fn estimate_quota(&self, _: Request<CallRequest>) -> Result<Response<ByteQuota>, Status> {
+        Ok(Response::new(ByteQuota {
+            bytes_quota: u256_bytes(TX_QUOTA_USED.into()),
+        }))
+    }
+
+    async fn get_receipt_proof(
+        &self,
+        _: Request<CloudHash>,
+    ) -> Result<Response<ReceiptProof>, Status> {
+        Err(Status::unimplemented("fake node has no receipt proof"))
+    }
+
+    async fn get_roots_info(
+        &self,
+        _: Request<cita_cloud_proto::evm::BlockNumber>,
+    ) -> Result<Response<RootsInfo>, Status> {
+        Err(Status::unimplemented("fake node has no roots info"))
+    }
+
+    async fn get_storage_at(
+        &self,
+        _: Request<GetStorageAtRequest>,
+    ) -> Result<Response<CloudHash>, Status> {
+        Ok(Response::new(CloudHash { hash: vec![0; 32] }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{
+            admin::AdminBehaviour,
+            client::{ConnectOptions, GrpcClientBehaviour},
+            controller::{ControllerBehaviour, ControllerClient, TransactionSenderBehaviour},
+            evm::{EvmBehaviour, EvmClient},
+            wallet::Account,
+        },
+        crypto::SmCrypto,
+    };
+
+    #[test]
+    fn test_fake_node() -> Result<()> {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async {
+            let admin = Account::<SmCrypto>::generate();
+            // Produce blocks manually.
+            let node = FakeNode::<SmCrypto>::new(*admin.address())
+                .with_balance(*admin.address(), U256::from(1000u64))
+                .with_block_interval(3600);
+            let (addr, _) = node.clone().spawn("127.0.0.1:0".parse()?).await?;
+            let addr = addr.to_string();
+            let controller = <ControllerClient as GrpcClientBehaviour>::connect(
+                &addr,
+                &ConnectOptions::default(),
+            )
+            .await?;
+            let evm =
+                <EvmClient as GrpcClientBehaviour>::connect(&addr, &ConnectOptions::default())
+                    .await?;
+
+            let to = [1u8; 20];
+            let tx_hash = controller
+                .send_tx(
+                    &admin,
+                    to.to_vec(),
+                    vec![],
+                    u256_bytes(U256::from(100u64)),
+                    200_000,
+                    10,
+                )
+                .await?;
+            // not committed yet
+            assert!(evm.get_receipt(tx_hash).await.is_err());
+            node.produce_block();
+            assert_eq!(controller.get_tx_block_number(tx_hash).await?, 1);
+            let receipt = evm.get_receipt(tx_hash).await?;
+            assert!(receipt.error_message.is_empty());
+            let balance = evm.get_balance(to, Default::default()).await?;
+            assert_eq!(U256::from_big_endian(&balance.value), U256::from(100u64));
+
+            controller.set_block_interval(&admin, 2).await?;
+            node.produce_block();
+            assert_eq!(controller.get_system_config().await?.block_interval, 2);
+
+            // not signed by admin
+            let other = Account::<SmCrypto>::generate();
+            assert!(controller.set_block_interval(&other, 3).await.is_err());
+
+            anyhow::Ok(())
+        })
+    }
+
+    #[test]
+    fn test_apply_validators() {
+        let mut config = SystemConfig::default();
+        let utxo = |output: Vec<u8>| CloudUtxoTransaction {
+            output,
+            lock_id: UtxoType::Validators as u64,
+            ..Default::default()
+        };
+
+        // 12 addresses are 240 bytes, which is also a multiple of the BLS key length.
+        let validators = (0..12u8).map(|i| vec![i; 20]).collect::<Vec<_>>();
+        apply_utxo(&mut config, &utxo(validators.concat()), &[1; 32]);
+        assert_eq!(config.validators, validators);
+        assert_eq!(config.validators_pre_hash, vec![1; 32]);
+
+        // not a list of addresses
+        apply_utxo(&mut config, &utxo(vec![0; 48]), &[2; 32]);
+        assert_eq!(config.validators, validators);
+        assert_eq!(config.validators_pre_hash, vec![1; 32]);
+    }
+}
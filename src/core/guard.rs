@@ -0,0 +1,432 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{io::Write, sync::Arc};
+
+use anyhow::{bail, ensure, Context as _, Result};
+use cita_cloud_proto::{
+    blockchain::{
+        raw_transaction::Tx, Block, CompactBlock, RawTransaction,
+        Transaction as CloudNormalTransaction, UtxoTransaction as CloudUtxoTransaction,
+    },
+    common::NodeStatus,
+    controller::{BlockNumber as CloudBlockNumber, CrossChainProof, SystemConfig},
+};
+use tokio::sync::OnceCell;
+use tonic::transport::Channel;
+
+use super::{
+    client::{ConnectOptions, GrpcClientBehaviour},
+    controller::{
+        CompactBlockWithStaterootProof, ControllerBehaviour, ProofWithValidators, SignerBehaviour,
+    },
+    interceptor::MetadataInterceptor,
+};
+use crate::{
+    config::{ChainPin, ContextSetting, PolicySetting},
+    crypto::{Address, Hash},
+    display::Display,
+    types::U256,
+    utils::{hex, parse_addr, parse_value},
+};
+
+/// Get the identity of the chain that the controller serves.
+pub async fn fetch_chain_pin<Co: ControllerBehaviour>(controller: &Co) -> Result<ChainPin> {
+    let system_config = controller
+        .get_system_config()
+        .await
+        .context("failed to get system config")?;
+    let genesis_hash = controller
+        .get_block_hash(0)
+        .await
+        .context("failed to get genesis block hash")?;
+    Ok(ChainPin {
+        chain_id: hex(&system_config.chain_id),
+        version: system_config.version,
+        genesis_hash: hex(&genesis_hash),
+    })
+}
+
+/// Check that the chain is the pinned one.
+pub fn check_chain_pin(pinned: &ChainPin, actual: &ChainPin) -> Result<()> {
+    let mut mismatches = vec![];
+    if pinned.chain_id != actual.chain_id {
+        mismatches.push(format!(
+            "chain id `{}` (pinned `{}`)",
+            actual.chain_id, pinned.chain_id
+        ));
+    }
+    if pinned.version != actual.version {
+        mismatches.push(format!(
+            "version `{}` (pinned `{}`)",
+            actual.version, pinned.version
+        ));
+    }
+    if pinned.genesis_hash != actual.genesis_hash {
+        mismatches.push(format!(
+            "genesis hash `{}` (pinned `{}`)",
+            actual.genesis_hash, pinned.genesis_hash
+        ));
+    }
+    if !mismatches.is_empty() {
+        bail!(
+            "the chain is not the one pinned in the context, it has {}. Use `--trust-new-chain` if the chain has been reset intentionally",
+            mismatches.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Ask the user to type `yes` to continue.
+pub fn confirm(summary: &str) -> Result<()> {
+    println!("{summary}");
+    print!("The context is protected, type `yes` to continue: ");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    ensure!(input.trim() == "yes", "canceled by user");
+    Ok(())
+}
+
+/// The policy of a context, parsed.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    protected: bool,
+    max_value: Option<U256>,
+    allowed_to: Vec<Address>,
+}
+
+impl Policy {
+    pub fn from_setting(setting: &PolicySetting) -> Result<Self> {
+        let max_value = setting
+            .max_value
+            .as_deref()
+            .map(|v| parse_value(v).map(|v| U256::from_big_endian(&v)))
+            .transpose()
+            .context("invalid max value in the context policy")?;
+        let allowed_to = setting
+            .allowed_to
+            .iter()
+            .map(|addr| parse_addr(addr))
+            .collect::<Result<_>>()
+            .context("invalid allowed address in the context policy")?;
+        Ok(Self {
+            protected: setting.protected,
+            max_value,
+            allowed_to,
+        })
+    }
+
+    pub fn check_tx(&self, tx: &CloudNormalTransaction) -> Result<()> {
+        self.check_limits(tx)?;
+        if self.protected {
+            confirm(&format!("About to send tx:\n{}", tx.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Check a batch of `count` txs like `tx`, confirming once for all of them.
+    fn check_batch(&self, tx: &CloudNormalTransaction, count: u64) -> Result<()> {
+        self.check_limits(tx)?;
+        if self.protected {
+            confirm(&format!(
+                "About to send `{count}` txs like this one:\n{}",
+                tx.display()
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn check_limits(&self, tx: &CloudNormalTransaction) -> Result<()> {
+        if let Some(max_value) = self.max_value {
+            ensure!(
+                tx.value.len() <= 32 && U256::from_big_endian(&tx.value) <= max_value,
+                "tx value `{}` exceeds the max value `{max_value}` of the context",
+                hex(&tx.value),
+            );
+        }
+        if !self.allowed_to.is_empty() {
+            ensure!(
+                self.allowed_to.iter().any(|addr| addr[..] == tx.to[..]),
+                "sending tx to `{}` is not allowed by the context",
+                hex(&tx.to),
+            );
+        }
+        Ok(())
+    }
+
+    fn check_utxo(&self, utxo: &CloudUtxoTransaction) -> Result<()> {
+        if self.protected {
+            confirm(&format!(
+                "About to update system config:\n{}",
+                utxo.display()
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// A controller that refuses to send txs to a chain other than the pinned one,
+/// or txs that violate the context's policy.
+///
+/// The chain is verified once on the first tx sent through the connection.
+#[derive(Debug, Clone)]
+pub struct TxGuard<T> {
+    inner: T,
+    pin: Option<ChainPin>,
+    policy: Policy,
+    verified: Arc<OnceCell<()>>,
+}
+
+impl<T> TxGuard<T> {
+    pub fn new(inner: T, pin: Option<ChainPin>, policy: Policy) -> Self {
+        Self {
+            inner,
+            pin,
+            policy,
+            verified: Arc::new(OnceCell::new()),
+        }
+    }
+}
+
+impl<T: ControllerBehaviour + Send + Sync> TxGuard<T> {
+    async fn verify(&self) -> Result<()> {
+        let Some(pin) = &self.pin else {
+            return Ok(());
+        };
+        self.verified
+            .get_or_try_init(|| async {
+                let actual = fetch_chain_pin(&self.inner)
+                    .await
+                    .context("failed to verify the chain")?;
+                check_chain_pin(pin, &actual)
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl<T> GrpcClientBehaviour for TxGuard<T>
+where
+    T: GrpcClientBehaviour + Send,
+{
+    fn from_channel(ch: Channel, interceptor: MetadataInterceptor) -> Self {
+        Self::new(T::from_channel(ch, interceptor), None, Policy::default())
+    }
+
+    fn connect_with_options(
+        addrs: &[String],
+        setting: &ContextSetting,
+        opts: &ConnectOptions,
+    ) -> Result<Self> {
+        let policy = Policy::from_setting(&setting.policy)?;
+        let inner = T::connect_with_options(addrs, setting, opts)?;
+        Ok(Self::new(inner, setting.chain_pin.clone(), policy))
+    }
+}
+
+#[tonic::async_trait]
+impl<T> ControllerBehaviour for TxGuard<T>
+where
+    T: ControllerBehaviour + Send + Sync,
+{
+    async fn send_raw(&self, raw: RawTransaction) -> Result<Hash> {
+        match &raw.tx {
+            Some(Tx::NormalTx(utx)) => {
+                if let Some(tx) = &utx.transaction {
+                    self.policy.check_tx(tx)?;
+                }
+            }
+            Some(Tx::UtxoTx(utx)) => {
+                if let Some(utxo) = &utx.transaction {
+                    self.policy.check_utxo(utxo)?;
+                }
+            }
+            None => (),
+        }
+        self.verify().await?;
+        self.inner.send_raw(raw).await
+    }
+
+    async fn get_system_config(&self) -> Result<SystemConfig> {
+        self.inner.get_system_config().await
+    }
+
+    async fn get_system_config_by_number(&self, block_number: u64) -> Result<SystemConfig> {
+        self.inner.get_system_config_by_number(block_number).await
+    }
+
+    async fn get_block_number(&self, for_pending: bool) -> Result<u64> {
+        self.inner.get_block_number(for_pending).await
+    }
+
+    async fn get_block_hash(&self, block_number: u64) -> Result<Hash> {
+        self.inner.get_block_hash(block_number).await
+    }
+
+    async fn get_height_by_hash(&self, hash: Hash) -> Result<CloudBlockNumber> {
+        self.inner.get_height_by_hash(hash).await
+    }
+
+    async fn get_compact_block_by_number(&self, block_number: u64) -> Result<CompactBlock> {
+        self.inner.get_compact_block_by_number(block_number).await
+    }
+
+    async fn get_block_by_number(
+        &self,
+        block_number: u64,
+    ) -> Result<CompactBlockWithStaterootProof> {
+        self.inner.get_block_by_number(block_number).await
+    }
+
+    async fn get_block_detail_by_number(&self, block_number: u64) -> Result<Block> {
+        self.inner.get_block_detail_by_number(block_number).await
+    }
+
+    async fn get_tx(&self, tx_hash: Hash) -> Result<RawTransaction> {
+        self.inner.get_tx(tx_hash).await
+    }
+
+    async fn get_tx_index(&self, tx_hash: Hash) -> Result<u64> {
+        self.inner.get_tx_index(tx_hash).await
+    }
+
+    async fn get_tx_block_number(&self, tx_hash: Hash) -> Result<u64> {
+        self.inner.get_tx_block_number(tx_hash).await
+    }
+
+    async fn get_node_status(&self) -> Result<NodeStatus> {
+        self.inner.get_node_status().await
+    }
+
+    async fn add_node(&self, multiaddr: String) -> Result<u32> {
+        self.inner.add_node(multiaddr).await
+    }
+
+    async fn parse_overlord_proof(&self, proof_bytes: Vec<u8>) -> Result<ProofWithValidators> {
+        self.inner.parse_overlord_proof(proof_bytes).await
+    }
+
+    async fn get_cross_chain_proof(&self, hash: Hash) -> Result<CrossChainProof> {
+        self.inner.get_cross_chain_proof(hash).await
+    }
+
+    async fn sign_and_send_tx<S>(&self, signer: &S, tx: CloudNormalTransaction) -> Result<Hash>
+    where
+        S: SignerBehaviour + Send + Sync,
+    {
+        // Check before signing, not only before sending.
+        self.policy.check_tx(&tx)?;
+        self.verify().await?;
+        self.inner.sign_and_send_tx(signer, tx).await
+    }
+
+    async fn check_txs(&self, tx: &CloudNormalTransaction, count: u64) -> Result<()> {
+        self.policy.check_batch(tx, count)?;
+        self.verify().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mock::MockControllerClient;
+
+    fn mock_controller(chain_id: u8) -> MockControllerClient {
+        let mut controller = MockControllerClient::default();
+        controller.expect_get_system_config().returning(move || {
+            Ok(SystemConfig {
+                chain_id: vec![chain_id; 32],
+                version: 0,
+                ..Default::default()
+            })
+        });
+        controller
+            .expect_get_block_hash()
+            .returning(|_| Ok([0; 32]));
+        controller.expect_send_raw().returning(|_| Ok([1; 32]));
+        controller
+    }
+
+    #[test]
+    fn test_tx_guard() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let pin = fetch_chain_pin(&mock_controller(1)).await.unwrap();
+            assert_eq!(pin.chain_id, hex(&[1; 32]));
+            assert_eq!(pin.genesis_hash, hex(&[0; 32]));
+
+            let guard = TxGuard::new(mock_controller(1), Some(pin.clone()), Policy::default());
+            assert!(guard.send_raw(RawTransaction::default()).await.is_ok());
+
+            let guard = TxGuard::new(mock_controller(2), Some(pin.clone()), Policy::default());
+            let err = guard.send_raw(RawTransaction::default()).await.unwrap_err();
+            assert!(err.to_string().contains("chain id"));
+            let tx = CloudNormalTransaction::default();
+            let err = guard.check_txs(&tx, 100).await.unwrap_err();
+            assert!(err.to_string().contains("chain id"));
+
+            // A batch is checked against the policy like a single tx.
+            let policy = Policy::from_setting(&PolicySetting {
+                allowed_to: vec![hex(&[1; 20])],
+                ..Default::default()
+            })
+            .unwrap();
+            let guard = TxGuard::new(mock_controller(1), Some(pin), policy);
+            assert!(guard.check_txs(&tx, 100).await.is_err());
+            let tx = CloudNormalTransaction {
+                to: vec![1; 20],
+                ..tx
+            };
+            assert!(guard.check_txs(&tx, 100).await.is_ok());
+
+            // Not pinned.
+            let guard = TxGuard::new(mock_controller(2), None, Policy::default());
+            assert!(guard.send_raw(RawTransaction::default()).await.is_ok());
+        });
+    }
+
+    #[test]
+    fn test_policy() {
+        let setting = PolicySetting {
+            max_value: Some("0x100".into()),
+            allowed_to: vec![hex(&[1; 20])],
+            ..Default::default()
+        };
+        let policy = Policy::from_setting(&setting).unwrap();
+
+        let tx = CloudNormalTransaction {
+            to: vec![1; 20],
+            value: parse_value("0x100").unwrap().to_vec(),
+            ..Default::default()
+        };
+        assert!(policy.check_tx(&tx).is_ok());
+
+        let too_much = CloudNormalTransaction {
+            value: parse_value("0x101").unwrap().to_vec(),
+            ..tx.clone()
+        };
+        assert!(policy.check_tx(&too_much).is_err());
+
+        let not_allowed = CloudNormalTransaction {
+            to: vec![2; 20],
+            ..tx.clone()
+        };
+        assert!(policy.check_tx(&not_allowed).is_err());
+
+        let create = CloudNormalTransaction { to: vec![], ..tx };
+        assert!(policy.check_tx(&create).is_err());
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A minimal HTTP/1.1 that is just enough for JSON over POST, shared by the CITA 1.x client,
+//! the HTTP remote signer and the JSON-RPC gateway.
+//!
+//! Clients send one request per connection with `Connection: close`, so that the response
+//! ends with the connection. There is no TLS.
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream as StdTcpStream,
+    time::Duration,
+};
+
+use anyhow::{ensure, Context as _, Result};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// Where to POST to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpUrl {
+    /// `host:port`
+    pub host: String,
+    pub path: String,
+}
+
+impl HttpUrl {
+    /// Accept `host:port[/path]` or `http://host:port[/path]`.
+    pub fn parse(url: &str) -> Result<Self> {
+        ensure!(
+            !url.starts_with("https://"),
+            "https is not supported for `{url}`"
+        );
+        let without_scheme = url.strip_prefix("http://").unwrap_or(url);
+        let (host, path) = match without_scheme.find('/') {
+            Some(i) => without_scheme.split_at(i),
+            None => (without_scheme, "/"),
+        };
+        ensure!(!host.is_empty(), "invalid HTTP url `{url}`");
+
+        Ok(Self {
+            host: host.into(),
+            path: path.into(),
+        })
+    }
+
+    fn post_request(&self, body: &str) -> String {
+        format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            self.path,
+            self.host,
+            body.len(),
+        )
+    }
+}
+
+/// POST the JSON body and return the response body.
+pub async fn post(url: &HttpUrl, body: &str) -> Result<Vec<u8>> {
+    let mut stream = TcpStream::connect(&url.host)
+        .await
+        .with_context(|| format!("cannot connect to `{}`", url.host))?;
+    stream.write_all(url.post_request(body).as_bytes()).await?;
+
+    let mut resp = Vec::new();
+    stream.read_to_end(&mut resp).await?;
+    parse_response(&resp)
+}
+
+/// The blocking [`post`], which gives up if the server doesn't read or answer within `timeout`.
+pub fn post_blocking(url: &HttpUrl, body: &str, timeout: Duration) -> Result<Vec<u8>> {
+    let mut stream = StdTcpStream::connect(&url.host)
+        .with_context(|| format!("cannot connect to `{}`", url.host))?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    stream.write_all(url.post_request(body).as_bytes())?;
+
+    let mut resp = Vec::new();
+    stream.read_to_end(&mut resp)?;
+    parse_response(&resp)
+}
+
+/// The body of a complete `200` response.
+fn parse_response(resp: &[u8]) -> Result<Vec<u8>> {
+    let header_end = resp
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .context("invalid HTTP response")?;
+    let head = std::str::from_utf8(&resp[..header_end]).context("invalid HTTP response header")?;
+    let body = &resp[header_end + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status = lines.next().unwrap_or_default();
+    ensure!(
+        status.split_whitespace().nth(1) == Some("200"),
+        "unexpected HTTP response `{status}`"
+    );
+    let is_chunked = lines.any(|line| {
+        let line = line.to_ascii_lowercase();
+        line.starts_with("transfer-encoding:") && line.contains("chunked")
+    });
+    if is_chunked {
+        decode_chunked(body)
+    } else {
+        Ok(body.to_vec())
+    }
+}
+
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .context("invalid chunked body")?;
+        let size = std::str::from_utf8(&body[..line_end])?
+            .split(';')
+            .next()
+            .unwrap_or_default();
+        let size = usize::from_str_radix(size.trim(), 16).context("invalid chunk size")?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        ensure!(body.len() >= size + 2, "truncated chunked body");
+        decoded.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+}
+
+/// A request received by a server.
+#[derive(Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub body: Vec<u8>,
+    /// Whether the client wants to send more requests on this connection.
+    pub keep_alive: bool,
+}
+
+/// Read the next request on the connection, `None` if it's closed by the client.
+pub async fn read_request<R>(reader: &mut R, max_body_size: usize) -> Result<Option<HttpRequest>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).await? == 0 {
+        return Ok(None);
+    }
+    let method = request_line
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_owned();
+
+    let mut content_length = 0;
+    let mut is_chunked = false;
+    let mut keep_alive = true;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().context("invalid content-length")?;
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                is_chunked = value.to_ascii_lowercase().contains("chunked");
+            } else if name.eq_ignore_ascii_case("connection") {
+                keep_alive = !value.eq_ignore_ascii_case("close");
+            }
+        }
+    }
+    let body = if is_chunked {
+        read_chunked(reader, max_body_size).await?
+    } else {
+        ensure!(content_length <= max_body_size, "request body too large");
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).await?;
+        body
+    };
+
+    Ok(Some(HttpRequest {
+        method,
+        body,
+        keep_alive,
+    }))
+}
+
+async fn read_chunked<R>(reader: &mut R, max_body_size: usize) -> Result<Vec<u8>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut body = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await?;
+        let size = line.split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size.trim(), 16).context("invalid chunk size")?;
+        if size == 0 {
+            break;
+        }
+        ensure!(size <= max_body_size - body.len(), "request body too large");
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..]).await?;
+        let mut crlf = [0; 2];
+        reader.read_exact(&mut crlf).await?;
+        ensure!(&crlf == b"\r\n", "invalid chunked body");
+    }
+    // Skip the trailers, which end with an empty line.
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim_end().is_empty() {
+            return Ok(body);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_url() {
+        let url = HttpUrl::parse("http://127.0.0.1:1337/rpc").unwrap();
+        assert_eq!(url.host, "127.0.0.1:1337");
+        assert_eq!(url.path, "/rpc");
+        assert_eq!(HttpUrl::parse("127.0.0.1:1337").unwrap().path, "/");
+        assert!(HttpUrl::parse("https://127.0.0.1:1337").is_err());
+        assert!(HttpUrl::parse("http:///rpc").is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let resp = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+        assert_eq!(parse_response(resp).unwrap(), b"{}");
+
+        let chunked =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\n{\"a\r\n4;ext\r\n\":1}\r\n0\r\n\r\n";
+        assert_eq!(parse_response(chunked).unwrap(), br#"{"a":1}"#);
+        let truncated = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n9\r\n{}";
+        assert!(parse_response(truncated).is_err());
+
+        let err = parse_response(b"HTTP/1.1 500 Internal Server Error\r\n\r\n").unwrap_err();
+        assert!(err.to_string().contains("500"));
+    }
+
+    #[test]
+    fn test_read_request() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut reader: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}\
+                OPTIONS / HTTP/1.1\r\nConnection: close\r\n\r\n";
+            let req = read_request(&mut reader, 1024).await.unwrap().unwrap();
+            assert_eq!(
+                (req.method.as_str(), req.body.as_slice()),
+                ("POST", &b"{}"[..])
+            );
+            assert!(req.keep_alive);
+            let req = read_request(&mut reader, 1024).await.unwrap().unwrap();
+            assert_eq!(req.method, "OPTIONS");
+            assert!(!req.keep_alive);
+            assert!(read_request(&mut reader, 1024).await.unwrap().is_none());
+
+            let mut reader: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: 2048\r\n\r\n";
+            assert!(read_request(&mut reader, 1024).await.is_err());
+
+            let mut reader: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                3\r\n{\"a\r\n4;ext\r\n\":1}\r\n0\r\nX-Trailer: 1\r\n\r\n\
+                POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n800\r\n";
+            let req = read_request(&mut reader, 1024).await.unwrap().unwrap();
+            assert_eq!(req.body, br#"{"a":1}"#);
+            assert!(read_request(&mut reader, 1024).await.is_err());
+            let mut reader: &[u8] =
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n{}}\r\n0\r\n\r\n";
+            assert!(read_request(&mut reader, 1024).await.is_err());
+        });
+    }
+}
@@ -0,0 +1,308 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{ensure, Context as _, Result};
+use tonic::{
+    metadata::{AsciiMetadataKey, AsciiMetadataValue},
+    service::Interceptor,
+    Request, Status,
+};
+
+use crate::config::MetadataSetting;
+
+const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(300);
+/// How often the token is checked for refreshing, e.g. whether the token file is modified.
+const TOKEN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How soon to rerun a failed token command.
+const TOKEN_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Attach the configured metadata to every gRPC request.
+#[derive(Clone, Default)]
+pub struct MetadataInterceptor(Option<Arc<Metadata>>);
+
+struct Metadata {
+    headers: Vec<(AsciiMetadataKey, AsciiMetadataValue)>,
+    token: Option<Arc<Token>>,
+}
+
+// The token is resolved and refreshed by a background thread, the interceptor only
+// reads the cached one. A missing token only fails the requests that need it.
+struct Token {
+    source: TokenSource,
+    state: Mutex<TokenState>,
+    resolved: Condvar,
+}
+
+enum TokenSource {
+    File(PathBuf),
+    Env(String),
+    Command { command: String, ttl: Duration },
+}
+
+#[derive(Default)]
+struct TokenState {
+    // `None` until the token is first resolved.
+    value: Option<Result<AsciiMetadataValue, String>>,
+    // The token file is reread only if it's modified.
+    file_modified: Option<SystemTime>,
+    // When to rerun the token command.
+    refresh_at: Option<Instant>,
+}
+
+impl MetadataInterceptor {
+    pub fn from_setting(setting: &MetadataSetting) -> Result<Self> {
+        if setting.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let headers = setting
+            .headers
+            .iter()
+            .map(|(key, value)| {
+                let key: AsciiMetadataKey = key
+                    .to_lowercase()
+                    .parse()
+                    .with_context(|| format!("invalid metadata key `{key}`"))?;
+                let value: AsciiMetadataValue = value
+                    .parse()
+                    .with_context(|| format!("invalid metadata value `{value}`"))?;
+                Ok((key, value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let token_sources = [
+            setting.token_file.is_some(),
+            setting.token_env.is_some(),
+            setting.token_command.is_some(),
+        ];
+        ensure!(
+            token_sources.iter().filter(|&&s| s).count() <= 1,
+            "only one of token file, token env and token command can be set"
+        );
+        let source = if let Some(path) = &setting.token_file {
+            Some(TokenSource::File(path.clone()))
+        } else if let Some(env) = &setting.token_env {
+            Some(TokenSource::Env(env.clone()))
+        } else {
+            setting
+                .token_command
+                .as_ref()
+                .map(|command| TokenSource::Command {
+                    command: command.clone(),
+                    ttl: setting
+                        .token_ttl
+                        .map(Duration::from_secs)
+                        .unwrap_or(DEFAULT_TOKEN_TTL),
+                })
+        };
+        let token = source.map(Token::start);
+
+        Ok(Self(Some(Arc::new(Metadata { headers, token }))))
+    }
+}
+
+impl Token {
+    fn start(source: TokenSource) -> Arc<Self> {
+        let token = Arc::new(Self {
+            source,
+            state: Mutex::new(TokenState::default()),
+            resolved: Condvar::new(),
+        });
+        if let TokenSource::Env(_) = token.source {
+            // Env won't change, no need to refresh.
+            token.refresh();
+        } else {
+            // Stops once the interceptor is dropped.
+            let weak = Arc::downgrade(&token);
+            std::thread::spawn(move || {
+                while let Some(token) = weak.upgrade() {
+                    token.refresh();
+                    drop(token);
+                    std::thread::sleep(TOKEN_POLL_INTERVAL);
+                }
+            });
+        }
+        token
+    }
+
+    // Resolve the token again if it's outdated. The lock is not held while resolving.
+    fn refresh(&self) {
+        let value = match &self.source {
+            TokenSource::File(path) => {
+                let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+                {
+                    let state = self.state.lock().unwrap();
+                    if state.value.is_some()
+                        && modified.is_some()
+                        && state.file_modified == modified
+                    {
+                        return;
+                    }
+                }
+                let value = std::fs::read_to_string(path)
+                    .with_context(|| format!("cannot read token file `{}`", path.display()))
+                    .and_then(|token| bearer(&token));
+                self.state.lock().unwrap().file_modified = modified;
+                value
+            }
+            TokenSource::Env(env) => std::env::var(env)
+                .with_context(|| format!("cannot read token from env `{env}`"))
+                .and_then(|token| bearer(&token)),
+            TokenSource::Command { command, ttl } => {
+                {
+                    let state = self.state.lock().unwrap();
+                    if state.refresh_at.is_some_and(|at| at > Instant::now()) {
+                        return;
+                    }
+                }
+                let value = run_token_command(command).and_then(|token| bearer(&token));
+                let retry_in = if value.is_ok() {
+                    *ttl
+                } else {
+                    TOKEN_RETRY_INTERVAL
+                };
+                self.state.lock().unwrap().refresh_at = Some(Instant::now() + retry_in);
+                value
+            }
+        };
+        self.state.lock().unwrap().value = Some(value.map_err(|e| format!("{e:#}")));
+        self.resolved.notify_all();
+    }
+
+    // Only the first requests wait, until the token is first resolved.
+    fn authorization(&self) -> Result<AsciiMetadataValue, String> {
+        let state = self.state.lock().unwrap();
+        let state = self
+            .resolved
+            .wait_while(state, |state| state.value.is_none())
+            .unwrap();
+        state.value.clone().unwrap()
+    }
+}
+
+fn bearer(token: &str) -> Result<AsciiMetadataValue> {
+    format!("Bearer {}", token.trim())
+        .parse()
+        .context("invalid token")
+}
+
+fn run_token_command(command: &str) -> Result<String> {
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .with_context(|| format!("cannot run token command `{command}`"))?;
+    ensure!(
+        output.status.success(),
+        "token command `{command}` failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    String::from_utf8(output.stdout).context("token command outputs invalid utf8")
+}
+
+impl Interceptor for MetadataInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(metadata) = &self.0 {
+            for (key, value) in &metadata.headers {
+                request.metadata_mut().insert(key.clone(), value.clone());
+            }
+            if let Some(token) = &metadata.token {
+                let authorization = token.authorization().map_err(Status::unauthenticated)?;
+                request
+                    .metadata_mut()
+                    .insert("authorization", authorization);
+            }
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_interceptor() {
+        let mut setting = MetadataSetting::default();
+        setting
+            .headers
+            .insert("X-Tenant-Id".into(), "tenant".into());
+        setting.token_command = Some("echo token".into());
+        let mut interceptor = MetadataInterceptor::from_setting(&setting).unwrap();
+
+        let request = interceptor.call(Request::new(())).unwrap();
+        let metadata = request.metadata();
+        assert_eq!(metadata.get("x-tenant-id").unwrap(), "tenant");
+        assert_eq!(metadata.get("authorization").unwrap(), "Bearer token");
+
+        // Missing token fails the request.
+        let setting = MetadataSetting {
+            token_file: Some("/non-existent/token".into()),
+            ..Default::default()
+        };
+        let mut interceptor = MetadataInterceptor::from_setting(&setting).unwrap();
+        let status = interceptor.call(Request::new(())).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        // Conflicting token sources.
+        let setting = MetadataSetting {
+            token_file: Some("token".into()),
+            token_env: Some("TOKEN".into()),
+            ..Default::default()
+        };
+        assert!(MetadataInterceptor::from_setting(&setting).is_err());
+    }
+
+    #[test]
+    fn test_token_file_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token");
+        std::fs::write(&path, "a").unwrap();
+        let setting = MetadataSetting {
+            token_file: Some(path.clone()),
+            ..Default::default()
+        };
+        let mut interceptor = MetadataInterceptor::from_setting(&setting).unwrap();
+        let token = interceptor.0.clone().unwrap().token.clone().unwrap();
+        let authorization = |interceptor: &mut MetadataInterceptor| {
+            let request = interceptor.call(Request::new(())).unwrap();
+            request.metadata().get("authorization").unwrap().clone()
+        };
+        assert_eq!(authorization(&mut interceptor), "Bearer a");
+
+        // Replace the file at once, so that the refreshing thread never sees it half done.
+        let write = |content: &str, modified: SystemTime| {
+            let tmp = dir.path().join("token.tmp");
+            std::fs::write(&tmp, content).unwrap();
+            let file = std::fs::File::options().write(true).open(&tmp).unwrap();
+            file.set_modified(modified).unwrap();
+            std::fs::rename(&tmp, &path).unwrap();
+        };
+        let modified = SystemTime::now() + Duration::from_secs(10);
+        write("b", modified);
+        token.refresh();
+        assert_eq!(authorization(&mut interceptor), "Bearer b");
+
+        // Not reread if the mtime is the same.
+        write("c", modified);
+        token.refresh();
+        assert_eq!(authorization(&mut interceptor), "Bearer b");
+    }
+}
@@ -0,0 +1,812 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An Ethereum JSON-RPC gateway translating requests to CITA-Cloud's gRPC services.
+//!
+//! Semantic differences it works around:
+//! - `eth_sendRawTransaction` takes a hex-encoded protobuf `RawTransaction`, or a bare
+//!   `UnverifiedTransaction`/`UnverifiedUtxoTransaction`, signed by the CITA-Cloud way,
+//!   i.e. what `SignerBehaviour::sign_raw_tx` builds. RLP-encoded Ethereum txs(legacy or
+//!   EIP-2718 typed) are rejected since their signing payload is different.
+//! - CITA-Cloud's chain id is 32 bytes, `eth_chainId` and `net_version` return its last 8 bytes.
+//! - There is no gas price. Quota is reported as gas, and `eth_gasPrice` is always 0.
+//! - Block timestamps are in milliseconds on CITA-Cloud and are converted to seconds.
+//! - Block hashes are the hash of the block header by the chain's crypto, not keccak of RLP.
+//!   The `miner` is the proposer, which may be a 48-byte BLS address.
+//! - A tx's nonce is an arbitrary string on CITA-Cloud, it's reported as 0 if not a number.
+//! - UTXO txs(system config changes) are shown with a `null` `to` and their output as `input`.
+//! - A receipt's `status` is derived from its error message, since there is no status field.
+//! - Filters and subscriptions are not supported.
+
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result};
+use cita_cloud_proto::{
+    blockchain::{
+        raw_transaction::Tx, Block, RawTransaction, UnverifiedTransaction,
+        UnverifiedUtxoTransaction,
+    },
+    evm::{BlockNumber as EvmBlockNumber, Log, Receipt},
+};
+use eth_jsonrpc_lib::rpc_types::{BlockNumber as RpcBlockNumber, BlockTag};
+use ethereum_types::{H256, U256};
+use prost::Message;
+use serde_json::{json, Value as Json};
+use tokio::{
+    io::{AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use super::{
+    controller::ControllerBehaviour,
+    endpoints::is_not_found,
+    evm::{convert_block_number, EvmBehaviour},
+    executor::ExecutorBehaviour,
+    http,
+};
+use crate::{
+    crypto::{Address, Hash},
+    utils::{hex, parse_addr, parse_data, parse_hash},
+};
+
+const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn invalid_params(message: impl std::fmt::Display) -> Self {
+        Self::new(INVALID_PARAMS, format!("invalid params: {message}"))
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(SERVER_ERROR, format!("{e:#}"))
+    }
+}
+
+fn quantity(v: u64) -> Json {
+    json!(format!("{v:#x}"))
+}
+
+fn u256_quantity(bytes: &[u8]) -> Json {
+    json!(format!("{:#x}", U256::from_big_endian(bytes)))
+}
+
+fn data_or_null(data: &[u8]) -> Json {
+    if data.is_empty() {
+        Json::Null
+    } else {
+        json!(hex(data))
+    }
+}
+
+// Accept what CITA-Cloud signs, i.e. a `RawTransaction` or one of the txs it wraps.
+fn decode_raw_tx(bytes: &[u8]) -> Result<RawTransaction, RpcError> {
+    // A legacy Ethereum tx is an RLP list, and a typed one starts with its type.
+    // Neither is a valid protobuf tag of these messages.
+    if matches!(bytes.first(), Some(0x01..=0x04 | 0xc0..=0xff)) {
+        return Err(RpcError::invalid_params(
+            "Ethereum txs are not supported, sign it the CITA-Cloud way",
+        ));
+    }
+    // Protobuf decoding is lenient, so check the required fields to tell them apart.
+    if let Ok(raw) = RawTransaction::decode(bytes) {
+        let is_complete = match &raw.tx {
+            Some(Tx::NormalTx(tx)) => tx.transaction.is_some() && tx.witness.is_some(),
+            Some(Tx::UtxoTx(utxo)) => utxo.transaction.is_some() && !utxo.witnesses.is_empty(),
+            None => false,
+        };
+        if is_complete {
+            return Ok(raw);
+        }
+    }
+    if let Ok(tx) = UnverifiedTransaction::decode(bytes) {
+        if tx.transaction.is_some() && tx.witness.is_some() {
+            return Ok(RawTransaction {
+                tx: Some(Tx::NormalTx(tx)),
+            });
+        }
+    }
+    if let Ok(utxo) = UnverifiedUtxoTransaction::decode(bytes) {
+        if utxo.transaction.is_some() && !utxo.witnesses.is_empty() {
+            return Ok(RawTransaction {
+                tx: Some(Tx::UtxoTx(utxo)),
+            });
+        }
+    }
+    Err(RpcError::invalid_params(
+        "expect a protobuf encoded CITA-Cloud raw transaction",
+    ))
+}
+
+struct Params(Vec<Json>);
+
+impl Params {
+    fn str(&self, idx: usize) -> Result<&str, RpcError> {
+        self.0
+            .get(idx)
+            .and_then(Json::as_str)
+            .ok_or_else(|| RpcError::invalid_params(format!("missing string param at `{idx}`")))
+    }
+
+    fn bool(&self, idx: usize) -> bool {
+        self.0.get(idx).and_then(Json::as_bool).unwrap_or(false)
+    }
+
+    fn addr(&self, idx: usize) -> Result<Address, RpcError> {
+        parse_addr(self.str(idx)?).map_err(RpcError::invalid_params)
+    }
+
+    fn hash(&self, idx: usize) -> Result<Hash, RpcError> {
+        parse_hash(self.str(idx)?).map_err(RpcError::invalid_params)
+    }
+
+    // A block tag, height or hash, defaults to `latest`.
+    fn block(&self, idx: usize) -> Result<RpcBlockNumber, RpcError> {
+        match self.0.get(idx) {
+            Some(block) => serde_json::from_value(block.clone()).map_err(RpcError::invalid_params),
+            None => Ok(RpcBlockNumber::Tag(BlockTag::Latest)),
+        }
+    }
+
+    fn block_number(&self, idx: usize) -> Result<EvmBlockNumber, RpcError> {
+        self.block(idx).map(convert_block_number)
+    }
+
+    // The call object of `eth_call` and `eth_estimateGas`, returns (from, to, data).
+    fn call_object(&self, idx: usize) -> Result<(Vec<u8>, Vec<u8>, Vec<u8>), RpcError> {
+        let obj = self
+            .0
+            .get(idx)
+            .and_then(Json::as_object)
+            .ok_or_else(|| RpcError::invalid_params("missing call object"))?;
+        let field = |name: &str| -> Result<Vec<u8>, RpcError> {
+            match obj.get(name).and_then(Json::as_str) {
+                Some(s) => parse_data(s).map_err(RpcError::invalid_params),
+                None => Ok(vec![]),
+            }
+        };
+        // `input` is the newer name of `data`.
+        let data = match obj.get("input") {
+            Some(_) => field("input")?,
+            None => field("data")?,
+        };
+        Ok((field("from")?, field("to")?, data))
+    }
+}
+
+pub struct Gateway<Co, Ex, Ev> {
+    controller: Co,
+    executor: Ex,
+    evm: Ev,
+}
+
+impl<Co, Ex, Ev> Gateway<Co, Ex, Ev>
+where
+    Co: ControllerBehaviour + Send + Sync + 'static,
+    Ex: ExecutorBehaviour + Send + Sync + 'static,
+    Ev: EvmBehaviour + Send + Sync + 'static,
+{
+    pub fn new(controller: Co, executor: Ex, evm: Ev) -> Self {
+        Self {
+            controller,
+            executor,
+            evm,
+        }
+    }
+
+    /// Serve JSON-RPC over HTTP POST. It never returns unless failing to accept connections.
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        let this = Arc::new(self);
+        loop {
+            let (stream, _) = listener
+                .accept()
+                .await
+                .context("failed to accept connection")?;
+            let this = this.clone();
+            tokio::spawn(async move {
+                if let Err(e) = this.handle_connection(stream).await {
+                    eprintln!("jsonrpc connection error: {e:#}");
+                }
+            });
+        }
+    }
+
+    // A minimal HTTP/1.1 server that is just enough for JSON-RPC clients.
+    async fn handle_connection(&self, mut stream: TcpStream) -> Result<()> {
+        let (reader, mut writer) = stream.split();
+        let mut reader = BufReader::new(reader);
+        while let Some(req) = http::read_request(&mut reader, MAX_BODY_SIZE).await? {
+            let (status, resp_body) = match req.method.as_str() {
+                "POST" => ("200 OK", self.handle_body(&req.body).await.to_string()),
+                // CORS preflight from browsers.
+                "OPTIONS" => ("204 No Content", String::new()),
+                _ => ("405 Method Not Allowed", String::new()),
+            };
+            let resp = format!(
+                "HTTP/1.1 {status}\r\n\
+                Content-Type: application/json\r\n\
+                Content-Length: {}\r\n\
+                Access-Control-Allow-Origin: *\r\n\
+                Access-Control-Allow-Methods: POST, OPTIONS\r\n\
+                Access-Control-Allow-Headers: content-type\r\n\
+                \r\n{resp_body}",
+                resp_body.len()
+            );
+            writer.write_all(resp.as_bytes()).await?;
+            if !req.keep_alive {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn handle_body(&self, body: &[u8]) -> Json {
+        match serde_json::from_slice::<Json>(body) {
+            Ok(Json::Array(reqs)) if !reqs.is_empty() => {
+                let mut resps = Vec::with_capacity(reqs.len());
+                for req in reqs {
+                    resps.push(self.handle_request(req).await);
+                }
+                Json::Array(resps)
+            }
+            Ok(Json::Array(_)) => {
+                error_response(Json::Null, RpcError::new(INVALID_REQUEST, "empty batch"))
+            }
+            Ok(req) => self.handle_request(req).await,
+            Err(e) => error_response(Json::Null, RpcError::new(PARSE_ERROR, e.to_string())),
+        }
+    }
+
+    async fn handle_request(&self, req: Json) -> Json {
+        let id = req.get("id").cloned().unwrap_or(Json::Null);
+        let Some(method) = req.get("method").and_then(Json::as_str) else {
+            return error_response(id, RpcError::new(INVALID_REQUEST, "missing method"));
+        };
+        let params = match req.get("params") {
+            None => Params(vec![]),
+            Some(Json::Array(params)) => Params(params.clone()),
+            Some(_) => {
+                return error_response(id, RpcError::invalid_params("params must be an array"))
+            }
+        };
+
+        match self.dispatch(method, params).await {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e),
+        }
+    }
+
+    async fn dispatch(&self, method: &str, params: Params) -> Result<Json, RpcError> {
+        let result = match method {
+            "web3_clientVersion" => json!(format!("cldi/{}", env!("CARGO_PKG_VERSION"))),
+            "net_version" => json!(self.chain_id().await?.to_string()),
+            "net_listening" => json!(true),
+            "eth_chainId" => quantity(self.chain_id().await?),
+            "eth_syncing" => json!(false),
+            "eth_accounts" => json!([]),
+            "eth_gasPrice" => quantity(0),
+            "eth_blockNumber" => quantity(self.controller.get_block_number(false).await?),
+            "eth_getBalance" => {
+                let balance = self
+                    .evm
+                    .get_balance(params.addr(0)?, params.block_number(1)?)
+                    .await?;
+                u256_quantity(&balance.value)
+            }
+            "eth_getTransactionCount" => {
+                let nonce = self
+                    .evm
+                    .get_tx_count(params.addr(0)?, params.block_number(1)?)
+                    .await?;
+                u256_quantity(&nonce.nonce)
+            }
+            "eth_getCode" => {
+                let code = self
+                    .evm
+                    .get_code(params.addr(0)?, params.block_number(1)?)
+                    .await?;
+                json!(hex(&code.byte_code))
+            }
+            "eth_getStorageAt" => {
+                let position = parse_data(params.str(1)?).map_err(RpcError::invalid_params)?;
+                if position.len() > 32 {
+                    return Err(RpcError::invalid_params("storage position too long"));
+                }
+                let mut padded = [0; 32];
+                padded[32 - position.len()..].copy_from_slice(&position);
+                let value = self
+                    .evm
+                    .get_storage_at(params.addr(0)?, padded, params.block_number(2)?)
+                    .await?;
+                json!(hex(&value))
+            }
+            "eth_call" => {
+                let (from, to, data) = params.call_object(0)?;
+                let from = if from.is_empty() {
+                    [0; 20]
+                } else {
+                    Address::try_from(from.as_slice()).map_err(RpcError::invalid_params)?
+                };
+                let to = Address::try_from(to.as_slice()).map_err(RpcError::invalid_params)?;
+                // Height 0 means the latest for executor.
+                let height = match params.0.get(1) {
+                    Some(_) => self.resolve_height(params.block(1)?).await?,
+                    None => 0,
+                };
+                let resp = self.executor.call(from, to, data, height).await?;
+                json!(hex(&resp.value))
+            }
+            "eth_estimateGas" => {
+                let (from, to, data) = params.call_object(0)?;
+                let quota = self.evm.estimate_quota(from, to, data).await?;
+                u256_quantity(&quota.bytes_quota)
+            }
+            "eth_sendRawTransaction" => {
+                let bytes = parse_data(params.str(0)?).map_err(RpcError::invalid_params)?;
+                let raw = decode_raw_tx(&bytes)?;
+                json!(hex(&self.controller.send_raw(raw).await?))
+            }
+            "eth_getBlockByNumber" => {
+                let height = self.resolve_height(params.block(0)?).await?;
+                self.block_json(height, params.bool(1)).await?
+            }
+            "eth_getBlockByHash" => {
+                match self.controller.get_height_by_hash(params.hash(0)?).await {
+                    Ok(height) => self.block_json(height.block_number, params.bool(1)).await?,
+                    // Ethereum returns null for unknown blocks.
+                    Err(e) if is_not_found(&e) => Json::Null,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            "eth_getTransactionByHash" => {
+                let tx_hash = params.hash(0)?;
+                match self.controller.get_tx(tx_hash).await {
+                    Ok(raw) => {
+                        let height = self.controller.get_tx_block_number(tx_hash).await?;
+                        let index = self.controller.get_tx_index(tx_hash).await?;
+                        let block_hash = self.controller.get_block_hash(height).await?;
+                        tx_json(&raw, &block_hash, height, index)
+                    }
+                    // Ethereum returns null for unknown txs, but an unavailable node is an error.
+                    Err(e) if is_not_found(&e) => Json::Null,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            "eth_getTransactionReceipt" => {
+                let tx_hash = params.hash(0)?;
+                match self.evm.get_receipt(tx_hash).await {
+                    Ok(receipt) => {
+                        let raw = self.controller.get_tx(tx_hash).await?;
+                        receipt_json(&receipt, &raw)
+                    }
+                    // Not committed yet.
+                    Err(e) if is_not_found(&e) => Json::Null,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            _ => {
+                return Err(RpcError::new(
+                    METHOD_NOT_FOUND,
+                    format!("method `{method}` not supported"),
+                ))
+            }
+        };
+
+        Ok(result)
+    }
+
+    async fn chain_id(&self) -> Result<u64> {
+        let chain_id = self.controller.get_system_config().await?.chain_id;
+        let mut last_8_bytes = [0; 8];
+        let len = chain_id.len().min(8);
+        last_8_bytes[8 - len..].copy_from_slice(&chain_id[chain_id.len() - len..]);
+        Ok(u64::from_be_bytes(last_8_bytes))
+    }
+
+    async fn resolve_height(&self, block: RpcBlockNumber) -> Result<u64> {
+        match block {
+            RpcBlockNumber::Tag(BlockTag::Latest | BlockTag::Safe | BlockTag::Finalized) => {
+                self.controller.get_block_number(false).await
+            }
+            RpcBlockNumber::Tag(BlockTag::Pending) => self.controller.get_block_number(true).await,
+            RpcBlockNumber::Tag(BlockTag::Earliest) => Ok(0),
+            RpcBlockNumber::Height(height) => Ok(height.0.low_u64()),
+            RpcBlockNumber::Hash(hash) => {
+                let hash: H256 = hash.into();
+                Ok(self
+                    .controller
+                    .get_height_by_hash(hash.0)
+                    .await?
+                    .block_number)
+            }
+        }
+    }
+
+    async fn block_json(&self, height: u64, full_txs: bool) -> Result<Json> {
+        let block = match self.controller.get_block_detail_by_number(height).await {
+            Ok(block) => block,
+            Err(e) if is_not_found(&e) => return Ok(Json::Null),
+            Err(e) => return Err(e),
+        };
+        let block_hash = self.controller.get_block_hash(height).await?;
+        let quota_limit = self
+            .controller
+            .get_system_config_by_number(height)
+            .await
+            .map(|config| u64::from(config.quota_limit))
+            .unwrap_or_default();
+        Ok(block_json(&block, &block_hash, quota_limit, full_txs))
+    }
+}
+
+fn error_response(id: Json, e: RpcError) -> Json {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": e.code, "message": e.message },
+    })
+}
+
+fn tx_hash_of(raw: &RawTransaction) -> &[u8] {
+    match &raw.tx {
+        Some(Tx::NormalTx(tx)) => &tx.transaction_hash,
+        Some(Tx::UtxoTx(utxo)) => &utxo.transaction_hash,
+        None => &[],
+    }
+}
+
+fn tx_json(raw: &RawTransaction, block_hash: &[u8], height: u64, index: u64) -> Json {
+    let mut obj = json!({
+        "hash": hex(tx_hash_of(raw)),
+        "blockHash": hex(block_hash),
+        "blockNumber": quantity(height),
+        "transactionIndex": quantity(index),
+        "gasPrice": quantity(0),
+    });
+    let fields = match &raw.tx {
+        Some(Tx::NormalTx(utx)) => {
+            let tx = utx.transaction.clone().unwrap_or_default();
+            json!({
+                "from": utx.witness.as_ref().map(|w| hex(&w.sender)),
+                "to": data_or_null(&tx.to),
+                "input": hex(&tx.data),
+                "value": u256_quantity(&tx.value),
+                "nonce": quantity(tx.nonce.parse().unwrap_or_default()),
+                "gas": quantity(tx.quota),
+            })
+        }
+        Some(Tx::UtxoTx(utxo)) => {
+            let tx = utxo.transaction.clone().unwrap_or_default();
+            json!({
+                "from": utxo.witnesses.first().map(|w| hex(&w.sender)),
+                "to": Json::Null,
+                "input": hex(&tx.output),
+                "value": quantity(0),
+                "nonce": quantity(0),
+                "gas": quantity(0),
+            })
+        }
+        None => json!({}),
+    };
+    if let (Some(obj), Json::Object(fields)) = (obj.as_object_mut(), fields) {
+        obj.extend(fields);
+    }
+    obj
+}
+
+fn block_json(block: &Block, block_hash: &[u8], quota_limit: u64, full_txs: bool) -> Json {
+    let header = block.header.clone().unwrap_or_default();
+    let txs = block
+        .body
+        .as_ref()
+        .map(|body| body.body.as_slice())
+        .unwrap_or_default();
+    let transactions = txs
+        .iter()
+        .enumerate()
+        .map(|(index, raw)| {
+            if full_txs {
+                tx_json(raw, block_hash, header.height, index as u64)
+            } else {
+                json!(hex(tx_hash_of(raw)))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "number": quantity(header.height),
+        "hash": hex(block_hash),
+        "parentHash": hex(&header.prevhash),
+        "nonce": "0x0000000000000000",
+        "sha3Uncles": hex(&[0; 32]),
+        "logsBloom": hex(&[0; 256]),
+        "transactionsRoot": hex(&header.transactions_root),
+        "stateRoot": hex(&block.state_root),
+        "receiptsRoot": hex(&[0; 32]),
+        "miner": hex(&header.proposer),
+        "difficulty": quantity(0),
+        "totalDifficulty": quantity(0),
+        "extraData": "0x",
+        "size": quantity(block.encoded_len() as u64),
+        "gasLimit": quantity(quota_limit),
+        "gasUsed": quantity(0),
+        "timestamp": quantity(header.timestamp / 1000),
+        "transactions": transactions,
+        "uncles": [],
+    })
+}
+
+fn log_json(log: &Log) -> Json {
+    json!({
+        "address": hex(&log.address),
+        "topics": log.topics.iter().map(|t| hex(t)).collect::<Vec<_>>(),
+        "data": hex(&log.data),
+        "blockHash": hex(&log.block_hash),
+        "blockNumber": quantity(log.block_number),
+        "transactionHash": hex(&log.transaction_hash),
+        "transactionIndex": quantity(log.transaction_index),
+        "logIndex": quantity(log.log_index),
+        "transactionLogIndex": quantity(log.transaction_log_index),
+        "removed": false,
+    })
+}
+
+fn receipt_json(receipt: &Receipt, raw: &RawTransaction) -> Json {
+    let (from, to) = match &raw.tx {
+        Some(Tx::NormalTx(utx)) => (
+            utx.witness.as_ref().map(|w| hex(&w.sender)),
+            utx.transaction
+                .as_ref()
+                .map(|tx| data_or_null(&tx.to))
+                .unwrap_or(Json::Null),
+        ),
+        _ => (None, Json::Null),
+    };
+    json!({
+        "transactionHash": hex(&receipt.transaction_hash),
+        "transactionIndex": quantity(receipt.transaction_index),
+        "blockHash": hex(&receipt.block_hash),
+        "blockNumber": quantity(receipt.block_number),
+        "from": from,
+        "to": to,
+        "cumulativeGasUsed": u256_quantity(&receipt.cumulative_quota_used),
+        "gasUsed": u256_quantity(&receipt.quota_used),
+        "effectiveGasPrice": quantity(0),
+        "contractAddress": data_or_null(&receipt.contract_address),
+        "logs": receipt.logs.iter().map(log_json).collect::<Vec<_>>(),
+        "logsBloom": hex(&receipt.logs_bloom),
+        "root": hex(&receipt.state_root),
+        "status": quantity(receipt.error_message.is_empty() as u64),
+        "type": quantity(0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mock::{MockControllerClient, MockEvmClient, MockExecutorClient};
+    use cita_cloud_proto::controller::SystemConfig;
+
+    #[test]
+    fn test_jsonrpc_gateway() {
+        let mut controller = MockControllerClient::default();
+        controller.expect_get_block_number().returning(|_| Ok(0x10));
+        controller.expect_get_system_config().returning(|| {
+            Ok(SystemConfig {
+                chain_id: [[0u8; 24].as_slice(), &0x1234u64.to_be_bytes()].concat(),
+                ..Default::default()
+            })
+        });
+        let gateway = Gateway::new(
+            controller,
+            MockExecutorClient::default(),
+            MockEvmClient::default(),
+        );
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let resp = rt.block_on(gateway.handle_body(
+            br#"[
+                {"jsonrpc":"2.0","id":1,"method":"eth_blockNumber","params":[]},
+                {"jsonrpc":"2.0","id":2,"method":"eth_chainId"},
+                {"jsonrpc":"2.0","id":3,"method":"eth_getLogs","params":[{}]},
+                {"jsonrpc":"2.0","id":4,"method":"eth_getBalance","params":["0x1234"]}
+            ]"#,
+        ));
+        assert_eq!(resp[0]["result"], "0x10");
+        assert_eq!(resp[1]["result"], "0x1234");
+        assert_eq!(resp[2]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(resp[3]["error"]["code"], INVALID_PARAMS);
+
+        let resp = rt.block_on(gateway.handle_body(b"{"));
+        assert_eq!(resp["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_jsonrpc_errors() {
+        let mut controller = MockControllerClient::default();
+        controller.expect_get_block_number().returning(|_| Ok(0x10));
+        let gateway = Gateway::new(
+            controller,
+            MockExecutorClient::default(),
+            MockEvmClient::default(),
+        );
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let handle = |body: &str| rt.block_on(gateway.handle_body(body.as_bytes()));
+
+        for malformed in ["", "{", r#"{"method":"eth_blockNumber""#, "\u{0}"] {
+            let resp = handle(malformed);
+            assert_eq!(resp["error"]["code"], PARSE_ERROR, "`{malformed}`");
+            assert_eq!(resp["id"], Json::Null);
+        }
+        assert_eq!(handle("[]")["error"]["code"], INVALID_REQUEST);
+        assert_eq!(handle(r#"{"id":1}"#)["error"]["code"], INVALID_REQUEST);
+        assert_eq!(handle("1")["error"]["code"], INVALID_REQUEST);
+        let resp = handle(r#"{"id":7,"method":"eth_blockNumber","params":{}}"#);
+        assert_eq!(resp["error"]["code"], INVALID_PARAMS);
+        assert_eq!(resp["id"], 7);
+        let resp = handle(r#"{"id":"a","method":"eth_newFilter","params":[]}"#);
+        assert_eq!(resp["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(resp["id"], "a");
+
+        // Each request of a batch is answered on its own, in order.
+        let resp = handle(
+            r#"[
+                1,
+                {"id":1},
+                {"jsonrpc":"2.0","id":2,"method":"eth_blockNumber"},
+                {"jsonrpc":"2.0","id":3,"method":"eth_getBlockByNumber","params":["0xzz"]},
+                {"jsonrpc":"2.0","id":4,"method":"eth_sendRawTransaction","params":["0xzz"]}
+            ]"#,
+        );
+        let codes: Vec<_> = resp
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|resp| resp["error"]["code"].clone())
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                json!(INVALID_REQUEST),
+                json!(INVALID_REQUEST),
+                Json::Null,
+                json!(INVALID_PARAMS),
+                json!(INVALID_PARAMS),
+            ]
+        );
+        assert_eq!(resp[2]["result"], "0x10");
+        assert_eq!(resp[2]["id"], 2);
+    }
+
+    #[test]
+    fn test_not_found_is_null() {
+        let hash = hex(&[1; 32]);
+        let mut controller = MockControllerClient::default();
+        controller
+            .expect_get_tx()
+            .returning(|_| Err(tonic::Status::not_found("tx not found").into()));
+        controller
+            .expect_get_block_detail_by_number()
+            .returning(|height| match height {
+                1 => Err(tonic::Status::not_found("block not found").into()),
+                _ => Err(tonic::Status::unavailable("node is down").into()),
+            });
+        let mut evm = MockEvmClient::default();
+        evm.expect_get_receipt()
+            .returning(|_| Err(tonic::Status::unavailable("node is down").into()));
+        let gateway = Gateway::new(controller, MockExecutorClient::default(), evm);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let call = |method: &str, params: Json| {
+            let req = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+            rt.block_on(gateway.handle_body(req.to_string().as_bytes()))
+        };
+
+        // Only a real not-found is `null`, anything else must not look like one.
+        let resp = call("eth_getTransactionByHash", json!([hash]));
+        assert_eq!(resp["result"], Json::Null);
+        assert!(resp.get("error").is_none());
+        let resp = call("eth_getBlockByNumber", json!(["0x1", false]));
+        assert_eq!(resp["result"], Json::Null);
+        assert!(resp.get("error").is_none());
+        let resp = call("eth_getBlockByNumber", json!(["0x2", false]));
+        assert_eq!(resp["error"]["code"], SERVER_ERROR);
+        let resp = call("eth_getTransactionReceipt", json!([hash]));
+        assert_eq!(resp["error"]["code"], SERVER_ERROR);
+        assert!(resp["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("node is down"));
+    }
+
+    #[test]
+    fn test_send_raw_transaction() {
+        use cita_cloud_proto::blockchain::{Transaction, Witness};
+
+        let tx = UnverifiedTransaction {
+            transaction: Some(Transaction {
+                nonce: "1".into(),
+                ..Default::default()
+            }),
+            transaction_hash: vec![1; 32],
+            witness: Some(Witness {
+                signature: vec![2; 64],
+                sender: vec![3; 20],
+            }),
+        };
+        let raw = RawTransaction {
+            tx: Some(Tx::NormalTx(tx.clone())),
+        };
+
+        let mut controller = MockControllerClient::default();
+        let expected = raw.clone();
+        controller
+            .expect_send_raw()
+            .times(2)
+            .withf(move |raw| raw == &expected)
+            .returning(|_| Ok([4; 32]));
+        let gateway = Gateway::new(
+            controller,
+            MockExecutorClient::default(),
+            MockEvmClient::default(),
+        );
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let send = |bytes: &[u8]| {
+            let req = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_sendRawTransaction",
+                "params": [hex(bytes)],
+            });
+            rt.block_on(gateway.handle_body(req.to_string().as_bytes()))
+        };
+
+        // Both the wrapped and the bare tx.
+        assert_eq!(send(&raw.encode_to_vec())["result"], hex(&[4; 32]));
+        assert_eq!(send(&tx.encode_to_vec())["result"], hex(&[4; 32]));
+
+        // A legacy and an EIP-1559 Ethereum tx.
+        let legacy = parse_data("0xf86c808504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83").unwrap();
+        let typed = parse_data("0x02f86b0180843b9aca00843b9aca0082520894000000000000000000000000000000000000000180c001a0aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa01111111111111111111111111111111111111111111111111111111111111111").unwrap();
+        for eth_tx in [legacy, typed] {
+            let resp = send(&eth_tx);
+            assert_eq!(resp["error"]["code"], INVALID_PARAMS);
+            assert!(resp["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("Ethereum txs are not supported"));
+        }
+        // Neither of them.
+        let resp = send(&tx.transaction.unwrap().encode_to_vec());
+        assert_eq!(resp["error"]["code"], INVALID_PARAMS);
+    }
+}
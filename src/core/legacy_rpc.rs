@@ -0,0 +1,785 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A client for legacy CITA 1.x nodes over their JSON-RPC.
+//!
+//! It implements the same behaviours as the gRPC clients, so that commands work
+//! on both generations. Some differences to keep in mind:
+//! - a CITA 1.x node serves all the methods at one address, the executor address is unused.
+//! - txs are re-encoded into CITA 1.x's protobuf format before signing, so only
+//!   [`ControllerBehaviour::sign_and_send_tx`] can send txs. Sending pre-signed
+//!   CITA-Cloud txs, including the UTXO txs used by admin commands, is unsupported.
+//! - block proofs, state roots of compact blocks and receipt proofs are unavailable.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
+use cita_cloud_proto::{
+    blockchain::{
+        raw_transaction::Tx, Block, BlockHeader, CompactBlock, CompactBlockBody, RawTransaction,
+        RawTransactions, Transaction as CloudNormalTransaction, UnverifiedTransaction, Witness,
+    },
+    common::{NodeStatus, PeerStatus, Proof, StateRoot},
+    controller::{BlockNumber as CloudBlockNumber, CrossChainProof, SystemConfig},
+    evm::{
+        block_number::Lable, Balance, BlockNumber, ByteAbi, ByteCode, ByteQuota, Log, Nonce,
+        Receipt, ReceiptProof, RootsInfo,
+    },
+    executor::CallResponse,
+};
+use ethereum_types::U256;
+use libproto::blockchain::{
+    Crypto as LegacyCrypto, Transaction as LegacyTransaction,
+    UnverifiedTransaction as LegacyUnverifiedTransaction,
+};
+use protobuf::Message as _;
+use serde_json::{json, Value as Json};
+
+use super::{
+    controller::{
+        CompactBlockWithStaterootProof, ControllerBehaviour, ProofWithValidators, SignerBehaviour,
+    },
+    evm::EvmBehaviour,
+    executor::ExecutorBehaviour,
+    http::{self, HttpUrl},
+};
+use crate::{
+    crypto::{Address, Hash},
+    utils::{hex, parse_data, parse_hash, parse_value},
+};
+
+/// CITA 1.x rejects txs whose valid_until_block is beyond current height + 100.
+const LEGACY_BLOCK_LIMIT: u64 = 100;
+
+#[derive(Debug, Clone)]
+pub struct LegacyClient {
+    url: HttpUrl,
+    timeout: Duration,
+}
+
+impl LegacyClient {
+    /// Accept `host:port` or `http://host:port/path`.
+    pub fn new(url: &str, timeout: Duration) -> Result<Self> {
+        let url = HttpUrl::parse(url).context("invalid CITA node address")?;
+        Ok(Self { url, timeout })
+    }
+
+    pub async fn request(&self, method: &str, params: Json) -> Result<Json> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        })
+        .to_string();
+        let resp = tokio::time::timeout(self.timeout, http::post(&self.url, &body))
+            .await
+            .map_err(|_| anyhow!("CITA JSON-RPC `{method}` timeout"))?
+            .with_context(|| format!("failed to request `{method}` from `{}`", self.url.host))?;
+
+        let mut resp: Json =
+            serde_json::from_slice(&resp).context("invalid CITA JSON-RPC response")?;
+        if let Some(err) = resp.get("error").filter(|err| !err.is_null()) {
+            bail!("CITA JSON-RPC `{method}` failed: {err}");
+        }
+        Ok(resp["result"].take())
+    }
+
+    async fn get_block_json(&self, block_number: u64, full_tx: bool) -> Result<Json> {
+        let block = self
+            .request(
+                "getBlockByNumber",
+                json!([format!("{block_number:#x}"), full_tx]),
+            )
+            .await?;
+        ensure!(!block.is_null(), "block `{block_number}` not found");
+        Ok(block)
+    }
+
+    async fn get_tx_json(&self, tx_hash: Hash) -> Result<Json> {
+        let tx = self
+            .request("getTransaction", json!([hex(&tx_hash)]))
+            .await?;
+        ensure!(!tx.is_null(), "tx `{}` not found", hex(&tx_hash));
+        Ok(tx)
+    }
+
+    async fn get_state(
+        &self,
+        method: &str,
+        addr: Address,
+        block_number: &BlockNumber,
+    ) -> Result<Json> {
+        self.request(method, json!([hex(&addr), block_tag(block_number)?]))
+            .await
+    }
+}
+
+fn block_tag(block_number: &BlockNumber) -> Result<String> {
+    let tag = match &block_number.lable {
+        None => "latest".to_string(),
+        Some(Lable::Tag(tag)) => tag.clone(),
+        Some(Lable::Height(height)) => format!("{height:#x}"),
+        Some(Lable::Hash(_)) => bail!("CITA 1.x doesn't support querying state by block hash"),
+    };
+    Ok(tag)
+}
+
+fn str_field<'a>(v: &'a Json, name: &str) -> Result<&'a str> {
+    v[name]
+        .as_str()
+        .with_context(|| format!("missing field `{name}` in CITA JSON-RPC response"))
+}
+
+fn data_field(v: &Json, name: &str) -> Result<Vec<u8>> {
+    match &v[name] {
+        Json::Null => Ok(vec![]),
+        field => parse_data(field.as_str().unwrap_or_default())
+            .with_context(|| format!("invalid field `{name}`")),
+    }
+}
+
+fn u64_field(v: &Json, name: &str) -> Result<u64> {
+    ensure!(
+        !v[name].is_null(),
+        "missing field `{name}` in CITA JSON-RPC response"
+    );
+    as_u64(&v[name]).with_context(|| format!("invalid field `{name}`"))
+}
+
+fn as_u64(v: &Json) -> Result<u64> {
+    // Quantities are hex strings, except some plain numbers like timestamp.
+    match v {
+        Json::Number(n) => n.as_u64().context("not a u64"),
+        Json::String(s) => Ok(u64::from_str_radix(s.trim_start_matches("0x"), 16)?),
+        v => bail!("unexpected quantity `{v}`"),
+    }
+}
+
+fn quantity(v: &Json) -> Result<Vec<u8>> {
+    let s = v
+        .as_str()
+        .context("invalid quantity in CITA JSON-RPC response")?;
+    Ok(parse_value(s)?.to_vec())
+}
+
+fn u256_bytes(v: U256) -> Vec<u8> {
+    let mut buf = [0; 32];
+    v.to_big_endian(&mut buf);
+    buf.to_vec()
+}
+
+/// Convert a CITA 1.x tx with its hash and sender into CITA-Cloud's format.
+fn convert_tx(tx_json: &Json) -> Result<RawTransaction> {
+    let content = data_field(tx_json, "content")?;
+    let utx = LegacyUnverifiedTransaction::parse_from_bytes(&content)
+        .context("invalid CITA 1.x tx content")?;
+    let tx = utx.get_transaction();
+    let (to, chain_id) = if tx.get_version() == 0 {
+        (
+            parse_data(tx.get_to())?,
+            u256_bytes(U256::from(tx.get_chain_id())),
+        )
+    } else {
+        (tx.get_to_v1().to_vec(), tx.get_chain_id_v1().to_vec())
+    };
+    let transaction = CloudNormalTransaction {
+        version: tx.get_version(),
+        to,
+        nonce: tx.get_nonce().into(),
+        quota: tx.get_quota(),
+        valid_until_block: tx.get_valid_until_block(),
+        data: tx.get_data().to_vec(),
+        value: tx.get_value().to_vec(),
+        chain_id,
+    };
+    let witness = Witness {
+        signature: utx.get_signature().to_vec(),
+        sender: data_field(tx_json, "from")?,
+    };
+
+    Ok(RawTransaction {
+        tx: Some(Tx::NormalTx(UnverifiedTransaction {
+            transaction: Some(transaction),
+            transaction_hash: data_field(tx_json, "hash")?,
+            witness: Some(witness),
+        })),
+    })
+}
+
+fn convert_header(block: &Json) -> Result<BlockHeader> {
+    let header = &block["header"];
+    Ok(BlockHeader {
+        prevhash: data_field(header, "prevHash")?,
+        timestamp: u64_field(header, "timestamp")?,
+        height: u64_field(header, "number")?,
+        transactions_root: data_field(header, "transactionsRoot")?,
+        proposer: data_field(header, "proposer")?,
+    })
+}
+
+fn block_txs(block: &Json) -> &[Json] {
+    block["body"]["transactions"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn convert_compact_block(block: &Json) -> Result<CompactBlock> {
+    let tx_hashes = block_txs(block)
+        .iter()
+        .map(|tx| match tx {
+            Json::String(hash) => parse_data(hash),
+            tx => data_field(tx, "hash"),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(CompactBlock {
+        version: u64_field(block, "version")? as u32,
+        header: Some(convert_header(block)?),
+        body: Some(CompactBlockBody { tx_hashes }),
+    })
+}
+
+fn convert_log(log: &Json) -> Result<Log> {
+    let topics = log["topics"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|t| parse_data(t.as_str().unwrap_or_default()))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Log {
+        address: data_field(log, "address")?,
+        topics,
+        data: data_field(log, "data")?,
+        block_hash: data_field(log, "blockHash")?,
+        block_number: u64_field(log, "blockNumber")?,
+        transaction_hash: data_field(log, "transactionHash")?,
+        transaction_index: u64_field(log, "transactionIndex")?,
+        log_index: u64_field(log, "logIndex")?,
+        transaction_log_index: u64_field(log, "transactionLogIndex")?,
+    })
+}
+
+fn convert_receipt(receipt: &Json) -> Result<Receipt> {
+    let logs = receipt["logs"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(convert_log)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Receipt {
+        transaction_hash: data_field(receipt, "transactionHash")?,
+        transaction_index: u64_field(receipt, "transactionIndex")?,
+        block_hash: data_field(receipt, "blockHash")?,
+        block_number: u64_field(receipt, "blockNumber")?,
+        cumulative_quota_used: quantity(&receipt["cumulativeQuotaUsed"])?,
+        quota_used: quantity(&receipt["quotaUsed"])?,
+        contract_address: data_field(receipt, "contractAddress")?,
+        logs,
+        state_root: data_field(receipt, "root")?,
+        logs_bloom: data_field(receipt, "logsBloom")?,
+        error_message: receipt["errorMessage"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+fn convert_metadata(metadata: &Json) -> Result<SystemConfig> {
+    let version = u64_field(metadata, "version")? as u32;
+    // CITA 1.x's v0 tx has a u32 chain id, keep it in the last 4 bytes.
+    let chain_id = if version == 0 {
+        u256_bytes(U256::from(u64_field(metadata, "chainId")?))
+    } else {
+        data_field(metadata, "chainIdV1")?
+    };
+    let validators = metadata["validators"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|v| parse_data(v.as_str().unwrap_or_default()))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(SystemConfig {
+        version,
+        chain_id,
+        block_interval: (u64_field(metadata, "blockInterval")? / 1000) as u32,
+        validators,
+        block_limit: LEGACY_BLOCK_LIMIT,
+        ..Default::default()
+    })
+}
+
+#[tonic::async_trait]
+impl ControllerBehaviour for LegacyClient {
+    async fn send_raw(&self, _raw: RawTransaction) -> Result<Hash> {
+        bail!("CITA 1.x doesn't accept CITA-Cloud txs, only normal txs signed by cldi can be sent")
+    }
+
+    async fn get_system_config(&self) -> Result<SystemConfig> {
+        let metadata = self.request("getMetaData", json!(["latest"])).await?;
+        convert_metadata(&metadata)
+    }
+
+    async fn get_system_config_by_number(&self, block_number: u64) -> Result<SystemConfig> {
+        let metadata = self
+            .request("getMetaData", json!([format!("{block_number:#x}")]))
+            .await?;
+        convert_metadata(&metadata)
+    }
+
+    async fn get_block_number(&self, _for_pending: bool) -> Result<u64> {
+        let height = self.request("blockNumber", json!([])).await?;
+        as_u64(&height)
+    }
+
+    async fn get_block_hash(&self, block_number: u64) -> Result<Hash> {
+        let block = self.get_block_json(block_number, false).await?;
+        parse_hash(str_field(&block, "hash")?)
+    }
+
+    async fn get_height_by_hash(&self, hash: Hash) -> Result<CloudBlockNumber> {
+        let block = self
+            .request("getBlockByHash", json!([hex(&hash), false]))
+            .await?;
+        ensure!(!block.is_null(), "block `{}` not found", hex(&hash));
+        Ok(CloudBlockNumber {
+            block_number: u64_field(&block["header"], "number")?,
+        })
+    }
+
+    async fn get_compact_block_by_number(&self, block_number: u64) -> Result<CompactBlock> {
+        let block = self.get_block_json(block_number, false).await?;
+        convert_compact_block(&block)
+    }
+
+    async fn get_block_by_number(
+        &self,
+        block_number: u64,
+    ) -> Result<CompactBlockWithStaterootProof> {
+        let block = self.get_block_json(block_number, false).await?;
+        Ok(CompactBlockWithStaterootProof {
+            compact_block: convert_compact_block(&block)?,
+            state_root: StateRoot {
+                state_root: data_field(&block["header"], "stateRoot")?,
+            },
+            // CITA 1.x's proof is in json, which has no counterpart in CITA-Cloud.
+            proof: Proof { proof: vec![] },
+        })
+    }
+
+    async fn get_block_detail_by_number(&self, block_number: u64) -> Result<Block> {
+        let block = self.get_block_json(block_number, true).await?;
+        let body = block_txs(&block)
+            .iter()
+            .map(convert_tx)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Block {
+            version: u64_field(&block, "version")? as u32,
+            header: Some(convert_header(&block)?),
+            body: Some(RawTransactions { body }),
+            proof: vec![],
+            state_root: data_field(&block["header"], "stateRoot")?,
+        })
+    }
+
+    async fn get_tx(&self, tx_hash: Hash) -> Result<RawTransaction> {
+        let tx = self.get_tx_json(tx_hash).await?;
+        convert_tx(&tx)
+    }
+
+    async fn get_tx_index(&self, tx_hash: Hash) -> Result<u64> {
+        let tx = self.get_tx_json(tx_hash).await?;
+        u64_field(&tx, "index")
+    }
+
+    async fn get_tx_block_number(&self, tx_hash: Hash) -> Result<u64> {
+        let tx = self.get_tx_json(tx_hash).await?;
+        u64_field(&tx, "blockNumber")
+    }
+
+    async fn get_node_status(&self) -> Result<NodeStatus> {
+        let peers_count = self.request("peerCount", json!([])).await?;
+        let height = self.get_block_number(false).await?;
+        Ok(NodeStatus {
+            is_sync: true,
+            version: "CITA 1.x".into(),
+            peers_count: as_u64(&peers_count)?,
+            self_status: Some(PeerStatus {
+                height,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    async fn add_node(&self, _multiaddr: String) -> Result<u32> {
+        bail!("CITA 1.x doesn't support adding node by rpc")
+    }
+
+    async fn parse_overlord_proof(&self, _proof_bytes: Vec<u8>) -> Result<ProofWithValidators> {
+        bail!("CITA 1.x doesn't use overlord consensus")
+    }
+
+    async fn get_cross_chain_proof(&self, _hash: Hash) -> Result<CrossChainProof> {
+        bail!("CITA 1.x doesn't support cross chain proof")
+    }
+
+    async fn sign_and_send_tx<S>(&self, signer: &S, tx: CloudNormalTransaction) -> Result<Hash>
+    where
+        S: SignerBehaviour + Send + Sync,
+    {
+        let mut legacy_tx = LegacyTransaction::new();
+        legacy_tx.set_nonce(tx.nonce);
+        legacy_tx.set_quota(tx.quota);
+        legacy_tx.set_valid_until_block(tx.valid_until_block);
+        legacy_tx.set_data(tx.data);
+        legacy_tx.set_value(tx.value);
+        legacy_tx.set_version(tx.version);
+        if tx.version == 0 {
+            // v0 uses a hex str without 0x for `to`, and a u32 chain id.
+            legacy_tx.set_to(hex::encode(&tx.to));
+            let chain_id = U256::from_big_endian(&tx.chain_id);
+            ensure!(chain_id <= U256::from(u32::MAX), "invalid v0 chain id");
+            legacy_tx.set_chain_id(chain_id.low_u32());
+        } else {
+            legacy_tx.set_to_v1(tx.to);
+            legacy_tx.set_chain_id_v1(tx.chain_id);
+        }
+
+        let tx_bytes = legacy_tx.write_to_bytes()?;
+        let tx_hash = signer.hash(&tx_bytes)?;
+        let mut utx = LegacyUnverifiedTransaction::new();
+        utx.set_transaction(legacy_tx);
+        utx.set_signature(signer.sign(&tx_hash)?);
+        utx.set_crypto(LegacyCrypto::DEFAULT);
+
+        let resp = self
+            .request("sendRawTransaction", json!([hex(&utx.write_to_bytes()?)]))
+            .await?;
+        parse_hash(str_field(&resp, "hash")?)
+    }
+}
+
+#[tonic::async_trait]
+impl ExecutorBehaviour for LegacyClient {
+    async fn call(
+        &self,
+        from: Address,
+        to: Address,
+        data: Vec<u8>,
+        height: u64,
+    ) -> Result<CallResponse> {
+        let tag = if height == 0 {
+            "latest".to_string()
+        } else {
+            format!("{height:#x}")
+        };
+        let call_request = json!({
+            "from": hex(&from),
+            "to": hex(&to),
+            "data": hex(&data),
+        });
+        let resp = self.request("call", json!([call_request, tag])).await?;
+        Ok(CallResponse {
+            value: parse_data(resp.as_str().unwrap_or_default())?,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl EvmBehaviour for LegacyClient {
+    async fn get_receipt(&self, hash: Hash) -> Result<Receipt> {
+        let receipt = self
+            .request("getTransactionReceipt", json!([hex(&hash)]))
+            .await?;
+        ensure!(!receipt.is_null(), "receipt of `{}` not found", hex(&hash));
+        convert_receipt(&receipt)
+    }
+
+    async fn get_code(&self, addr: Address, block_number: BlockNumber) -> Result<ByteCode> {
+        let code = self.get_state("getCode", addr, &block_number).await?;
+        Ok(ByteCode {
+            byte_code: parse_data(code.as_str().unwrap_or_default())?,
+        })
+    }
+
+    async fn get_balance(&self, addr: Address, block_number: BlockNumber) -> Result<Balance> {
+        let balance = self.get_state("getBalance", addr, &block_number).await?;
+        Ok(Balance {
+            value: quantity(&balance)?,
+        })
+    }
+
+    async fn get_tx_count(&self, addr: Address, block_number: BlockNumber) -> Result<Nonce> {
+        let count = self
+            .get_state("getTransactionCount", addr, &block_number)
+            .await?;
+        Ok(Nonce {
+            nonce: quantity(&count)?,
+        })
+    }
+
+    async fn get_abi(&self, addr: Address, block_number: BlockNumber) -> Result<ByteAbi> {
+        let abi = self.get_state("getAbi", addr, &block_number).await?;
+        Ok(ByteAbi {
+            bytes_abi: parse_data(abi.as_str().unwrap_or_default())?,
+        })
+    }
+
+    async fn estimate_quota(
+        &self,
+        from: Vec<u8>,
+        to: Vec<u8>,
+        method: Vec<u8>,
+    ) -> Result<ByteQuota> {
+        let call_request = json!({
+            "from": hex(&from),
+            "to": hex(&to),
+            "data": hex(&method),
+        });
+        let quota = self
+            .request("estimateQuota", json!([call_request, "latest"]))
+            .await?;
+        Ok(ByteQuota {
+            bytes_quota: quantity(&quota)?,
+        })
+    }
+
+    async fn get_receipt_proof(&self, _hash: Hash) -> Result<ReceiptProof> {
+        bail!("CITA 1.x doesn't support receipt proof")
+    }
+
+    async fn get_roots_info(&self, _block_number: BlockNumber) -> Result<RootsInfo> {
+        bail!("CITA 1.x doesn't support roots info")
+    }
+
+    async fn get_storage_at(
+        &self,
+        addr: Address,
+        position: Hash,
+        block_number: BlockNumber,
+    ) -> Result<Hash> {
+        let value = self
+            .request(
+                "getStorageAt",
+                json!([hex(&addr), hex(&position), block_tag(&block_number)?]),
+            )
+            .await?;
+        Ok(parse_value(value.as_str().unwrap_or_default())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::wallet::Account, crypto::SmCrypto};
+    use tokio::{
+        io::{AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    // A stub CITA 1.x node that answers each request by method name.
+    async fn stub_node(respond: fn(&str, &Json) -> Json) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(stream);
+                let body = http::read_request(&mut stream, 1 << 20)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .body;
+                let req: Json = serde_json::from_slice(&body).unwrap();
+                let result = respond(req["method"].as_str().unwrap(), &req["params"]);
+                let resp =
+                    json!({ "jsonrpc": "2.0", "id": req["id"], "result": result }).to_string();
+                // Reply in chunked encoding like CITA 1.x's jsonrpc server does for large bodies.
+                let (first, second) = resp.split_at(resp.len() / 2);
+                let chunked = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{first}\r\n{:x}\r\n{second}\r\n0\r\n\r\n",
+                    first.len(),
+                    second.len(),
+                );
+                stream.write_all(chunked.as_bytes()).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    fn respond(method: &str, params: &Json) -> Json {
+        match method {
+            "blockNumber" => json!("0x10"),
+            "getBalance" => json!("0x64"),
+            "getMetaData" => json!({
+                "chainId": 1,
+                "chainIdV1": "0x01",
+                "version": 0,
+                "blockInterval": 3000,
+                "validators": ["0x0000000000000000000000000000000000000001"],
+            }),
+            "getBlockByNumber" => json!({
+                "version": 1,
+                "hash": format!("0x{}", "11".repeat(32)),
+                "header": {
+                    "timestamp": 1_600_000_000_000u64,
+                    "prevHash": format!("0x{}", "22".repeat(32)),
+                    "number": params[0],
+                    "stateRoot": format!("0x{}", "33".repeat(32)),
+                    "transactionsRoot": format!("0x{}", "44".repeat(32)),
+                    "proposer": "0x0000000000000000000000000000000000000001",
+                },
+                "body": { "transactions": [format!("0x{}", "55".repeat(32))] },
+            }),
+            "getTransactionReceipt" => Json::Null,
+            "sendRawTransaction" => {
+                let content = parse_data(params[0].as_str().unwrap()).unwrap();
+                let utx = LegacyUnverifiedTransaction::parse_from_bytes(&content).unwrap();
+                assert_eq!(utx.get_transaction().get_chain_id(), 1);
+                assert_eq!(utx.get_transaction().get_to(), "00".repeat(20));
+                json!({ "hash": format!("0x{}", "66".repeat(32)), "status": "OK" })
+            }
+            _ => json!({ "code": -32601 }),
+        }
+    }
+
+    // The `result` of a response from a CITA 1.x node.
+    fn fixture(json: &str) -> Json {
+        let mut resp: Json = serde_json::from_str(json).unwrap();
+        resp["result"].take()
+    }
+
+    #[test]
+    fn test_convert_fixtures() {
+        let tx_hash =
+            parse_data("0x2a7d8b2a4f4d7e0d9ac5c1b3e8f1e2d3c4b5a69788796a5b4c3d2e1f0a1b2c3d")
+                .unwrap();
+        let block_hash =
+            parse_data("0xa7deea2c1a4d0f5bd6b1f4b3bbb6f9e1e2b2a0c5e0c6d5f8aa01ab92cb3c1b40")
+                .unwrap();
+        let sender = parse_data("0x46a23e25df9a0f6c18729dda9ad1af3b6a131160").unwrap();
+
+        let metadata = fixture(include_str!("../../test/legacy/metadata.json"));
+        let system_config = convert_metadata(&metadata).unwrap();
+        assert_eq!(system_config.version, 2);
+        assert_eq!(
+            U256::from_big_endian(&system_config.chain_id),
+            U256::from(1)
+        );
+        assert_eq!(system_config.block_interval, 3);
+        assert_eq!(system_config.validators.len(), 2);
+        assert_eq!(system_config.block_limit, LEGACY_BLOCK_LIMIT);
+
+        let tx = fixture(include_str!("../../test/legacy/transaction.json"));
+        let Some(Tx::NormalTx(utx)) = convert_tx(&tx).unwrap().tx else {
+            panic!("not a normal tx");
+        };
+        assert_eq!(utx.transaction_hash, tx_hash);
+        let witness = utx.witness.unwrap();
+        assert_eq!(witness.sender, sender);
+        assert_eq!(witness.signature.len(), 65);
+        let transaction = utx.transaction.unwrap();
+        assert_eq!(transaction.version, 2);
+        assert_eq!(
+            transaction.to,
+            parse_data("0xffffffffffffffffffffffffffffffffff020004").unwrap()
+        );
+        assert_eq!(transaction.nonce, "6d2c9e7f3a0c4b1e");
+        assert_eq!(transaction.quota, 1_000_000);
+        assert_eq!(transaction.valid_until_block, 1025);
+        assert_eq!(&transaction.data[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(transaction.value, vec![0; 32]);
+        assert_eq!(transaction.chain_id, system_config.chain_id);
+
+        let block = fixture(include_str!("../../test/legacy/block.json"));
+        let compact_block = convert_compact_block(&block).unwrap();
+        assert_eq!(compact_block.version, 1);
+        let header = compact_block.header.unwrap();
+        assert_eq!(header.height, 0x1d);
+        assert_eq!(header.timestamp, 1_557_474_376_426);
+        assert_eq!(header.proposer, system_config.validators[0]);
+        assert_eq!(compact_block.body.unwrap().tx_hashes, vec![tx_hash.clone()]);
+        assert_eq!(block_txs(&block).len(), 1);
+        assert!(convert_tx(&block_txs(&block)[0]).is_ok());
+
+        let receipt = fixture(include_str!("../../test/legacy/receipt.json"));
+        let receipt = convert_receipt(&receipt).unwrap();
+        assert_eq!(receipt.transaction_hash, tx_hash);
+        assert_eq!(receipt.block_hash, block_hash);
+        assert_eq!(receipt.block_number, 0x1d);
+        assert_eq!(
+            U256::from_big_endian(&receipt.quota_used),
+            U256::from(0xaf5c)
+        );
+        // `null`s of a successful call.
+        assert!(receipt.contract_address.is_empty());
+        assert!(receipt.state_root.is_empty());
+        assert!(receipt.error_message.is_empty());
+        assert_eq!(receipt.logs_bloom.len(), 256);
+        let log = &receipt.logs[0];
+        assert_eq!(log.topics.len(), 3);
+        assert_eq!(log.block_number, 0x1d);
+        assert_eq!(U256::from_big_endian(&log.data), U256::from(100));
+    }
+
+    #[test]
+    fn test_legacy_client() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let addr = stub_node(respond).await;
+            let client = LegacyClient::new(&addr, Duration::from_secs(3)).unwrap();
+
+            assert_eq!(client.get_block_number(false).await.unwrap(), 16);
+            let balance = client
+                .get_balance([0; 20], BlockNumber::default())
+                .await
+                .unwrap();
+            assert_eq!(U256::from_big_endian(&balance.value), U256::from(100));
+            assert!(client.get_receipt([0; 32]).await.is_err());
+
+            let system_config = client.get_system_config().await.unwrap();
+            assert_eq!(system_config.version, 0);
+            assert_eq!(system_config.block_interval, 3);
+            assert_eq!(
+                U256::from_big_endian(&system_config.chain_id),
+                U256::from(1)
+            );
+
+            let block = client.get_compact_block_by_number(8).await.unwrap();
+            assert_eq!(block.header.unwrap().height, 8);
+            assert_eq!(block.body.unwrap().tx_hashes, vec![vec![0x55; 32]]);
+            assert_eq!(client.get_block_hash(8).await.unwrap(), [0x11; 32]);
+
+            let account = Account::<SmCrypto>::generate();
+            let tx = CloudNormalTransaction {
+                version: system_config.version,
+                to: vec![0; 20],
+                nonce: "1".into(),
+                quota: 200_000,
+                valid_until_block: 100,
+                data: vec![],
+                value: vec![0; 32],
+                chain_id: system_config.chain_id,
+            };
+            let tx_hash = client.sign_and_send_tx(&account, tx).await.unwrap();
+            assert_eq!(tx_hash, [0x66; 32]);
+        });
+    }
+}
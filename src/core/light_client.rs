@@ -0,0 +1,243 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use cita_cloud_proto::{blockchain::BlockHeader, common::ProposalInner};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    controller::{CompactBlockWithStaterootProof, ControllerBehaviour},
+    cross_chain::{verify_overlord_proof, CrossChainResultCode},
+};
+use crate::{
+    crypto::{Crypto, Hash},
+    utils::{hex, parse_data, safe_save},
+};
+
+const CHECKPOINTS_DIR_NAME: &str = "checkpoints";
+
+#[derive(Debug, Error)]
+pub enum LightClientError {
+    #[error("block `{0}` has no header")]
+    NoneBlockHeader(u64),
+    #[error("block `{height}` returned by the node has a header of height `{found}`")]
+    HeightMismatched { height: u64, found: u64 },
+    #[error(
+        "block `{height}` is not linked to its parent, expected prevhash `{expected}`, found `{found}`"
+    )]
+    BrokenLinkage {
+        height: u64,
+        expected: String,
+        found: String,
+    },
+    #[error("the proof of block `{height}` is invalid: {code}")]
+    InvalidProof {
+        height: u64,
+        code: CrossChainResultCode,
+    },
+}
+
+/// A block trusted to be finalized. It's the starting point for verifying its descendants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub height: u64,
+    pub block_hash: Vec<u8>,
+    pub state_root: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedCheckpoint {
+    height: u64,
+    block_hash: String,
+    state_root: String,
+}
+
+impl Serialize for Checkpoint {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedCheckpoint {
+            height: self.height,
+            block_hash: hex(&self.block_hash),
+            state_root: hex(&self.state_root),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Checkpoint {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let serialized = SerializedCheckpoint::deserialize(deserializer)?;
+        Ok(Self {
+            height: serialized.height,
+            block_hash: parse_data(&serialized.block_hash).map_err(D::Error::custom)?,
+            state_root: parse_data(&serialized.state_root).map_err(D::Error::custom)?,
+        })
+    }
+}
+
+/// Checkpoints persisted in `<data-dir>/checkpoints/<chain-id>.toml`.
+pub struct CheckpointStore {
+    checkpoints_dir: PathBuf,
+}
+
+impl CheckpointStore {
+    pub fn open(data_dir: impl AsRef<Path>) -> Result<Self> {
+        let checkpoints_dir = data_dir.as_ref().join(CHECKPOINTS_DIR_NAME);
+        fs::create_dir_all(&checkpoints_dir).context("cannot create checkpoints dir")?;
+
+        Ok(Self { checkpoints_dir })
+    }
+
+    pub fn load(&self, chain_id: &[u8]) -> Result<Option<Checkpoint>> {
+        let path = self.checkpoint_path(chain_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("cannot read checkpoint file `{}`", path.display()))?;
+        let checkpoint = toml::from_str(&content)
+            .with_context(|| format!("invalid checkpoint file `{}`", path.display()))?;
+
+        Ok(Some(checkpoint))
+    }
+
+    pub fn save(&self, chain_id: &[u8], checkpoint: &Checkpoint) -> Result<()> {
+        let content = toml::to_string_pretty(checkpoint)?;
+        safe_save(self.checkpoint_path(chain_id), content.as_bytes(), true)
+    }
+
+    fn checkpoint_path(&self, chain_id: &[u8]) -> PathBuf {
+        self.checkpoints_dir
+            .join(format!("{}.toml", hex::encode(chain_id)))
+    }
+}
+
+pub fn block_header_hash<C: Crypto>(header: &BlockHeader) -> Hash {
+    let mut header_bytes = Vec::with_capacity(header.encoded_len());
+    header.encode(&mut header_bytes).unwrap();
+    C::hash(&header_bytes)
+}
+
+/// Fetch a block as a checkpoint without verifying it. Its descendants can
+/// still be verified against it, but trusting it is up to the caller.
+pub async fn fetch_checkpoint<C: Crypto, Co: ControllerBehaviour>(
+    controller: &Co,
+    height: u64,
+) -> Result<Checkpoint> {
+    let CompactBlockWithStaterootProof {
+        compact_block,
+        state_root,
+        ..
+    } = controller.get_block_by_number(height).await?;
+    let header = compact_block
+        .header
+        .ok_or(LightClientError::NoneBlockHeader(height))?;
+
+    Ok(Checkpoint {
+        height,
+        block_hash: block_header_hash::<C>(&header).to_vec(),
+        state_root: state_root.state_root,
+    })
+}
+
+/// Verify the child block of `parent`, that is
+/// 1. its header links to the parent via prevhash,
+/// 2. its Overlord proof commits to the proposal built from its compact block
+///    and the parent's state root,
+/// 3. the proof is signed by the validators of that height.
+///
+/// On success, returns the verified block as the new checkpoint.
+pub async fn verify_child_block<C: Crypto, Co: ControllerBehaviour>(
+    controller: &Co,
+    parent: &Checkpoint,
+) -> Result<Checkpoint> {
+    let height = parent.height + 1;
+    let CompactBlockWithStaterootProof {
+        compact_block,
+        state_root,
+        proof,
+    } = controller.get_block_by_number(height).await?;
+    let header = compact_block
+        .header
+        .clone()
+        .ok_or(LightClientError::NoneBlockHeader(height))?;
+
+    if header.height != height {
+        return Err(LightClientError::HeightMismatched {
+            height,
+            found: header.height,
+        }
+        .into());
+    }
+    if header.prevhash != parent.block_hash {
+        return Err(LightClientError::BrokenLinkage {
+            height,
+            expected: hex(&parent.block_hash),
+            found: hex(&header.prevhash),
+        }
+        .into());
+    }
+
+    let validators = controller
+        .get_system_config_by_number(height)
+        .await?
+        .validators;
+    let proposal = ProposalInner {
+        pre_state_root: parent.state_root.clone(),
+        proposal: Some(compact_block),
+    };
+    let mut proposal_bytes = Vec::with_capacity(proposal.encoded_len());
+    proposal.encode(&mut proposal_bytes).unwrap();
+    let proposal_hash = C::hash(&proposal_bytes);
+
+    verify_overlord_proof::<C>(&proof.proof, &proposal_hash, height, &validators)
+        .map_err(|code| LightClientError::InvalidProof { height, code })?;
+
+    Ok(Checkpoint {
+        height,
+        block_hash: block_header_hash::<C>(&header).to_vec(),
+        state_root: state_root.state_root,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_checkpoint_store() -> Result<()> {
+        let data_dir = tempdir()?;
+        let store = CheckpointStore::open(data_dir.path())?;
+        let chain_id = [1u8; 32];
+        assert_eq!(store.load(&chain_id)?, None);
+
+        let checkpoint = Checkpoint {
+            height: 100,
+            block_hash: [2u8; 32].to_vec(),
+            state_root: [3u8; 32].to_vec(),
+        };
+        store.save(&chain_id, &checkpoint)?;
+        assert_eq!(store.load(&chain_id)?, Some(checkpoint));
+        assert_eq!(store.load(&[4u8; 32])?, None);
+
+        Ok(())
+    }
+}
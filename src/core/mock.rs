@@ -18,16 +18,18 @@ use super::{
     controller::{CompactBlockWithStaterootProof, ControllerBehaviour, ProofWithValidators},
     evm::EvmBehaviour,
     executor::ExecutorBehaviour,
+    interceptor::MetadataInterceptor,
 };
 use crate::{
     config::Config,
     core::wallet::Account,
     crypto::{Address, Hash, SmCrypto},
+    types::clean_0x,
 };
 use anyhow::Result;
 use cita_cloud_proto::{
-    blockchain::{Block, CompactBlock, RawTransaction},
-    common::NodeStatus,
+    blockchain::{Block, BlockHeader, CompactBlock, CompactBlockBody, RawTransaction},
+    common::{NodeStatus, ProposalInner},
     controller::{BlockNumber, CrossChainProof, SystemConfig},
     evm::{self, Balance, ByteAbi, ByteCode, ByteQuota, Nonce, Receipt, ReceiptProof, RootsInfo},
     executor::CallResponse,
@@ -73,7 +75,7 @@ mock! {
 }
 
 impl GrpcClientBehaviour for MockControllerClient {
-    fn from_channel(_ch: Channel) -> Self {
+    fn from_channel(_ch: Channel, _interceptor: MetadataInterceptor) -> Self {
         MockControllerClient::default()
     }
 }
@@ -98,7 +100,7 @@ mock! {
 }
 
 impl GrpcClientBehaviour for MockExecutorClient {
-    fn from_channel(_ch: Channel) -> Self {
+    fn from_channel(_ch: Channel, _interceptor: MetadataInterceptor) -> Self {
         MockExecutorClient::default()
     }
 }
@@ -135,7 +137,7 @@ mock! {
 }
 
 impl GrpcClientBehaviour for MockEvmClient {
-    fn from_channel(_ch: Channel) -> Self {
+    fn from_channel(_ch: Channel, _interceptor: MetadataInterceptor) -> Self {
         MockEvmClient::default()
     }
 }
@@ -152,7 +154,7 @@ pub fn context() -> (
         ..Default::default()
     };
 
-    let mut ctx = Context::from_config(config).expect("fail to create test context");
+    let mut ctx = Context::from_config(config, &[]).expect("fail to create test context");
 
     let default_account = Account::<SmCrypto>::generate();
     ctx.wallet
@@ -161,3 +163,48 @@ pub fn context() -> (
 
     (ctx, test_dir)
 }
+
+/// A cross chain proof from the fixtures in `test/cross_chain`, which proves the 3rd receipt
+/// in a block of 4 txs. Its hashes are computed outside of this crate, so that the verification
+/// isn't only checked against itself.
+pub fn cross_chain_proof_fixture(json: &str) -> CrossChainProof {
+    let fixture: serde_json::Value = serde_json::from_str(json).unwrap();
+    let bytes = |v: &serde_json::Value| hex::decode(clean_0x(v.as_str().unwrap())).unwrap();
+    let height = fixture["height"].as_u64().unwrap();
+
+    let compact_block = CompactBlock {
+        version: 0,
+        header: Some(BlockHeader {
+            height,
+            transactions_root: bytes(&fixture["transactions_root"]),
+            ..Default::default()
+        }),
+        body: Some(CompactBlockBody {
+            tx_hashes: fixture["tx_hashes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(bytes)
+                .collect(),
+        }),
+    };
+    CrossChainProof {
+        version: fixture["version"].as_u64().unwrap() as u32,
+        chain_id: bytes(&fixture["chain_id"]),
+        proposal: Some(ProposalInner {
+            pre_state_root: bytes(&fixture["pre_state_root"]),
+            proposal: Some(compact_block),
+        }),
+        receipt_proof: Some(ReceiptProof {
+            receipt: bytes(&fixture["receipt"]),
+            receipt_proof: bytes(&fixture["receipt_proof"]),
+            roots_info: Some(RootsInfo {
+                height,
+                state_root: bytes(&fixture["roots_state_root"]),
+                receipt_root: bytes(&fixture["receipt_root"]),
+            }),
+        }),
+        proof: vec![],
+        state_root: bytes(&fixture["state_root"]),
+    }
+}
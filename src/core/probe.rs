@@ -0,0 +1,190 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{bail, ensure, Context as _, Result};
+use cita_cloud_proto::blockchain::{raw_transaction::Tx, Block};
+use overlord::types::Proof as OverlordProof;
+use rlp::{Decodable, Rlp};
+use serde::Serialize;
+
+use super::{controller::ControllerBehaviour, light_client::block_header_hash};
+use crate::{
+    config::{ConsensusType, CryptoType},
+    crypto::{eth, sm, EthCrypto, Hash, SmCrypto},
+};
+
+/// How many recent blocks to scan for a signed tx.
+const MAX_SCANNED_BLOCKS: u64 = 32;
+
+/// The chain parameters that a context must agree with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ChainProfile {
+    pub crypto_type: CryptoType,
+    pub consensus_type: ConsensusType,
+}
+
+/// Infer the chain's crypto type from its blocks and txs, and the consensus type from its proofs.
+pub async fn probe<Co: ControllerBehaviour>(controller: &Co) -> Result<ChainProfile> {
+    let height = controller
+        .get_block_number(false)
+        .await
+        .context("failed to get block number")?;
+    // The genesis block has no proof.
+    ensure!(
+        height > 0,
+        "no block has been produced, cannot probe the chain"
+    );
+
+    let mut crypto_type = None;
+    let mut consensus_type = None;
+    for h in (1..=height).rev().take(MAX_SCANNED_BLOCKS as usize) {
+        let block = controller
+            .get_block_detail_by_number(h)
+            .await
+            .with_context(|| format!("failed to get block `{h}`"))?;
+        if consensus_type.is_none() {
+            consensus_type.replace(consensus_type_of_proof(&block.proof)?);
+        }
+        if crypto_type.is_none() {
+            let block_hash = controller
+                .get_block_hash(h)
+                .await
+                .with_context(|| format!("failed to get the hash of block `{h}`"))?;
+            crypto_type = crypto_type_of_block(&block, &block_hash);
+        }
+        if let (Some(crypto_type), Some(consensus_type)) = (crypto_type, consensus_type) {
+            return Ok(ChainProfile {
+                crypto_type,
+                consensus_type,
+            });
+        }
+    }
+
+    bail!("cannot infer the crypto type from the latest `{MAX_SCANNED_BLOCKS}` blocks")
+}
+
+fn crypto_type_of_block(block: &Block, block_hash: &Hash) -> Option<CryptoType> {
+    // Block hash is the hash of the encoded header, try both hash algorithms
+    // since they have the same length.
+    if let Some(header) = &block.header {
+        if block_header_hash::<SmCrypto>(header) == *block_hash {
+            return Some(CryptoType::Sm);
+        }
+        if block_header_hash::<EthCrypto>(header) == *block_hash {
+            return Some(CryptoType::Eth);
+        }
+    }
+
+    // Fall back to the signature length of the txs.
+    let body = block.body.as_ref()?;
+    body.body.iter().find_map(|raw| {
+        let signatures: Vec<&[u8]> = match raw.tx.as_ref()? {
+            Tx::NormalTx(tx) => tx.witness.iter().map(|w| w.signature.as_slice()).collect(),
+            Tx::UtxoTx(tx) => tx
+                .witnesses
+                .iter()
+                .map(|w| w.signature.as_slice())
+                .collect(),
+        };
+        signatures.into_iter().find_map(crypto_type_of_signature)
+    })
+}
+
+fn crypto_type_of_signature(signature: &[u8]) -> Option<CryptoType> {
+    match signature.len() {
+        sm::SM2_SIGNATURE_BYTES_LEN => Some(CryptoType::Sm),
+        eth::SIGNATURE_BYTES_LEN => Some(CryptoType::Eth),
+        _ => None,
+    }
+}
+
+fn consensus_type_of_proof(proof: &[u8]) -> Result<ConsensusType> {
+    // Raft doesn't produce proofs.
+    if proof.is_empty() {
+        return Ok(ConsensusType::Raft);
+    }
+    match OverlordProof::decode(&Rlp::new(proof)) {
+        Ok(_) => Ok(ConsensusType::Overlord),
+        Err(_) => bail!("unknown proof format"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mock::MockControllerClient;
+    use cita_cloud_proto::blockchain::{
+        BlockHeader, RawTransaction, RawTransactions, UnverifiedTransaction, Witness,
+    };
+
+    fn block_with_signature(signature: Vec<u8>) -> Block {
+        let tx = UnverifiedTransaction {
+            witness: Some(Witness {
+                signature,
+                sender: vec![0; 20],
+            }),
+            ..Default::default()
+        };
+        Block {
+            header: Some(BlockHeader {
+                height: 1,
+                ..Default::default()
+            }),
+            body: Some(RawTransactions {
+                body: vec![RawTransaction {
+                    tx: Some(Tx::NormalTx(tx)),
+                }],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_probe() {
+        let mut controller = MockControllerClient::default();
+        controller.expect_get_block_number().returning(|_| Ok(1));
+        controller
+            .expect_get_block_detail_by_number()
+            .returning(|_| Ok(block_with_signature(vec![0; eth::SIGNATURE_BYTES_LEN])));
+        // Doesn't match the header, so the signature decides.
+        controller
+            .expect_get_block_hash()
+            .returning(|_| Ok([0; 32]));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let profile = rt.block_on(probe(&controller)).unwrap();
+        assert_eq!(
+            profile,
+            ChainProfile {
+                crypto_type: CryptoType::Eth,
+                consensus_type: ConsensusType::Raft,
+            }
+        );
+    }
+
+    #[test]
+    fn test_crypto_type_of_block() {
+        let block = block_with_signature(vec![]);
+        let header = block.header.as_ref().unwrap();
+        assert_eq!(
+            crypto_type_of_block(&block, &block_header_hash::<SmCrypto>(header)),
+            Some(CryptoType::Sm)
+        );
+        assert_eq!(
+            crypto_type_of_block(&block, &block_header_hash::<EthCrypto>(header)),
+            Some(CryptoType::Eth)
+        );
+        assert_eq!(crypto_type_of_block(&block, &[0; 32]), None);
+    }
+}
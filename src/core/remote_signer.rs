@@ -0,0 +1,345 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Accounts whose keys live outside of cldi, e.g. in an HSM or a custodial signing service.
+//!
+//! cldi delegates `address`, `hash` and `sign` to an external signer, which is either
+//! - a command run by `sh -c`, reading one request from stdin and writing the response to stdout, or
+//! - a local HTTP endpoint, receiving the request as a POST body.
+//!
+//! A request is `{"method": "address" | "hash" | "sign", "msg": "0x.."}`, where `msg` is
+//! absent for `address`. The response is `{"result": "0x.."}` or `{"error": "<message>"}`.
+
+use std::{
+    io::{Read, Write},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+
+use super::{
+    controller::SignerBehaviour,
+    http::{self, HttpUrl},
+};
+use crate::{
+    config::CryptoType,
+    crypto::{Address, Crypto, EthCrypto, SmCrypto},
+    utils::{hex, parse_addr, parse_data},
+};
+
+/// How long to wait for the signer, either a command or an HTTP endpoint.
+const SIGNER_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteEndpoint {
+    Command(String),
+    /// `http://host:port/path`
+    Http(String),
+}
+
+impl RemoteEndpoint {
+    fn request(&self, method: &str, msg: Option<&[u8]>) -> Result<Vec<u8>> {
+        let mut req = json!({ "method": method });
+        if let Some(msg) = msg {
+            req["msg"] = hex(msg).into();
+        }
+        let resp = match self {
+            Self::Command(command) => {
+                run_signer_command(command, &req.to_string(), SIGNER_TIMEOUT)?
+            }
+            Self::Http(url) => {
+                let http_url = HttpUrl::parse(url)?;
+                http::post_blocking(&http_url, &req.to_string(), SIGNER_TIMEOUT)
+                    .with_context(|| format!("signer `{url}` failed"))?
+            }
+        };
+
+        let resp: Json = serde_json::from_slice(&resp).context("invalid signer response")?;
+        if let Some(err) = resp.get("error").filter(|err| !err.is_null()) {
+            bail!("signer refused to `{method}`: {err}");
+        }
+        let result = resp["result"]
+            .as_str()
+            .ok_or_else(|| anyhow!("signer response has no result"))?;
+        parse_data(result)
+    }
+}
+
+// The command is killed if it doesn't finish in time.
+fn run_signer_command(command: &str, req: &str, timeout: Duration) -> Result<Vec<u8>> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("cannot run signer command `{command}`"))?;
+    // Dropped after writing to close the stdin.
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{req}").context("cannot write to signer command")?;
+    }
+    // Read in background so that the command never blocks on a full pipe.
+    fn read_in_background<R: Read + Send + 'static>(
+        pipe: Option<R>,
+    ) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    }
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!(
+                "signer command `{command}` didn't finish within {}s",
+                timeout.as_secs_f32()
+            );
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    let stdout = stdout.join().unwrap();
+    let stderr = stderr.join().unwrap();
+    ensure!(
+        status.success(),
+        "signer command `{command}` failed: {}",
+        String::from_utf8_lossy(&stderr).trim()
+    );
+    Ok(stdout)
+}
+
+/// An account signed by an external signer, it's saved in the wallet with its address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "SerializedRemoteAccount", into = "SerializedRemoteAccount")]
+pub struct RemoteAccount {
+    address: Address,
+    crypto_type: CryptoType,
+    endpoint: RemoteEndpoint,
+}
+
+impl RemoteAccount {
+    /// Ask the signer for its address.
+    pub fn connect(endpoint: RemoteEndpoint, crypto_type: CryptoType) -> Result<Self> {
+        let address = endpoint.request("address", None)?;
+        let address = address
+            .as_slice()
+            .try_into()
+            .with_context(|| format!("invalid address `{}` from signer", hex(&address)))?;
+        Ok(Self {
+            address,
+            crypto_type,
+            endpoint,
+        })
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn crypto_type(&self) -> CryptoType {
+        self.crypto_type
+    }
+}
+
+// What the signer returns is checked, so that a misbehaving signer can't make cldi send
+// a tx that is not of this account.
+impl SignerBehaviour for RemoteAccount {
+    fn hash(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        let hash = self.endpoint.request("hash", Some(msg))?;
+        let expected = match self.crypto_type {
+            CryptoType::Sm => SmCrypto::hash(msg),
+            CryptoType::Eth => EthCrypto::hash(msg),
+        };
+        ensure!(
+            hash == expected.as_slice(),
+            "signer returns hash `{}`, but it should be `{}`",
+            hex(&hash),
+            hex(&expected)
+        );
+        Ok(hash)
+    }
+
+    fn address(&self) -> &[u8] {
+        self.address.as_slice()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        let signature = self.endpoint.request("sign", Some(msg))?;
+        let signer = match self.crypto_type {
+            CryptoType::Sm => SmCrypto::recover(msg, &signature).map(|pk| SmCrypto::pk2addr(&pk)),
+            CryptoType::Eth => {
+                EthCrypto::recover(msg, &signature).map(|pk| EthCrypto::pk2addr(&pk))
+            }
+        };
+        ensure!(
+            signer == Some(self.address),
+            "signature from signer is not signed by `{}`",
+            hex(&self.address)
+        );
+        Ok(signature)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SerializedRemoteAccount {
+    crypto_type: CryptoType,
+    address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+}
+
+impl TryFrom<SerializedRemoteAccount> for RemoteAccount {
+    type Error = anyhow::Error;
+
+    fn try_from(serialized: SerializedRemoteAccount) -> Result<Self, Self::Error> {
+        let endpoint = match (serialized.command, serialized.url) {
+            (Some(command), None) => RemoteEndpoint::Command(command),
+            (None, Some(url)) => RemoteEndpoint::Http(url),
+            _ => bail!("remote account must have either a signer command or a signer url"),
+        };
+        Ok(Self {
+            address: parse_addr(&serialized.address)?,
+            crypto_type: serialized.crypto_type,
+            endpoint,
+        })
+    }
+}
+
+impl From<RemoteAccount> for SerializedRemoteAccount {
+    fn from(account: RemoteAccount) -> Self {
+        let (command, url) = match account.endpoint {
+            RemoteEndpoint::Command(command) => (Some(command), None),
+            RemoteEndpoint::Http(url) => (None, Some(url)),
+        };
+        Self {
+            crypto_type: account.crypto_type,
+            address: hex(&account.address),
+            command,
+            url,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::wallet::{Account, MaybeLocked};
+    use std::net::TcpListener;
+
+    const ADDRESS: &str = "0x0102030405060708091011121314151617181920";
+
+    // Answers by the method with the hash of `msg` and its signature by `account`, whatever
+    // the message is, and refuses empty messages.
+    fn stub_signer_script(dir: &std::path::Path, account: &Account<SmCrypto>) -> String {
+        let hash = SmCrypto::hash(b"msg");
+        let address = hex(account.address());
+        let hash_hex = hex(&hash);
+        let signature = hex(&account.sign(&hash));
+        let script = dir.join("signer.sh");
+        std::fs::write(
+            &script,
+            format!(
+                r#"read req
+case "$req" in
+  *'"msg":"0x"}}') echo '{{"error":"empty msg"}}' ;;
+  *'"address"'*) echo '{{"result":"{address}"}}' ;;
+  *'"hash"'*) echo '{{"result":"{hash_hex}"}}' ;;
+  *'"sign"'*) echo '{{"result":"{signature}"}}' ;;
+esac
+"#
+            ),
+        )
+        .unwrap();
+        format!("sh {}", script.display())
+    }
+
+    #[test]
+    fn test_command_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = Account::<SmCrypto>::generate();
+        let endpoint = RemoteEndpoint::Command(stub_signer_script(dir.path(), &key));
+        let account = RemoteAccount::connect(endpoint, CryptoType::Sm).unwrap();
+        assert_eq!(account.address(), key.address());
+        let hash = account.hash(b"msg").unwrap();
+        assert_eq!(hash, SmCrypto::hash(b"msg").as_slice());
+        assert!(account.sign(&hash).is_ok());
+        assert!(account.sign(b"").is_err());
+
+        // Wrong answers are refused.
+        let err = account.hash(b"other msg").unwrap_err();
+        assert!(err.to_string().contains("should be"));
+        let err = account.sign(b"other msg").unwrap_err();
+        assert!(err.to_string().contains("not signed by"));
+
+        // Saved and loaded by the wallet.
+        let serialized = toml::to_string(&MaybeLocked::from(account.clone())).unwrap();
+        match toml::from_str(&serialized).unwrap() {
+            MaybeLocked::Remote(loaded) => assert_eq!(loaded.endpoint, account.endpoint),
+            _ => panic!("remote account is loaded as a local one"),
+        }
+
+        let failed = RemoteEndpoint::Command("exit 1".into());
+        assert!(RemoteAccount::connect(failed, CryptoType::Sm).is_err());
+    }
+
+    #[test]
+    fn test_signer_command_timeout() {
+        let begin = Instant::now();
+        let err = run_signer_command("sleep 10", "{}", Duration::from_millis(100)).unwrap_err();
+        assert!(err.to_string().contains("didn't finish"));
+        assert!(begin.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_http_signer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/sign", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let n = stream.read(&mut buf).unwrap();
+            let req = String::from_utf8_lossy(&buf[..n]).to_string();
+            let body = format!(r#"{{"result":"{ADDRESS}"}}"#);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            req
+        });
+
+        let account = RemoteAccount::connect(RemoteEndpoint::Http(url), CryptoType::Eth).unwrap();
+        assert_eq!(*account.address(), parse_addr(ADDRESS).unwrap());
+        let req = server.join().unwrap();
+        assert!(req.starts_with("POST /sign HTTP/1.1"));
+        assert!(req.ends_with(r#"{"method":"address"}"#));
+    }
+}
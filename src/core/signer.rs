@@ -0,0 +1,96 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{anyhow, Result};
+
+use super::{
+    agent::AgentSigner,
+    controller::SignerBehaviour,
+    remote_signer::RemoteAccount,
+    wallet::{MultiCryptoAccount, WatchOnlyAccount},
+};
+use crate::{
+    config::CryptoType,
+    crypto::{Address, Crypto, EthCrypto, SmCrypto},
+};
+
+/// Where the current account signs.
+pub enum Signer<'a> {
+    /// Unlocked in the wallet.
+    Local(&'a MultiCryptoAccount),
+    /// Held by the key agent.
+    Agent(AgentSigner),
+    /// Signed by an external signer.
+    Remote(&'a RemoteAccount),
+    /// Only usable for queries, it refuses to sign.
+    WatchOnly {
+        name: &'a str,
+        account: &'a WatchOnlyAccount,
+    },
+}
+
+impl Signer<'_> {
+    pub fn address(&self) -> &Address {
+        match self {
+            Self::Local(account) => account.address(),
+            Self::Agent(signer) => signer.address(),
+            Self::Remote(account) => account.address(),
+            Self::WatchOnly { account, .. } => account.address(),
+        }
+    }
+
+    /// Refuse a watch-only account, before a tx is built for it to sign.
+    pub fn ensure_can_sign(&self) -> Result<()> {
+        match self {
+            Self::WatchOnly { name, .. } => Err(watch_only_error(name)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn watch_only_error(name: &str) -> anyhow::Error {
+    anyhow!(
+        "account `{name}` is watch-only and cannot sign, switch to another account(e.g. `cldi -u <account> [subcommand]`)"
+    )
+}
+
+impl SignerBehaviour for Signer<'_> {
+    fn hash(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Local(account) => account.hash(msg),
+            Self::Agent(signer) => signer.hash(msg),
+            Self::Remote(account) => account.hash(msg),
+            Self::WatchOnly { account, .. } => {
+                let hash = match account.crypto_type() {
+                    CryptoType::Sm => SmCrypto::hash(msg),
+                    CryptoType::Eth => EthCrypto::hash(msg),
+                };
+                Ok(hash.to_vec())
+            }
+        }
+    }
+
+    fn address(&self) -> &[u8] {
+        Signer::address(self)
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Local(account) => account.sign(msg),
+            Self::Agent(signer) => signer.sign(msg),
+            Self::Remote(account) => account.sign(msg),
+            Self::WatchOnly { name, .. } => Err(watch_only_error(name)),
+        }
+    }
+}
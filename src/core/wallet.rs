@@ -22,7 +22,7 @@ use std::{
 
 use crate::{
     config::CryptoType,
-    core::controller::SignerBehaviour,
+    core::{controller::SignerBehaviour, remote_signer::RemoteAccount},
     crypto::{Address, ArrayLike, Crypto, EthCrypto, SmCrypto},
     utils::{hex, parse_addr, parse_data, parse_pk, parse_sk, safe_save},
 };
@@ -406,11 +406,100 @@ impl From<LockedAccount<EthCrypto>> for LockedMultiCryptoAccount {
     }
 }
 
+/// An account known only by its address, used to watch its balance and make queries from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    try_from = "SerializedWatchOnlyAccount",
+    into = "SerializedWatchOnlyAccount"
+)]
+pub struct WatchOnlyAccount {
+    address: Address,
+    public_key: Option<Vec<u8>>,
+    crypto_type: CryptoType,
+}
+
+impl WatchOnlyAccount {
+    /// The address must match the public key if there is one.
+    pub fn new(
+        address: Address,
+        public_key: Option<&str>,
+        crypto_type: CryptoType,
+    ) -> Result<Self> {
+        let public_key = match public_key {
+            Some(pk) => {
+                let pk_addr = match crypto_type {
+                    CryptoType::Sm => SmCrypto::pk2addr(&parse_pk::<SmCrypto>(pk)?),
+                    CryptoType::Eth => EthCrypto::pk2addr(&parse_pk::<EthCrypto>(pk)?),
+                };
+                ensure!(
+                    pk_addr == address,
+                    "address `{}` mismatched with the one computed from public key `{}`",
+                    hex(&address),
+                    hex(&pk_addr),
+                );
+                Some(parse_data(pk)?)
+            }
+            None => None,
+        };
+        Ok(Self {
+            address,
+            public_key,
+            crypto_type,
+        })
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn crypto_type(&self) -> CryptoType {
+        self.crypto_type
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedWatchOnlyAccount {
+    crypto_type: CryptoType,
+    address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    // Tell it apart from other kinds of accounts.
+    watch_only: bool,
+}
+
+impl TryFrom<SerializedWatchOnlyAccount> for WatchOnlyAccount {
+    type Error = anyhow::Error;
+
+    fn try_from(serialized: SerializedWatchOnlyAccount) -> Result<Self, Self::Error> {
+        ensure!(serialized.watch_only, "not a watch-only account");
+        Self::new(
+            parse_addr(&serialized.address)?,
+            serialized.public_key.as_deref(),
+            serialized.crypto_type,
+        )
+    }
+}
+
+impl From<WatchOnlyAccount> for SerializedWatchOnlyAccount {
+    fn from(account: WatchOnlyAccount) -> Self {
+        Self {
+            crypto_type: account.crypto_type,
+            address: hex(&account.address),
+            public_key: account.public_key.as_deref().map(hex),
+            watch_only: true,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum MaybeLocked {
     Unlocked(MultiCryptoAccount),
     Locked(LockedMultiCryptoAccount),
+    /// Signed by an external signer, there is no secret key to lock or unlock.
+    Remote(RemoteAccount),
+    /// No secret key at all, it can't sign.
+    WatchOnly(WatchOnlyAccount),
 }
 
 impl MaybeLocked {
@@ -418,28 +507,42 @@ impl MaybeLocked {
         match self {
             Self::Unlocked(unlocked) => unlocked.address(),
             Self::Locked(locked) => locked.address(),
+            Self::Remote(remote) => remote.address(),
+            Self::WatchOnly(watch_only) => watch_only.address(),
         }
     }
 
+    /// Empty for remote accounts and watch-only accounts without a public key.
     pub fn public_key(&self) -> &[u8] {
         match self {
             Self::Unlocked(unlocked) => unlocked.public_key(),
             Self::Locked(locked) => locked.public_key(),
+            Self::Remote(_) => &[],
+            Self::WatchOnly(watch_only) => watch_only.public_key.as_deref().unwrap_or_default(),
         }
     }
 
     pub fn is_locked(&self) -> bool {
         match self {
-            Self::Unlocked(..) => false,
+            Self::Unlocked(..) | Self::Remote(..) | Self::WatchOnly(..) => false,
             Self::Locked(..) => true,
         }
     }
 
-    #[allow(dead_code)]
-    pub fn lock(self, pw: &[u8]) -> LockedMultiCryptoAccount {
+    pub fn is_remote(&self) -> bool {
+        matches!(self, Self::Remote(..))
+    }
+
+    pub fn is_watch_only(&self) -> bool {
+        matches!(self, Self::WatchOnly(..))
+    }
+
+    pub fn lock(self, pw: &[u8]) -> Result<LockedMultiCryptoAccount> {
         match self {
-            Self::Unlocked(unlocked) => unlocked.lock(pw),
-            Self::Locked(locked) => locked,
+            Self::Unlocked(unlocked) => Ok(unlocked.lock(pw)),
+            Self::Locked(locked) => Ok(locked),
+            Self::Remote(_) => bail!("remote account cannot be locked"),
+            Self::WatchOnly(_) => bail!("watch-only account cannot be locked"),
         }
     }
 
@@ -458,6 +561,8 @@ impl MaybeLocked {
                 MultiCryptoAccount::Sm(ac) => Ok(cloned(ac).into()),
             },
             Self::Locked(locked) => locked.unlock(pw),
+            Self::Remote(_) => bail!("remote account has no secret key in the wallet"),
+            Self::WatchOnly(_) => bail!("watch-only account has no secret key"),
         }
     }
 
@@ -467,6 +572,8 @@ impl MaybeLocked {
             Self::Locked(_) => bail!(
                 "account is locked, please unlock it first(e.g. `cldi -p <password> [subcommand]`)"
             ),
+            Self::Remote(_) => bail!("remote account has no secret key in the wallet"),
+            Self::WatchOnly(_) => bail!("watch-only account has no secret key"),
         }
     }
 
@@ -474,6 +581,8 @@ impl MaybeLocked {
         match self {
             Self::Unlocked(unlocked) => unlocked.crypto_type(),
             Self::Locked(locked) => locked.crypto_type(),
+            Self::Remote(remote) => remote.crypto_type(),
+            Self::WatchOnly(watch_only) => watch_only.crypto_type(),
         }
     }
 }
@@ -514,22 +623,34 @@ impl From<LockedMultiCryptoAccount> for MaybeLocked {
     }
 }
 
+impl From<RemoteAccount> for MaybeLocked {
+    fn from(remote: RemoteAccount) -> Self {
+        Self::Remote(remote)
+    }
+}
+
+impl From<WatchOnlyAccount> for MaybeLocked {
+    fn from(watch_only: WatchOnlyAccount) -> Self {
+        Self::WatchOnly(watch_only)
+    }
+}
+
 impl<C: Crypto> SignerBehaviour for Account<C> {
-    fn hash(&self, msg: &[u8]) -> Vec<u8> {
-        C::hash(msg).to_vec()
+    fn hash(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        Ok(C::hash(msg).to_vec())
     }
 
     fn address(&self) -> &[u8] {
         self.address.as_slice()
     }
 
-    fn sign(&self, msg: &[u8]) -> Vec<u8> {
-        Self::sign(self, msg).to_vec()
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        Ok(Self::sign(self, msg).to_vec())
     }
 }
 
 impl SignerBehaviour for MultiCryptoAccount {
-    fn hash(&self, msg: &[u8]) -> Vec<u8> {
+    fn hash(&self, msg: &[u8]) -> Result<Vec<u8>> {
         match self {
             Self::Sm(ac) => ac.hash(msg),
             Self::Eth(ac) => ac.hash(msg),
@@ -543,7 +664,7 @@ impl SignerBehaviour for MultiCryptoAccount {
         }
     }
 
-    fn sign(&self, msg: &[u8]) -> Vec<u8> {
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
         match self {
             Self::Sm(ac) => <Account<SmCrypto> as SignerBehaviour>::sign(ac, msg),
             Self::Eth(ac) => <Account<EthCrypto> as SignerBehaviour>::sign(ac, msg),
@@ -686,13 +807,25 @@ impl Wallet {
         Ok(())
     }
 
+    fn ensure_lockable(&self, account_name: &str) -> Result<()> {
+        let maybe_locked = self.get(account_name)?;
+        ensure!(!maybe_locked.is_remote(), "remote account cannot be locked");
+        ensure!(
+            !maybe_locked.is_watch_only(),
+            "watch-only account cannot be locked"
+        );
+        Ok(())
+    }
+
     /// Lock the account in both memory and keystore.
     pub fn lock(&mut self, account_name: &str, pw: &[u8]) -> Result<()> {
+        // Checked before removing it.
+        self.ensure_lockable(account_name)?;
         let (account_name, maybe_locked) = self
             .accounts
             .remove_entry(account_name)
             .ok_or_else(|| anyhow!("account `{}` not found", account_name))?;
-        let locked: MaybeLocked = maybe_locked.lock(pw).into();
+        let locked: MaybeLocked = maybe_locked.lock(pw)?.into();
         self.save_overwrite(account_name, locked)?;
 
         Ok(())
@@ -700,11 +833,13 @@ impl Wallet {
 
     /// Lock the account in memory.
     pub fn lock_in_memory(&mut self, account_name: &str, pw: &[u8]) -> Result<()> {
+        // Checked before removing it.
+        self.ensure_lockable(account_name)?;
         let (account_name, maybe_locked) = self
             .accounts
             .remove_entry(account_name)
             .ok_or_else(|| anyhow!("account `{}` not found", account_name))?;
-        let locked: MaybeLocked = maybe_locked.lock(pw).into();
+        let locked: MaybeLocked = maybe_locked.lock(pw)?.into();
         self.save_in_memory(account_name, locked);
 
         Ok(())
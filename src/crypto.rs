@@ -90,6 +90,8 @@ pub trait Crypto: Send + Sync + 'static {
     }
 
     fn sign(msg: &[u8], sk: &Self::SecretKey) -> Self::Signature;
+    /// Recover the signer's public key, `None` if the signature is invalid.
+    fn recover(msg: &[u8], signature: &[u8]) -> Option<Self::PublicKey>;
     fn sk2pk(sk: &Self::SecretKey) -> Self::PublicKey;
     fn pk2addr(pk: &Self::PublicKey) -> Address;
     #[allow(dead_code)]
@@ -93,7 +93,6 @@ fn secp256k1_sign(msg: &[u8], sk: &SecretKey) -> Signature {
     output
 }
 
-#[allow(unused)]
 pub fn secp256k1_recover(message: &[u8], signature: &Signature) -> Option<PublicKey> {
     let context = &SECP256K1;
     let rid =
@@ -138,6 +137,11 @@ impl Crypto for EthCrypto {
         secp256k1_sign(msg, sk)
     }
 
+    fn recover(msg: &[u8], signature: &[u8]) -> Option<Self::PublicKey> {
+        // The message is hashed before signing.
+        secp256k1_recover(&keccak_hash(msg), signature.try_into().ok()?)
+    }
+
     fn pk2addr(pk: &Self::PublicKey) -> Address {
         secp256k1_pk2addr(pk)
     }
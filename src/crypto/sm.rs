@@ -73,7 +73,6 @@ pub fn sm2_sign(msg: &[u8], sk: &SecretKey) -> Signature {
     sig_bytes
 }
 
-#[allow(unused)]
 pub fn sm2_recover_signature(msg: &[u8], signature: &Signature) -> Option<PublicKey> {
     let r = &signature[0..32];
     let s = &signature[32..64];
@@ -132,6 +131,10 @@ impl Crypto for SmCrypto {
         sm2_sign(msg, sk)
     }
 
+    fn recover(msg: &[u8], signature: &[u8]) -> Option<Self::PublicKey> {
+        sm2_recover_signature(msg, signature.try_into().ok()?)
+    }
+
     fn pk2addr(pk: &Self::PublicKey) -> Address {
         pk2addr(pk)
     }
@@ -16,7 +16,7 @@ use crate::core::cross_chain::CrossChainResultCode;
 use crate::{
     core::controller::{CompactBlockWithStaterootProof, ProofType, ProofWithValidators},
     crypto::{Address, Hash},
-    utils::{display_time, hex},
+    utils::{display_time, hex, parse_addr},
 };
 use cita_cloud_proto::blockchain::{BlockHeader, CompactBlock, CompactBlockBody};
 use cita_cloud_proto::common::ProposalInner;
@@ -38,6 +38,34 @@ use serde_json::map::Map;
 use serde_json::Value as Json;
 use tentacle_multiaddr::{Multiaddr, Protocol};
 
+// The fields that may hold an address of an account or a contract.
+const ADDR_FIELDS: [&str; 5] = ["to", "admin", "sender", "address", "contract_addr"];
+
+/// Add `<field>_name` for the known addresses in the json, the fields themselves are kept for scripts.
+pub fn annotate_addrs(json: &mut Json, name_of: &dyn Fn(&[u8]) -> Option<String>) {
+    match json {
+        Json::Object(obj) => {
+            let names: Vec<(String, String)> = ADDR_FIELDS
+                .iter()
+                .filter_map(|field| {
+                    let addr = parse_addr(obj.get(*field)?.as_str()?).ok()?;
+                    Some((format!("{field}_name"), name_of(&addr)?))
+                })
+                .collect();
+            for v in obj.values_mut() {
+                annotate_addrs(v, name_of);
+            }
+            obj.extend(names.into_iter().map(|(field, name)| (field, name.into())));
+        }
+        Json::Array(items) => {
+            for v in items {
+                annotate_addrs(v, name_of);
+            }
+        }
+        _ => (),
+    }
+}
+
 pub trait Display {
     fn to_json(&self) -> Json;
     fn display(&self) -> String {
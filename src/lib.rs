@@ -17,6 +17,7 @@ pub mod config;
 pub mod core;
 pub mod crypto;
 pub mod display;
+pub mod sdk;
 pub mod types;
 pub mod utils;
 
@@ -26,13 +26,20 @@ use rustyline::error::ReadlineError;
 use std::fs;
 
 use crate::{
-    config::{Config, CLOUD_CLI_DATA_DIR_NAME},
+    config::{data_dir, Config, ConfigLayer, CLOUD_CLI_DATA_DIR_NAME},
     core::{
-        context::Context, controller::ControllerClient, evm::EvmClient, executor::ExecutorClient,
-        wallet::Account, wallet::Wallet,
+        backend::BackendClient,
+        context::{AccountLocked, Context},
+        controller::ControllerClient,
+        endpoints::EndpointPool,
+        evm::EvmClient,
+        executor::ExecutorClient,
+        guard::TxGuard,
+        wallet::Account,
+        wallet::Wallet,
     },
     crypto::SmCrypto,
-    utils::init_local_utc_offset,
+    utils::{init_local_utc_offset, prompt_password},
 };
 
 #[macro_use]
@@ -42,10 +49,7 @@ fn main() -> Result<()> {
     // This should be called without any other concurrent running threads.
     init_local_utc_offset();
 
-    let data_dir = {
-        let home = home::home_dir().expect("cannot find home dir");
-        home.join(CLOUD_CLI_DATA_DIR_NAME)
-    };
+    let data_dir = data_dir();
     let is_init = !data_dir.exists();
     let is_legacy = data_dir.is_file();
 
@@ -92,8 +96,12 @@ fn main() -> Result<()> {
     }
 
     let config = Config::open(data_dir)?;
-    let mut ctx: Context<ControllerClient, ExecutorClient, EvmClient> =
-        Context::from_config(config)?;
+    let layers = ConfigLayer::discover(&std::env::current_dir()?)?;
+    let mut ctx: Context<
+        TxGuard<BackendClient<EndpointPool<ControllerClient>>>,
+        BackendClient<EndpointPool<ExecutorClient>>,
+        BackendClient<EndpointPool<EvmClient>>,
+    > = Context::from_config(config, &layers)?;
 
     if is_init {
         let default_account = Account::<SmCrypto>::generate();
@@ -109,6 +117,9 @@ fn main() -> Result<()> {
         if let Some(e) = e.downcast_ref::<clap::Error>() {
             e.exit();
         }
+        if let Some(status) = e.downcast_ref::<cmd::ExitStatus>() {
+            std::process::exit(status.0);
+        }
     })?;
 
     // Enter interactive mode if no subcommand provided
@@ -128,8 +139,21 @@ fn main() -> Result<()> {
                         }
                     };
                     let input = std::iter::once(cldi.get_name().into()).chain(args);
-                    if let Err(e) = cldi.exec_from(input, &mut ctx) {
-                        println!("{e:?}");
+                    let mut ret = cldi.exec_from(input.clone(), &mut ctx);
+                    // Ask for the password and retry if the command needs a locked account.
+                    if let Some(AccountLocked(account_name)) =
+                        ret.as_ref().err().and_then(|e| e.downcast_ref())
+                    {
+                        let account_name = account_name.clone();
+                        ret = prompt_password(&format!("Password of `{account_name}`: "), false)
+                            .and_then(|pw| ctx.wallet.unlock(&account_name, pw.as_bytes()))
+                            .and_then(|_| cldi.exec_from(input, &mut ctx));
+                    }
+                    if let Err(e) = ret {
+                        // The result has been printed by the command.
+                        if !e.is::<cmd::ExitStatus>() {
+                            println!("{e:?}");
+                        }
                     }
                 }
                 Err(ReadlineError::Eof) => break,
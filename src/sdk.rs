@@ -0,0 +1,543 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An async client for embedding cldi's functionalities in other crates.
+//!
+//! Unlike the cli [`Context`](crate::core::context::Context), it has no
+//! line editor or runtime of its own, and must be used inside a tokio runtime.
+//!
+//! ```no_run
+//! # async fn example() -> cldi::sdk::Result<()> {
+//! use cldi::{
+//!     core::wallet::{Account, MultiCryptoAccount},
+//!     crypto::SmCrypto,
+//!     sdk::{Client, TxOptions},
+//! };
+//!
+//! let account = MultiCryptoAccount::from(Account::<SmCrypto>::generate());
+//! let client = Client::connect("localhost:50004", "localhost:50002")
+//!     .await?
+//!     .with_signer(account);
+//! let tx_hash = client
+//!     .send_tx([0u8; 20], vec![], TxOptions::default())
+//!     .await?;
+//! let receipt = client
+//!     .wait_receipt(tx_hash, std::time::Duration::from_secs(30))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use cita_cloud_proto::{
+    blockchain::{Block, RawTransaction},
+    controller::SystemConfig,
+    evm::{Balance, BlockNumber, ByteCode, Nonce, Receipt},
+};
+
+use crate::{
+    config::ContextSetting,
+    core::{
+        admin::AdminBehaviour,
+        backend::BackendClient,
+        client::{ConnectOptions, GrpcClientBehaviour},
+        controller::{
+            ControllerBehaviour, ControllerClient, SignerBehaviour, TransactionSenderBehaviour,
+        },
+        endpoints::EndpointPool,
+        evm::{EvmBehaviour, EvmClient},
+        executor::{ExecutorBehaviour, ExecutorClient},
+        wallet::MultiCryptoAccount,
+    },
+    crypto::{Address, Hash},
+    utils::{get_block_height_at, hex, Position},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("rpc failed: {0}")]
+    Rpc(#[from] tonic::Status),
+    #[error("transport error: {0}")]
+    Transport(#[from] tonic::transport::Error),
+    /// Invalid endpoints or TLS settings.
+    #[error("invalid connection setting: {0}")]
+    InvalidSetting(String),
+    #[error("no signer is set for this client")]
+    NoSigner,
+    #[error("failed to sign: {0}")]
+    Sign(String),
+    /// The service returns something unexpected, e.g. a hash of the wrong length.
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+    #[error("timeout when waiting for the receipt of tx `{}`", hex(.0))]
+    Timeout(Hash),
+}
+
+impl Error {
+    /// Keep the tonic errors underlying `e` through anyhow's context,
+    /// and classify the others by `other`.
+    fn classify(e: anyhow::Error, other: fn(String) -> Self) -> Self {
+        let e = match e.downcast::<SignError>() {
+            Ok(SignError(e)) => return Self::Sign(format!("{e:#}")),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<tonic::Status>() {
+            Ok(status) => return Self::Rpc(status),
+            Err(e) => e,
+        };
+        match e.downcast::<tonic::transport::Error>() {
+            Ok(transport) => Self::Transport(transport),
+            Err(e) => other(format!("{e:#}")),
+        }
+    }
+
+    fn connect(e: anyhow::Error) -> Self {
+        Self::classify(e, Self::InvalidSetting)
+    }
+
+    fn response(e: anyhow::Error) -> Self {
+        Self::classify(e, Self::InvalidResponse)
+    }
+}
+
+/// Marks the errors of the signer, to tell them from the other errors of sending a tx.
+#[derive(Debug, thiserror::Error)]
+#[error("{0:#}")]
+struct SignError(anyhow::Error);
+
+struct MarkedSigner<'a, S>(&'a S);
+
+impl<S: SignerBehaviour> SignerBehaviour for MarkedSigner<'_, S> {
+    fn hash(&self, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.0.hash(msg).map_err(|e| SignError(e).into())
+    }
+
+    fn address(&self) -> &[u8] {
+        self.0.address()
+    }
+
+    fn sign(&self, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.0.sign(msg).map_err(|e| SignError(e).into())
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Options for sending a tx, the defaults are the same as the cli's.
+#[derive(Debug, Clone)]
+pub struct TxOptions {
+    pub value: [u8; 32],
+    pub quota: u64,
+    pub valid_until_block: Position,
+}
+
+impl Default for TxOptions {
+    fn default() -> Self {
+        Self {
+            value: [0; 32],
+            quota: 200_000,
+            valid_until_block: Position::FromCurrent(95),
+        }
+    }
+}
+
+// The same client stack as the cli's, so that the endpoints, retry and backend
+// of a context setting apply.
+pub type Controller = BackendClient<EndpointPool<ControllerClient>>;
+pub type Executor = BackendClient<EndpointPool<ExecutorClient>>;
+pub type Evm = BackendClient<EndpointPool<EvmClient>>;
+
+/// A client bundling the controller, executor and evm services of a chain,
+/// and an optional signer for sending txs.
+#[derive(Clone)]
+pub struct Client<S = MultiCryptoAccount> {
+    controller: Controller,
+    executor: Executor,
+    evm: Evm,
+    signer: Option<S>,
+}
+
+impl<S> Client<S> {
+    /// Connect to the services, the evm service is served by the executor.
+    pub async fn connect(controller_addr: &str, executor_addr: &str) -> Result<Self> {
+        let opts = ConnectOptions::default();
+        Ok(Self {
+            controller: Controller::connect(controller_addr, &opts)
+                .await
+                .map_err(Error::connect)?,
+            executor: Executor::connect(executor_addr, &opts)
+                .await
+                .map_err(Error::connect)?,
+            evm: Evm::connect(executor_addr, &opts)
+                .await
+                .map_err(Error::connect)?,
+            signer: None,
+        })
+    }
+
+    /// Connect lazily, which must be called within a tokio runtime.
+    pub fn connect_lazy(
+        controller_addr: &str,
+        executor_addr: &str,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let opts = ConnectOptions::default();
+        Ok(Self {
+            controller: Controller::connect_lazy(controller_addr, timeout, &opts)
+                .map_err(Error::connect)?,
+            executor: Executor::connect_lazy(executor_addr, timeout, &opts)
+                .map_err(Error::connect)?,
+            evm: Evm::connect_lazy(executor_addr, timeout, &opts).map_err(Error::connect)?,
+            signer: None,
+        })
+    }
+
+    /// Connect lazily by a context setting, with its backend, endpoints, retry, timeout
+    /// and TLS settings, the same as the cli.
+    pub fn from_setting(setting: &ContextSetting) -> Result<Self> {
+        let opts = ConnectOptions::from_setting(setting).map_err(Error::connect)?;
+        Ok(Self {
+            controller: Controller::connect_with_options(
+                &setting.controller_addrs(),
+                setting,
+                &opts,
+            )
+            .map_err(Error::connect)?,
+            executor: Executor::connect_with_options(&setting.executor_addrs(), setting, &opts)
+                .map_err(Error::connect)?,
+            evm: Evm::connect_with_options(&setting.evm_addrs(), setting, &opts)
+                .map_err(Error::connect)?,
+            signer: None,
+        })
+    }
+
+    pub fn with_signer(mut self, signer: S) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn signer(&self) -> Option<&S> {
+        self.signer.as_ref()
+    }
+
+    pub fn controller(&self) -> &Controller {
+        &self.controller
+    }
+
+    pub fn executor(&self) -> &Executor {
+        &self.executor
+    }
+
+    pub fn evm(&self) -> &Evm {
+        &self.evm
+    }
+
+    fn require_signer(&self) -> Result<&S> {
+        self.signer.as_ref().ok_or(Error::NoSigner)
+    }
+
+    pub async fn get_block_number(&self, for_pending: bool) -> Result<u64> {
+        self.controller
+            .get_block_number(for_pending)
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn get_block(&self, height: u64) -> Result<Block> {
+        self.controller
+            .get_block_detail_by_number(height)
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn get_system_config(&self) -> Result<SystemConfig> {
+        self.controller
+            .get_system_config()
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn get_tx(&self, tx_hash: Hash) -> Result<RawTransaction> {
+        self.controller
+            .get_tx(tx_hash)
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn get_receipt(&self, tx_hash: Hash) -> Result<Receipt> {
+        self.evm.get_receipt(tx_hash).await.map_err(Error::response)
+    }
+
+    pub async fn get_balance(&self, addr: Address, block_number: BlockNumber) -> Result<Balance> {
+        self.evm
+            .get_balance(addr, block_number)
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn get_code(&self, addr: Address, block_number: BlockNumber) -> Result<ByteCode> {
+        self.evm
+            .get_code(addr, block_number)
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn get_tx_count(&self, addr: Address, block_number: BlockNumber) -> Result<Nonce> {
+        self.evm
+            .get_tx_count(addr, block_number)
+            .await
+            .map_err(Error::response)
+    }
+
+    /// Call a contract without sending a tx. `height` 0 means the latest block.
+    pub async fn call(
+        &self,
+        from: Address,
+        to: Address,
+        data: Vec<u8>,
+        height: u64,
+    ) -> Result<Vec<u8>> {
+        self.executor
+            .call(from, to, data, height)
+            .await
+            .map_err(Error::response)
+            .map(|resp| resp.value)
+    }
+
+    /// Poll the receipt of the tx every second until it's available or timeout.
+    pub async fn wait_receipt(&self, tx_hash: Hash, timeout: Duration) -> Result<Receipt> {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            interval.tick().await;
+            // The receipt is not found until the tx is committed.
+            match self.get_receipt(tx_hash).await {
+                Ok(receipt) => return Ok(receipt),
+                Err(Error::Rpc(status)) if status.code() == tonic::Code::NotFound => (),
+                Err(e) => return Err(e),
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(Error::Timeout(tx_hash));
+            }
+        }
+    }
+}
+
+impl<S> Client<S>
+where
+    S: SignerBehaviour + Send + Sync,
+{
+    pub async fn send_tx(&self, to: Address, data: Vec<u8>, opts: TxOptions) -> Result<Hash> {
+        self.send(to.to_vec(), data, opts).await
+    }
+
+    pub async fn create_contract(&self, code: Vec<u8>, opts: TxOptions) -> Result<Hash> {
+        // Empty `to` for creating contract.
+        self.send(Vec::new(), code, opts).await
+    }
+
+    async fn send(&self, to: Vec<u8>, data: Vec<u8>, opts: TxOptions) -> Result<Hash> {
+        let signer = &MarkedSigner(self.require_signer()?);
+        let valid_until_block = get_block_height_at(&self.controller, opts.valid_until_block)
+            .await
+            .map_err(Error::response)?;
+        let tx_hash = self
+            .controller
+            .send_tx(
+                signer,
+                to,
+                data,
+                opts.value.to_vec(),
+                opts.quota,
+                valid_until_block,
+            )
+            .await
+            .map_err(Error::response)?;
+        Ok(tx_hash)
+    }
+
+    pub async fn update_admin(&self, new_admin_addr: Address) -> Result<Hash> {
+        let signer = &MarkedSigner(self.require_signer()?);
+        self.controller
+            .update_admin(signer, new_admin_addr)
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn set_block_interval(&self, block_interval: u32) -> Result<Hash> {
+        let signer = &MarkedSigner(self.require_signer()?);
+        self.controller
+            .set_block_interval(signer, block_interval)
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn update_validators(&self, validators: &[Vec<u8>]) -> Result<Hash> {
+        let signer = &MarkedSigner(self.require_signer()?);
+        self.controller
+            .update_validators(signer, validators)
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn emergency_brake(&self, switch: bool) -> Result<Hash> {
+        let signer = &MarkedSigner(self.require_signer()?);
+        self.controller
+            .emergency_brake(signer, switch)
+            .await
+            .map_err(Error::response)
+    }
+
+    pub async fn set_quota_limit(&self, quota_limit: u64) -> Result<Hash> {
+        let signer = &MarkedSigner(self.require_signer()?);
+        self.controller
+            .set_quota_limit(signer, quota_limit)
+            .await
+            .map_err(Error::response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context as _;
+    use cita_cloud_proto::blockchain::Transaction as CloudNormalTransaction;
+
+    struct FailingSigner;
+
+    impl SignerBehaviour for FailingSigner {
+        fn hash(&self, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+            Ok(msg.to_vec())
+        }
+
+        fn address(&self) -> &[u8] {
+            &[0; 20]
+        }
+
+        fn sign(&self, _msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+            anyhow::bail!("signer is offline")
+        }
+    }
+
+    #[test]
+    fn test_error_mapping() {
+        let e = anyhow::Error::from(tonic::Status::not_found("no receipt"))
+            .context("failed to get receipt");
+        assert!(matches!(Error::response(e), Error::Rpc(s) if s.code() == tonic::Code::NotFound));
+
+        let e = anyhow::anyhow!("invalid transaction hash");
+        assert!(
+            matches!(Error::response(e), Error::InvalidResponse(s) if s == "invalid transaction hash")
+        );
+        let e = anyhow::anyhow!("client cert and key must be provided together for mTLS");
+        assert!(matches!(Error::connect(e), Error::InvalidSetting(_)));
+
+        // Signing errors are told apart from the others of sending.
+        let e = MarkedSigner(&FailingSigner)
+            .sign_raw_tx(CloudNormalTransaction::default())
+            .context("failed to send raw")
+            .unwrap_err();
+        assert!(matches!(Error::response(e), Error::Sign(s) if s.contains("signer is offline")));
+    }
+
+    #[test]
+    fn test_from_setting() {
+        use crate::config::Backend;
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let setting = ContextSetting {
+                extra_controller_addrs: vec!["localhost:60004".into()],
+                extra_executor_addrs: vec!["localhost:60002".into()],
+                extra_evm_addrs: vec!["localhost:60002".into()],
+                ..Default::default()
+            };
+            let client: Client = Client::from_setting(&setting).unwrap();
+            assert!(matches!(client.controller(), BackendClient::CitaCloud(_)));
+
+            let setting = ContextSetting {
+                backend: Backend::Cita,
+                ..Default::default()
+            };
+            let client: Client = Client::from_setting(&setting).unwrap();
+            assert!(matches!(client.controller(), BackendClient::Cita(_)));
+            assert!(matches!(client.evm(), BackendClient::Cita(_)));
+        });
+    }
+
+    #[test]
+    fn test_from_setting_shares_token() {
+        use crate::config::{MetadataSetting, RetrySetting};
+
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let down = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let down_addr = down.local_addr().unwrap().to_string();
+            drop(down);
+
+            let setting = ContextSetting {
+                controller_addr: down_addr.clone(),
+                executor_addr: down_addr.clone(),
+                evm_addr: down_addr,
+                metadata: MetadataSetting {
+                    token_command: Some(format!("echo >> {}; echo token", runs.display())),
+                    ..Default::default()
+                },
+                retry: RetrySetting {
+                    max_attempts: 1,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let client: Client = Client::from_setting(&setting).unwrap();
+            // Each request waits for the token, which is resolved once for all the services.
+            assert!(client.controller().get_block_number(false).await.is_err());
+            assert!(client
+                .executor()
+                .call([0; 20], [0; 20], vec![], 0)
+                .await
+                .is_err());
+            assert!(client.evm().get_receipt([0; 32]).await.is_err());
+        });
+        assert_eq!(std::fs::read_to_string(&runs).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn test_wait_receipt_error() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let down = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let down_addr = down.local_addr().unwrap().to_string();
+            drop(down);
+
+            // Errors other than the receipt not found are returned instead of waited out.
+            let client: Client =
+                Client::connect_lazy(&down_addr, &down_addr, Duration::from_secs(1)).unwrap();
+            let e = tokio::time::timeout(
+                Duration::from_secs(30),
+                client.wait_receipt([0; 32], Duration::from_secs(60)),
+            )
+            .await
+            .expect("should not wait until timeout")
+            .unwrap_err();
+            assert!(
+                matches!(e, Error::Rpc(ref s) if s.code() == tonic::Code::Unavailable),
+                "{e}"
+            );
+        });
+    }
+}
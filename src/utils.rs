@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use cita_cloud_proto::evm::BlockNumber;
 use crossbeam::atomic::AtomicCell;
 use serde_json::{from_str, to_string};
@@ -140,6 +140,18 @@ pub fn parse_position(s: &str) -> Result<Position> {
     Ok(pos)
 }
 
+/// Parse `<height>` or `<begin>..<end>`(inclusive), where `begin` and `end`
+/// are block positions like `100`, `+1` or `-10`.
+pub fn parse_block_range(s: &str) -> Result<(Position, Position)> {
+    match s.split_once("..") {
+        Some((begin, end)) => Ok((parse_position(begin)?, parse_position(end)?)),
+        None => {
+            let pos = parse_position(s)?;
+            Ok((pos, pos))
+        }
+    }
+}
+
 pub async fn get_block_height_at<Co: ControllerBehaviour>(
     controller: &Co,
     pos: Position,
@@ -206,6 +218,24 @@ pub fn safe_save(path: impl AsRef<Path>, content: &[u8], overwrite_existing: boo
     Ok(())
 }
 
+/// Read a password from the file, without the trailing newline.
+pub fn read_password_file(path: &Path) -> Result<String> {
+    let pw = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read password file `{}`", path.display()))?;
+    Ok(pw.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Prompt for a password without echoing it, ask again to confirm if it's a new one.
+pub fn prompt_password(prompt: &str, confirm: bool) -> Result<String> {
+    let pw = rpassword::prompt_password(prompt).context("cannot read password")?;
+    if confirm {
+        let again =
+            rpassword::prompt_password("Confirm password: ").context("cannot read password")?;
+        ensure!(pw == again, "passwords don't match");
+    }
+    Ok(pw)
+}
+
 pub fn clap_about() -> String {
     let name = env!("CARGO_PKG_NAME").to_string();
     let version = env!("CARGO_PKG_VERSION");
@@ -240,4 +270,24 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_block_range() -> Result<()> {
+        assert_eq!(
+            parse_block_range("100")?,
+            (Position::Absolute(100), Position::Absolute(100))
+        );
+        assert_eq!(
+            parse_block_range("100..200")?,
+            (Position::Absolute(100), Position::Absolute(200))
+        );
+        assert_eq!(
+            parse_block_range("-10..+0")?,
+            (Position::ToCurrent(10), Position::FromCurrent(0))
+        );
+        assert!(parse_block_range("100..").is_err());
+        assert!(parse_block_range("a..b").is_err());
+
+        Ok(())
+    }
 }